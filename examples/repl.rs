@@ -0,0 +1,278 @@
+//! Interactive shell of a tiny language
+//!
+//! The shell keeps reading lines until the input is complete. Try for instance:
+//! ```text
+//! > print ("Hello " +
+//! .     "World")
+//! > if 1:
+//! .   print 2
+//! .
+//! ```
+//! Type `:history` to list the previous inputs, `!<number>` to run one of them again
+//! and `:quit` (or end of input) to exit.
+
+use std::io::{self, BufRead, Write};
+use heraclitus_compiler::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(f64),
+    Text(String)
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(number) => *number != 0.0,
+            Value::Text(text) => !text.is_empty()
+        }
+    }
+
+    fn add(self, other: Value) -> Value {
+        match (self, other) {
+            (Value::Number(left), Value::Number(right)) => Value::Number(left + right),
+            (left, right) => Value::Text(format!("{left}{right}"))
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{number}"),
+            Value::Text(text) => write!(f, "{text}")
+        }
+    }
+}
+
+// Expression: number, text or a sum in parentheses
+#[derive(Debug)]
+struct Expr {
+    value: Option<Value>
+}
+
+impl SyntaxModule<DefaultMetadata> for Expr {
+    syntax_name!("Expr");
+
+    fn new() -> Self {
+        Expr { value: None }
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        if let Ok(number) = number(meta, vec![]) {
+            self.value = Some(Value::Number(number.parse().unwrap_or_default()));
+            return Ok(())
+        }
+        if let Ok(text) = token_by(meta, |word| word.len() > 1 && word.starts_with('"') && word.ends_with('"')) {
+            self.value = Some(Value::Text(text[1..text.len() - 1].to_string()));
+            return Ok(())
+        }
+        token(meta, "(")?;
+        let mut value = None;
+        loop {
+            // New lines are allowed inside of the parentheses
+            while token_by(meta, |word| word.starts_with('\n')).is_ok() {}
+            let tok = meta.get_current_token();
+            let mut expr = Expr::new();
+            if syntax(meta, &mut expr).is_err() {
+                return error!(meta, tok, "Expected an expression")
+            }
+            value = match (value, expr.value) {
+                (Some(left), Some(right)) => Some(Value::add(left, right)),
+                (_, right) => right
+            };
+            while token_by(meta, |word| word.starts_with('\n')).is_ok() {}
+            if token(meta, "+").is_err() {
+                break
+            }
+        }
+        let tok = meta.get_current_token();
+        if token(meta, ")").is_err() {
+            return error!(meta, tok, "Expected ')'")
+        }
+        self.value = value;
+        Ok(())
+    }
+}
+
+// Statement: `print <expr>` or `if <expr>:` followed by an indented block
+#[derive(Debug)]
+enum Statement {
+    Print(Expr),
+    If(Expr, Vec<Statement>)
+}
+
+impl Statement {
+    fn run(&self) {
+        match self {
+            Statement::Print(expr) => println!("{}", expr.value.clone().unwrap()),
+            Statement::If(cond, block) => if cond.value.as_ref().is_some_and(Value::is_truthy) {
+                block.iter().for_each(Statement::run);
+            }
+        }
+    }
+}
+
+impl SyntaxModule<DefaultMetadata> for Statement {
+    syntax_name!("Statement");
+
+    fn new() -> Self {
+        Statement::Print(Expr::new())
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        let tok = meta.get_current_token();
+        if token(meta, "print").is_ok() {
+            let tok = meta.get_current_token();
+            let mut expr = Expr::new();
+            if syntax(meta, &mut expr).is_err() {
+                return error!(meta, tok, "Expected an expression")
+            }
+            *self = Statement::Print(expr);
+            return Ok(())
+        }
+        if token(meta, "if").is_ok() {
+            let mut cond = Expr::new();
+            syntax(meta, &mut cond)?;
+            token(meta, ":")?;
+            let mut block = Block::new();
+            syntax(meta, &mut block)?;
+            *self = Statement::If(cond, block.statements);
+            return Ok(())
+        }
+        error!(meta, tok, "Expected a statement")
+    }
+}
+
+// Block of statements that are indented more than the parent
+#[derive(Debug)]
+struct Block {
+    statements: Vec<Statement>
+}
+
+impl SyntaxModule<DefaultMetadata> for Block {
+    syntax_name!("Block");
+
+    fn new() -> Self {
+        Block { statements: vec![] }
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        let size = indent(meta)?;
+        if size == 0 {
+            return error!(meta, meta.get_current_token(), "Expected an indented block")
+        }
+        loop {
+            let mut statement = Statement::new();
+            syntax(meta, &mut statement)?;
+            self.statements.push(statement);
+            match indent_with(meta, size) {
+                Ok(std::cmp::Ordering::Equal) => continue,
+                // Leave the new line for the outer block
+                Ok(_) => meta.set_index(meta.get_index() - 1),
+                Err(_) => {}
+            }
+            return Ok(())
+        }
+    }
+}
+
+// Whole input of the shell
+struct Program {
+    statements: Vec<Statement>
+}
+
+impl SyntaxModule<DefaultMetadata> for Program {
+    syntax_name!("Program");
+
+    fn new() -> Self {
+        Program { statements: vec![] }
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        loop {
+            while token_by(meta, |word| word.starts_with('\n')).is_ok() {}
+            if meta.get_current_token().is_none() {
+                return Ok(())
+            }
+            let mut statement = Statement::new();
+            syntax(meta, &mut statement)?;
+            self.statements.push(statement);
+        }
+    }
+}
+
+fn rules() -> Rules {
+    Rules::new(vec!['(', ')', '+', ':'], vec![], reg![
+        reg!(text as "text" => {
+            begin: "\"",
+            end: "\""
+        })
+    ]).set_brackets(vec![('(', ')')])
+}
+
+fn prompt(text: &str) {
+    print!("{text}");
+    io::stdout().flush().unwrap();
+}
+
+fn main() {
+    let mut history: Vec<String> = vec![];
+    let mut input = String::new();
+    let mut lines = io::stdin().lock().lines();
+    prompt("> ");
+    while let Some(Ok(line)) = lines.next() {
+        // Commands are recognized only at the beginning of the input
+        if input.is_empty() {
+            match line.trim() {
+                "" => {
+                    prompt("> ");
+                    continue
+                }
+                ":quit" => break,
+                ":history" => {
+                    history.iter().enumerate().for_each(|(index, entry)| println!("{:>3}  {}", index + 1, entry.trim_end().replace('\n', "\n     ")));
+                    prompt("> ");
+                    continue
+                }
+                command if command.starts_with('!') => {
+                    match command[1..].parse::<usize>().ok().and_then(|index| history.get(index.wrapping_sub(1))) {
+                        Some(entry) => {
+                            println!("{}", entry.trim_end());
+                            input = entry.clone();
+                        }
+                        None => {
+                            println!("No such entry in the history");
+                            prompt("> ");
+                            continue
+                        }
+                    }
+                }
+                _ => input.push_str(&line)
+            }
+        } else {
+            input.push('\n');
+            input.push_str(&line);
+        }
+        let cc = Compiler::builder("Shell", rules())
+            .indent()
+            .source(&input)
+            .build()
+            .unwrap();
+        let mut program = Program::new();
+        match cc.compile_input(&mut program) {
+            InputStatus::Incomplete(_) => {
+                prompt(". ");
+                continue
+            }
+            InputStatus::Complete(_) => program.statements.iter().for_each(Statement::run),
+            InputStatus::Invalid(Failure::Loud(message)) => message.show(),
+            InputStatus::Invalid(Failure::Quiet(_)) => println!("Invalid input")
+        }
+        if history.last() != Some(&input) {
+            history.push(input.clone());
+        }
+        input.clear();
+        prompt("> ");
+    }
+}
@@ -0,0 +1,45 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use heraclitus_compiler::prelude::*;
+use heraclitus_compiler_fuzz::FuzzRules;
+
+// Module that consumes the tokens with patterns and reports errors at odd positions
+struct Program;
+
+impl SyntaxModule<DefaultMetadata> for Program {
+    fn new() -> Self {
+        Program
+    }
+
+    fn name() -> &'static str {
+        "Program"
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        while meta.get_current_token().is_some() {
+            let begin = meta.get_current_token();
+            if variable(meta, vec!['_']).is_err() && number(meta, vec![]).is_err() {
+                meta.increment_index();
+            }
+            if meta.get_index() % 7 == 0 {
+                let position = PositionInfo::from_between_tokens(meta, begin, meta.get_current_token());
+                return error_pos!(meta, position, "Unexpected token")
+            }
+        }
+        Ok(())
+    }
+}
+
+fuzz_target!(|input: (FuzzRules, String)| {
+    let (rules, code) = input;
+    let Some(compiler) = rules.compiler(&code) else {
+        return
+    };
+    let mut program = Program::new();
+    match compiler.compile(&mut program) {
+        Ok(compilation) => compilation.diagnostics.iter().for_each(Message::show),
+        Err(Failure::Loud(message)) => message.show(),
+        Err(Failure::Quiet(_)) => {}
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use heraclitus_compiler::prelude::*;
+use heraclitus_compiler_fuzz::{check_tokens, FuzzRules};
+
+// Code generated from the rules reaches deeper into the region tree than arbitrary text
+fuzz_target!(|input: (FuzzRules, u64, u8)| {
+    let (rules, seed, size) = input;
+    let code = SourceGenerator::new(&rules.rules(), seed).generate(size as usize);
+    let Some(compiler) = rules.compiler(&code) else {
+        return
+    };
+    check_tokens(&code, &compiler.tokenize());
+});
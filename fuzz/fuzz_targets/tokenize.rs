@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use heraclitus_compiler_fuzz::{check_tokens, FuzzRules};
+
+fuzz_target!(|input: (FuzzRules, String)| {
+    let (rules, code) = input;
+    let Some(compiler) = rules.compiler(&code) else {
+        return
+    };
+    let result = compiler.tokenize();
+    check_tokens(&code, &result);
+    let (tokens, errors) = compiler.tokenize_all();
+    if errors.is_empty() {
+        check_tokens(&code, &Ok(tokens));
+    }
+    let _ = compiler.tokenize_borrowed();
+});
@@ -0,0 +1,104 @@
+//! Arbitrary inputs shared by the fuzz targets
+
+use arbitrary::Arbitrary;
+use heraclitus_compiler::prelude::*;
+
+/// Region that can be generated from the fuzzer data
+#[derive(Debug, Arbitrary)]
+pub struct FuzzRegion {
+    begin: String,
+    end: String,
+    tokenize: bool,
+    singleline: bool,
+    allow_unclosed: bool,
+    // Index of the referenced region (the global region is the first one)
+    reference: Option<u8>,
+    children: Vec<FuzzRegion>
+}
+
+/// Lexing rules that can be generated from the fuzzer data
+#[derive(Debug, Arbitrary)]
+pub struct FuzzRules {
+    symbols: Vec<char>,
+    compounds: Vec<(char, char)>,
+    escape: char,
+    regions: Vec<FuzzRegion>,
+    indent: bool,
+    separator: Option<bool>
+}
+
+impl FuzzRules {
+    /// Create the rules of the compiler
+    pub fn rules(&self) -> Rules {
+        let mut count = 0;
+        let mut references = vec![];
+        let interp = build_regions(&self.regions, &mut count, &mut references);
+        let mut global = Region::new_global(interp);
+        // References can point only to the regions that exist
+        for (id, reference) in references {
+            let target = match reference as usize % (count + 1) {
+                0 => "global".to_string(),
+                index => format!("r{}", index - 1)
+            };
+            set_reference(&mut global, &id, target);
+        }
+        Rules::new(self.symbols.clone(), self.compounds.clone(), global).set_escape(self.escape)
+    }
+
+    /// Create the compiler of the code
+    pub fn compiler(&self, code: &str) -> Option<Compiler> {
+        let mut builder = Compiler::builder("Fuzz", self.rules()).source(code);
+        builder = match self.separator {
+            Some(true) => builder.separator(SeparatorMode::Automatic(";".to_string())),
+            Some(false) => builder.separator(SeparatorMode::SemiAutomatic(";".to_string())),
+            None => builder
+        };
+        if self.indent {
+            builder = builder.indent();
+        }
+        builder.build().ok()
+    }
+}
+
+fn build_regions(regions: &[FuzzRegion], count: &mut usize, references: &mut Vec<(String, u8)>) -> Vec<Region> {
+    let mut result = vec![];
+    for region in regions.iter() {
+        if region.begin.is_empty() || region.end.is_empty() {
+            continue
+        }
+        let id = format!("r{count}");
+        *count += 1;
+        let children = match region.reference {
+            Some(reference) => {
+                references.push((id.clone(), reference));
+                vec![]
+            }
+            None => build_regions(&region.children, count, references)
+        };
+        let mut built = Region::new(id.as_str(), id.as_str(), &region.begin, &region.end, children, None);
+        built.tokenize = region.tokenize;
+        built.singleline = region.singleline;
+        built.allow_unclosed_region = region.allow_unclosed;
+        result.push(built);
+    }
+    result
+}
+
+fn set_reference(region: &mut Region, id: &str, target: String) {
+    if region.id == id {
+        region.references = Some(target);
+        return
+    }
+    for child in region.interp.iter_mut() {
+        set_reference(child, id, target.clone());
+    }
+}
+
+/// Check the lexer invariants of the tokens if the code was tokenized
+pub fn check_tokens(code: &str, result: &Result<Vec<Token>, LexerError>) {
+    if let Ok(tokens) = result {
+        if let Err(error) = verify_tokens(code, tokens) {
+            panic!("{error}");
+        }
+    }
+}
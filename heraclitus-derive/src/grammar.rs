@@ -0,0 +1,345 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitInt, LitStr, Member, PathArguments, Result, Token, Type, Variant};
+
+// Presets that can be used to parse a field
+const PRESETS: [&str; 7] = ["variable", "alphabetic", "alphanumeric", "numeric", "integer", "float", "number"];
+
+// Item of the sequence set on the type or the variant
+enum Item {
+    Literal(LitStr),
+    Field(Member)
+}
+
+impl Parse for Item {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(LitStr) {
+            Ok(Item::Literal(input.parse()?))
+        } else if input.peek(LitInt) {
+            let index: LitInt = input.parse()?;
+            Ok(Item::Field(Member::Unnamed(syn::Index { index: index.base10_parse()?, span: index.span() })))
+        } else {
+            Ok(Item::Field(Member::Named(input.parse()?)))
+        }
+    }
+}
+
+// How the field is parsed
+enum Rule {
+    Module,
+    Preset(Ident, LitStr),
+    OneOf(Vec<LitStr>)
+}
+
+struct Field {
+    member: Member,
+    ty: Type,
+    rule: Rule,
+    separator: Option<LitStr>,
+    skip: bool
+}
+
+impl Field {
+    fn parse(member: Member, field: &syn::Field) -> Result<Self> {
+        let mut result = Field { member, ty: field.ty.clone(), rule: Rule::Module, separator: None, skip: false };
+        let mut extend = None;
+        for attr in grammar_attrs(&field.attrs).filter(|attr| matches!(attr.meta, syn::Meta::List(_))) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    result.skip = true;
+                } else if meta.path.is_ident("separator") {
+                    result.separator = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("extend") {
+                    extend = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("token") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let words = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                    result.rule = Rule::OneOf(words.into_iter().collect());
+                } else if let Some(preset) = PRESETS.iter().find(|preset| meta.path.is_ident(preset)) {
+                    result.rule = Rule::Preset(Ident::new(preset, Span::call_site()), LitStr::new("", Span::call_site()));
+                } else {
+                    let message = format!("Unknown grammar option. Expected one of: {}, token, separator, extend, skip", PRESETS.join(", "));
+                    return Err(meta.error(message))
+                }
+                Ok(())
+            })?;
+        }
+        match (&mut result.rule, extend) {
+            (Rule::Preset(_, chars), Some(extend)) => *chars = extend,
+            (_, Some(extend)) => return Err(Error::new_spanned(extend, "Only presets can be extended with characters")),
+            _ => {}
+        }
+        if let Rule::OneOf(words) = &result.rule {
+            if words.is_empty() {
+                return Err(Error::new_spanned(&field.ty, "At least one token has to be provided"))
+            }
+        }
+        Ok(result)
+    }
+
+    fn is_module(&self) -> bool {
+        matches!(self.rule, Rule::Module)
+    }
+}
+
+fn grammar_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("grammar"))
+}
+
+// Check if the type, its variants or fields use the grammar attributes
+pub fn is_grammar(input: &DeriveInput) -> bool {
+    let has_fields = |fields: &Fields| fields.iter().any(|field| grammar_attrs(&field.attrs).next().is_some());
+    grammar_attrs(&input.attrs).next().is_some() || match &input.data {
+        Data::Struct(data) => has_fields(&data.fields),
+        Data::Enum(data) => data.variants.iter().any(|variant| {
+            grammar_attrs(&variant.attrs).next().is_some() || has_fields(&variant.fields)
+        }),
+        Data::Union(_) => false
+    }
+}
+
+// Get the type wrapped in `Option`, `Vec` or `Box`
+fn unwrap_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first()? {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None
+    }
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("bool"))
+}
+
+pub struct Grammar<'a> {
+    krate: TokenStream,
+    metadata: &'a TokenStream
+}
+
+impl<'a> Grammar<'a> {
+    pub fn new(metadata: &'a TokenStream) -> Self {
+        Grammar { krate: quote!(::heraclitus_compiler::prelude), metadata }
+    }
+
+    // Parse the fields in the order given by the sequence or the declaration
+    fn sequence(&self, attrs: &[Attribute], fields: &Fields) -> Result<(Vec<Item>, Vec<Field>)> {
+        let fields = fields.iter().enumerate().map(|(index, field)| {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into())
+            };
+            Field::parse(member, field)
+        }).collect::<Result<Vec<_>>>()?;
+        let mut items = None;
+        for attr in grammar_attrs(attrs) {
+            if let syn::Meta::List(_) = attr.meta {
+                let list = attr.parse_args_with(Punctuated::<Item, Token![,]>::parse_terminated)?;
+                items = Some(list.into_iter().collect::<Vec<_>>());
+            }
+        }
+        let items = match items {
+            Some(items) => {
+                for item in items.iter() {
+                    if let Item::Field(member) = item {
+                        if !fields.iter().any(|field| &field.member == member) {
+                            return Err(Error::new_spanned(member, "Unknown field in the grammar sequence"))
+                        }
+                    }
+                }
+                items
+            }
+            None => fields.iter()
+                .filter(|field| !field.skip)
+                .map(|field| Item::Field(field.member.clone()))
+                .collect()
+        };
+        Ok((items, fields))
+    }
+
+    // Initial values of the fields
+    pub fn init(&self, attrs: &[Attribute], fields: &Fields) -> Result<Vec<TokenStream>> {
+        let (items, parsed) = self.sequence(attrs, fields)?;
+        let members = self.parsed_members(&items).collect::<Vec<_>>();
+        Ok(parsed.iter().map(|field| self.init_field(field, members.contains(&&field.member))).collect())
+    }
+
+    // Initial value of the field
+    //
+    // Child modules of the grammar are created with `SyntaxModule::new`, other fields with `Default`.
+    fn init_field(&self, field: &Field, is_parsed: bool) -> TokenStream {
+        let Grammar { krate, metadata } = self;
+        let ty = &field.ty;
+        if !is_parsed || !field.is_module() || unwrap_type(ty, "Option").is_some() || unwrap_type(ty, "Vec").is_some() {
+            return quote!(::std::default::Default::default())
+        }
+        match unwrap_type(ty, "Box") {
+            Some(inner) => quote!(Box::new(<#inner as #krate::SyntaxModule<#metadata>>::new())),
+            None => quote!(<#ty as #krate::SyntaxModule<#metadata>>::new())
+        }
+    }
+
+    // Expression that parses a single element of the field and its description
+    fn element(&self, field: &Field, ty: &Type) -> (TokenStream, TokenStream) {
+        let Grammar { krate, metadata } = self;
+        match &field.rule {
+            Rule::Module => match unwrap_type(ty, "Box") {
+                Some(inner) => (
+                    quote!(#krate::module::<#metadata, #inner>(meta).map(Box::new)),
+                    quote!(<#inner as #krate::SyntaxModule<#metadata>>::name())
+                ),
+                None => (
+                    quote!(#krate::module::<#metadata, #ty>(meta)),
+                    quote!(<#ty as #krate::SyntaxModule<#metadata>>::name())
+                )
+            },
+            Rule::Preset(preset, extend) => {
+                let chars = extend.value().chars().collect::<Vec<_>>();
+                let name = preset.to_string();
+                (quote!(#krate::#preset(meta, vec![#(#chars),*])), quote!(#name))
+            }
+            Rule::OneOf(words) => {
+                let expected = words.iter()
+                    .map(|word| format!("'{}'", word.value()))
+                    .collect::<Vec<_>>()
+                    .join(" or ");
+                (quote!(#krate::one_of(meta, &[#(#words),*])), quote!(#expected))
+            }
+        }
+    }
+
+    // Expression that parses the whole field and its description
+    fn field(&self, field: &Field) -> (TokenStream, TokenStream) {
+        let krate = &self.krate;
+        let ty = &field.ty;
+        if let Some(inner) = unwrap_type(ty, "Option") {
+            let (element, expected) = self.element(field, inner);
+            return (quote!(#krate::optional(meta, |meta| #element)), expected)
+        }
+        if let Some(inner) = unwrap_type(ty, "Vec") {
+            let (element, expected) = self.element(field, inner);
+            let separator = match &field.separator {
+                Some(separator) => quote!(Some(#separator)),
+                None => quote!(None)
+            };
+            return (quote!(#krate::repeat(meta, #separator, |meta| #element)), expected)
+        }
+        if is_bool(ty) && !field.is_module() {
+            let (element, expected) = self.element(field, ty);
+            return (quote!(#krate::optional(meta, |meta| #element).map(|value| value.is_some())), expected)
+        }
+        self.element(field, ty)
+    }
+
+    // Statements that parse the sequence into local variables
+    //
+    // Once the sequence consumed any token - failing to parse the rest of it is reported loudly.
+    fn parse_sequence(&self, items: &[Item], fields: &[Field]) -> TokenStream {
+        let Grammar { krate, metadata } = self;
+        let steps = items.iter().map(|item| {
+            let (parse, expected, local) = match item {
+                Item::Literal(word) => {
+                    let expected = format!("'{}'", word.value());
+                    (quote!(#krate::token(meta, #word)), quote!(#expected), format_ident!("_"))
+                }
+                Item::Field(member) => {
+                    let field = fields.iter().find(|field| &field.member == member).unwrap();
+                    let (parse, expected) = self.field(field);
+                    (parse, expected, local(member))
+                }
+            };
+            quote! {
+                let #local = {
+                    let parse = |meta: &mut #metadata| #parse;
+                    if #krate::Metadata::get_index(meta) > start {
+                        #krate::expect(meta, #expected, parse)?
+                    } else {
+                        parse(meta)?
+                    }
+                };
+            }
+        });
+        quote! {
+            let start = #krate::Metadata::get_index(meta);
+            #(#steps)*
+        }
+    }
+
+    fn parsed_members<'b>(&self, items: &'b [Item]) -> impl Iterator<Item = &'b Member> {
+        items.iter().filter_map(|item| match item {
+            Item::Field(member) => Some(member),
+            Item::Literal(_) => None
+        })
+    }
+
+    pub fn parse_struct(&self, input: &DeriveInput, fields: &Fields) -> Result<TokenStream> {
+        let (items, fields) = self.sequence(&input.attrs, fields)?;
+        let parse = self.parse_sequence(&items, &fields);
+        let members = self.parsed_members(&items);
+        let locals = self.parsed_members(&items).map(local);
+        Ok(quote! {
+            #parse
+            #(self.#members = #locals;)*
+            Ok(())
+        })
+    }
+
+    fn parse_variant(&self, variant: &Variant) -> Result<TokenStream> {
+        let krate = &self.krate;
+        let ident = &variant.ident;
+        let (items, fields) = self.sequence(&variant.attrs, &variant.fields)?;
+        let parse = self.parse_sequence(&items, &fields);
+        let values = fields.iter().map(|field| {
+            let value = match self.parsed_members(&items).any(|member| member == &field.member) {
+                true => {
+                    let local = local(&field.member);
+                    quote!(#local)
+                }
+                false => quote!(::std::default::Default::default())
+            };
+            let member = &field.member;
+            quote!(#member: #value)
+        });
+        Ok(quote! {
+            match #krate::attempt(meta, |meta| {
+                #parse
+                Ok(Self::#ident { #(#values),* })
+            }) {
+                Ok(value) => {
+                    *self = value;
+                    return Ok(())
+                }
+                Err(#krate::Failure::Quiet(position)) => failure = Some(#krate::Failure::Quiet(position)),
+                Err(failure) => return Err(failure)
+            }
+        })
+    }
+
+    pub fn parse_enum(&self, input: &DeriveInput, variants: &Punctuated<Variant, Token![,]>) -> Result<TokenStream> {
+        if variants.is_empty() {
+            return Err(Error::new_spanned(&input.ident, "Grammar enum needs at least one variant"))
+        }
+        let alternatives = variants.iter()
+            .map(|variant| self.parse_variant(variant))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(quote! {
+            let mut failure = None;
+            #(#alternatives)*
+            Err(failure.unwrap())
+        })
+    }
+}
+
+fn local(member: &Member) -> Ident {
+    match member {
+        Member::Named(ident) => format_ident!("field_{}", ident),
+        Member::Unnamed(index) => format_ident!("field_{}", index.index)
+    }
+}
@@ -0,0 +1,141 @@
+//! Derive macros for Heraclitus
+//!
+//! This crate removes the boilerplate of implementing `Metadata` and `SyntaxModule`.
+//! It is re-exported by `heraclitus-compiler` when the `derive` feature is enabled
+//! so that the macros are available through the prelude.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod grammar;
+mod metadata;
+mod syntax_module;
+
+/// Implement `Metadata` for a struct
+///
+/// Fields are matched with their role by the `#[metadata(...)]` attribute.
+/// Fields without an attribute are matched by their name.
+///
+/// | Role       | Type                   | Field name by default |
+/// |------------|------------------------|-----------------------|
+/// | `tokens`   | `Vec<Token>`           | `tokens` or `expr`    |
+/// | `index`    | `usize`                | `index`               |
+/// | `debug`    | `Option<usize>`        | `debug` or `indent`   |
+/// | `path`     | `Option<String>`       | `path`                |
+/// | `code`     | `Option<String>`       | `code`                |
+/// | `sources`  | `SourceMap`            | `sources`             |
+/// | `file`     | `Option<FileId>`       | `file`                |
+/// | `tracer`   | `Option<Tracer>`       | `tracer`              |
+/// | `loader`   | `Option<ModuleLoader>` | `loader`              |
+/// | `semantic` | `SemanticTokens`       | `semantic`            |
+/// | `scopes`   | `Scopes<T>`            | -                     |
+/// | `state`    | any `Clone` type       | -                     |
+///
+/// The `tokens`, `index` and `debug` fields are required. Fields marked with `state` are
+/// rolled back when the parsing attempt fails. Field marked with `scopes` implements `ScopedMetadata<T>`.
+/// The symbol table marked with `state` records its changes instead of being cloned on every attempt.
+/// All the other fields are initialized with `Default::default()`.
+/// Without the `code` field the code is stored only in the `sources`.
+/// # Example
+/// ```
+/// use heraclitus_compiler::prelude::*;
+/// use heraclitus_derive::Metadata;
+///
+/// #[derive(Metadata)]
+/// struct MyMetadata {
+///     index: usize,
+///     #[metadata(tokens)]
+///     lexem: Vec<Token>,
+///     debug: Option<usize>,
+///     #[metadata(scopes, state)]
+///     variables: Scopes<String>,
+///     mutable: bool
+/// }
+///
+/// let meta = MyMetadata::new(vec![], None, None);
+/// assert_eq!(meta.get_scopes().depth(), 1);
+/// ```
+#[proc_macro_derive(Metadata, attributes(metadata))]
+pub fn derive_metadata(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    metadata::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `SyntaxModule` for a type
+///
+/// The name of the module is the name of the type unless it's set with `#[syntax(name = "...")]`.
+/// New module is created by initializing all the fields with `Default::default()`
+/// (enums have to implement `Default` themselves).
+/// Parsing is delegated to the `parse_syntax` method of the type which can be changed
+/// with `#[syntax(parse = method)]`. Modules are parsed with `DefaultMetadata`
+/// unless other metadata is set with `#[syntax(metadata = Type)]`.
+/// # Example
+/// ```
+/// use heraclitus_compiler::prelude::*;
+/// use heraclitus_derive::SyntaxModule;
+///
+/// #[derive(SyntaxModule)]
+/// #[syntax(name = "Variable Declaration")]
+/// struct VarDecl {
+///     name: String
+/// }
+///
+/// impl VarDecl {
+///     fn parse_syntax(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+///         token(meta, "let")?;
+///         self.name = variable(meta, vec!['_'])?;
+///         Ok(())
+///     }
+/// }
+///
+/// assert_eq!(<VarDecl as SyntaxModule<DefaultMetadata>>::name(), "Variable Declaration");
+/// ```
+///
+/// # Grammar
+/// The `parse` method is generated when the type, its variants or fields use the `#[grammar(...)]` attribute.
+/// The attribute on a struct or a variant lists the sequence of literal tokens and fields
+/// (tuple fields are referred to by their index). Without the sequence the fields are parsed
+/// in the order of declaration. A bare `#[grammar]` attribute only enables the generated parser.
+///
+/// Fields are parsed as child modules unless the attribute on the field says otherwise:
+/// - `variable`, `alphabetic`, `alphanumeric`, `numeric`, `integer`, `float` or `number` parses the field with a preset
+///   (`extend = "_"` adds characters to the preset)
+/// - `token("+", "-")` matches one of the tokens
+/// - `separator = ","` separates the elements of a `Vec` field
+/// - `skip` leaves the field out of the grammar
+///
+/// `Option` fields are optional, `Vec` fields are repeated and `bool` fields tell whether the token was matched.
+/// `Box` fields parse the boxed module. Enum variants are tried in order as alternatives
+/// and a new enum module starts as the first variant.
+///
+/// Once the sequence consumed a token - the rest of it is required. Failing to parse it
+/// is reported loudly with a message that tells what was expected.
+/// # Example
+/// ```
+/// use heraclitus_compiler::prelude::*;
+/// use heraclitus_derive::SyntaxModule;
+///
+/// #[derive(SyntaxModule)]
+/// enum Value {
+///     Number(#[grammar(number)] String),
+///     #[grammar("[", 0, "]")]
+///     List(#[grammar(separator = ",")] Vec<Value>)
+/// }
+///
+/// #[derive(SyntaxModule)]
+/// #[grammar("let", name, "=", value)]
+/// struct VarDecl {
+///     #[grammar(variable, extend = "_")]
+///     name: String,
+///     value: Value
+/// }
+/// ```
+#[proc_macro_derive(SyntaxModule, attributes(syntax, grammar))]
+pub fn derive_syntax_module(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    syntax_module::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,339 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, GenericArgument, Ident, PathArguments, Result, Type};
+
+// Roles that can be assigned to at most one field
+const ROLES: [&str; 11] = ["tokens", "index", "debug", "path", "code", "sources", "file", "tracer", "loader", "semantic", "scopes"];
+
+// Names of the fields that take the role without an attribute
+const DEFAULT_NAMES: [(&str, &str); 12] = [
+    ("tokens", "tokens"),
+    ("expr", "tokens"),
+    ("index", "index"),
+    ("debug", "debug"),
+    ("indent", "debug"),
+    ("path", "path"),
+    ("code", "code"),
+    ("sources", "sources"),
+    ("file", "file"),
+    ("tracer", "tracer"),
+    ("loader", "loader"),
+    ("semantic", "semantic")
+];
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    roles: Vec<String>,
+    state: bool
+}
+
+struct Roles {
+    fields: Vec<Field>
+}
+
+impl Roles {
+    fn parse(input: &DeriveInput) -> Result<Self> {
+        let fields = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => &fields.named,
+                _ => return Err(Error::new_spanned(&input.ident, "Metadata can only be derived for structs with named fields"))
+            },
+            _ => return Err(Error::new_spanned(&input.ident, "Metadata can only be derived for structs"))
+        };
+        let mut roles = Roles { fields: Vec::new() };
+        let mut attributed = Vec::new();
+        for field in fields {
+            let ident = field.ident.clone().unwrap();
+            let mut result = Field { ident, ty: field.ty.clone(), roles: Vec::new(), state: false };
+            for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("metadata")) {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("state") {
+                        result.state = true;
+                        return Ok(())
+                    }
+                    match ROLES.iter().find(|role| meta.path.is_ident(role)) {
+                        Some(role) if roles.get(role).is_some() || result.roles.iter().any(|name| name == role) => {
+                            Err(meta.error(format!("Role '{role}' is already assigned to another field")))
+                        }
+                        Some(role) => {
+                            result.roles.push(role.to_string());
+                            Ok(())
+                        }
+                        None => Err(meta.error(format!("Unknown metadata role. Expected one of: {}, state", ROLES.join(", "))))
+                    }
+                })?;
+                attributed.push(result.ident.clone());
+            }
+            roles.fields.push(result);
+        }
+        // Match the remaining roles by the field names
+        for (name, role) in DEFAULT_NAMES {
+            if roles.get(role).is_some() {
+                continue
+            }
+            if let Some(field) = roles.fields.iter_mut().find(|field| field.ident == name && !attributed.contains(&field.ident)) {
+                field.roles.push(role.to_string());
+            }
+        }
+        for role in ["tokens", "index", "debug"] {
+            if roles.get(role).is_none() {
+                let message = format!("Missing field for '{role}'. Mark the field with #[metadata({role})]");
+                return Err(Error::new_spanned(&input.ident, message))
+            }
+        }
+        if roles.get("sources").is_some() != roles.get("file").is_some() {
+            return Err(Error::new_spanned(&input.ident, "Roles 'sources' and 'file' have to be used together"))
+        }
+        Ok(roles)
+    }
+
+    fn get(&self, role: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.roles.iter().any(|name| name == role))
+    }
+
+    fn ident(&self, role: &str) -> Option<&Ident> {
+        self.get(role).map(|field| &field.ident)
+    }
+}
+
+// Get the type of symbols stored in `Scopes<T>`
+fn scope_data(ty: &Type) -> Result<&Type> {
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                if let Some(GenericArgument::Type(data)) = args.args.first() {
+                    return Ok(data)
+                }
+            }
+        }
+    }
+    Err(Error::new_spanned(ty, "Field with the 'scopes' role has to be of type Scopes<T>"))
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let roles = Roles::parse(&input)?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let krate = quote!(::heraclitus_compiler::prelude);
+    let tokens = roles.ident("tokens").unwrap();
+    let index = roles.ident("index").unwrap();
+    let debug = roles.ident("debug").unwrap();
+    // Constructor
+    let sources = roles.ident("sources");
+    let path_arg = if roles.get("path").is_some() || sources.is_some() { format_ident!("path") } else { format_ident!("_path") };
+    let code_arg = if roles.get("code").is_some() || sources.is_some() { format_ident!("code") } else { format_ident!("_code") };
+    let init_sources = sources.map(|_| quote! {
+        let mut sources = #krate::SourceMap::new();
+        let file = code.as_ref().map(|code| sources.add(path.clone(), code.clone()));
+    });
+    let init_fields = roles.fields.iter().map(|field| {
+        let ident = &field.ident;
+        let value = match field.roles.first().map(String::as_str) {
+            Some("tokens") => quote!(tokens),
+            Some("index") => quote!(0),
+            Some("path") => quote!(path),
+            Some("code") => quote!(code),
+            Some("sources") => quote!(sources),
+            Some("file") => quote!(file),
+            _ => quote!(::std::default::Default::default())
+        };
+        quote!(#ident: #value)
+    });
+    // Optional getters and setters
+    let path = roles.ident("path")
+        .map(|path| quote!(self.#path.clone()))
+        .unwrap_or(quote!(None));
+    // Without the code field the code is stored only in the sources
+    let code = match (roles.ident("code"), roles.ident("file").and(sources)) {
+        (Some(code), _) => quote!(self.#code.as_ref()),
+        (None, Some(_)) => quote!(#krate::Metadata::get_source(self).map(|source| source.code())),
+        (None, None) => quote!(None)
+    };
+    let tracer = roles.ident("tracer").map(|tracer| quote! {
+        fn get_tracer(&mut self) -> Option<&mut #krate::Tracer> {
+            self.#tracer.as_mut()
+        }
+
+        fn set_tracer(&mut self, tracer: #krate::Tracer) {
+            self.#tracer = Some(tracer)
+        }
+
+        fn take_tracer(&mut self) -> Option<#krate::Tracer> {
+            self.#tracer.take()
+        }
+    });
+    let source_map = roles.ident("file").map(|file| {
+        let sources = sources.unwrap();
+        quote! {
+            fn get_sources(&self) -> Option<&#krate::SourceMap> {
+                Some(&self.#sources)
+            }
+
+            fn get_file(&self) -> Option<#krate::FileId> {
+                self.#file
+            }
+
+            fn set_sources(&mut self, sources: #krate::SourceMap, file: #krate::FileId) {
+                self.#sources = sources;
+                self.#file = Some(file);
+            }
+        }
+    });
+    let loader = roles.ident("loader").map(|loader| quote! {
+        fn get_trace(&self) -> Vec<#krate::PositionInfo> {
+            self.#loader.as_ref()
+                .map(|loader| loader.get_chain())
+                .unwrap_or_default()
+        }
+
+        fn take_loader(&mut self) -> Option<#krate::ModuleLoader> {
+            self.#loader.take()
+        }
+
+        fn set_loader(&mut self, loader: #krate::ModuleLoader) {
+            self.#loader = Some(loader)
+        }
+    });
+    let semantic = roles.ident("semantic").map(|semantic| quote! {
+        fn get_semantic_tokens(&self) -> Option<&#krate::SemanticTokens> {
+            Some(&self.#semantic)
+        }
+
+        fn get_semantic_tokens_mut(&mut self) -> Option<&mut #krate::SemanticTokens> {
+            Some(&mut self.#semantic)
+        }
+    });
+    // State that is rolled back on failed attempts
+    // (symbol table records its changes instead of being cloned)
+    let state = roles.fields.iter().filter(|field| field.state).collect::<Vec<_>>();
+    let state = (!state.is_empty()).then(|| {
+        let is_scopes = |field: &Field| field.roles.iter().any(|role| role == "scopes");
+        let types = state.iter().map(|field| {
+            let ty = &field.ty;
+            match is_scopes(field) {
+                true => quote!(#krate::ScopesCheckpoint),
+                false => quote!(#ty)
+            }
+        }).collect::<Vec<_>>();
+        let values = state.iter().map(|field| format_ident!("__{}", field.ident)).collect::<Vec<_>>();
+        let snapshots = state.iter().map(|field| {
+            let ident = &field.ident;
+            match is_scopes(field) {
+                true => quote!(self.#ident.checkpoint()),
+                false => quote!(self.#ident.clone())
+            }
+        });
+        let restores = state.iter().zip(values.iter()).map(|(field, value)| {
+            let ident = &field.ident;
+            match is_scopes(field) {
+                true => quote!(self.#ident.rollback(#value)),
+                false => quote!(self.#ident = #value)
+            }
+        });
+        let commits = state.iter().filter(|field| is_scopes(field)).map(|field| {
+            let ident = &field.ident;
+            let value = format_ident!("__{}", field.ident);
+            quote!(self.#ident.commit(#value))
+        });
+        let committed = state.iter().zip(values.iter()).map(|(field, value)| match is_scopes(field) {
+            true => quote!(#value),
+            false => quote!(_)
+        });
+        quote! {
+            fn snapshot_state(&mut self) -> Option<Box<dyn ::std::any::Any>> {
+                Some(Box::new((#(#snapshots,)*)))
+            }
+
+            fn restore_state(&mut self, state: Box<dyn ::std::any::Any>) {
+                if let Ok(state) = state.downcast::<(#(#types,)*)>() {
+                    let (#(#values,)*) = *state;
+                    #(#restores;)*
+                }
+            }
+
+            fn commit_state(&mut self, state: Box<dyn ::std::any::Any>) {
+                if let Ok(state) = state.downcast::<(#(#types,)*)>() {
+                    let (#(#committed,)*) = *state;
+                    #(#commits;)*
+                }
+            }
+        }
+    });
+    let scopes = match roles.get("scopes") {
+        Some(field) => {
+            let ident = &field.ident;
+            let data = scope_data(&field.ty)?;
+            Some(quote! {
+                impl #impl_generics #krate::ScopedMetadata<#data> for #name #ty_generics #where_clause {
+                    fn get_scopes(&self) -> &#krate::Scopes<#data> {
+                        &self.#ident
+                    }
+
+                    fn get_scopes_mut(&mut self) -> &mut #krate::Scopes<#data> {
+                        &mut self.#ident
+                    }
+                }
+            })
+        }
+        None => None
+    };
+    Ok(quote! {
+        impl #impl_generics #krate::Metadata for #name #ty_generics #where_clause {
+            fn new(tokens: Vec<#krate::Token>, #path_arg: Option<String>, #code_arg: Option<String>) -> Self {
+                #init_sources
+                #name {
+                    #(#init_fields,)*
+                }
+            }
+
+            fn get_token_at(&self, index: usize) -> Option<#krate::Token> {
+                self.#tokens.get(index).cloned()
+            }
+
+            fn get_tokens(&self) -> Option<&[#krate::Token]> {
+                Some(&self.#tokens)
+            }
+
+            fn get_word_at(&self, index: usize) -> Option<::std::borrow::Cow<'_, str>> {
+                self.#tokens.get(index).map(|token| ::std::borrow::Cow::Borrowed(token.word.as_str()))
+            }
+
+            fn get_span_at(&self, index: usize) -> Option<#krate::Span> {
+                self.#tokens.get(index).map(|token| token.span)
+            }
+
+            fn get_index(&self) -> usize {
+                self.#index
+            }
+
+            fn set_index(&mut self, index: usize) {
+                self.#index = index
+            }
+
+            fn get_debug(&mut self) -> Option<usize> {
+                self.#debug
+            }
+
+            fn set_debug(&mut self, indent: usize) {
+                self.#debug = Some(indent)
+            }
+
+            fn get_path(&self) -> Option<String> {
+                #path
+            }
+
+            fn get_code(&self) -> Option<&String> {
+                #code
+            }
+
+            #tracer
+            #source_map
+            #loader
+            #semantic
+            #state
+        }
+
+        #scopes
+    })
+}
@@ -0,0 +1,115 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, LitStr, Result, Type};
+use crate::grammar::{is_grammar, Grammar};
+
+struct Options {
+    name: Option<LitStr>,
+    metadata: Option<Type>,
+    parse: Option<Ident>
+}
+
+impl Options {
+    fn parse(input: &DeriveInput) -> Result<Self> {
+        let mut options = Options { name: None, metadata: None, parse: None };
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("syntax")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    options.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("metadata") {
+                    options.metadata = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("parse") {
+                    options.parse = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("Unknown syntax option. Expected one of: name, metadata, parse"))
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let options = Options::parse(&input)?;
+    let ident = &input.ident;
+    let krate = quote!(::heraclitus_compiler::prelude);
+    let name = options.name.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+    let metadata = options.metadata
+        .map(|metadata| quote!(#metadata))
+        .unwrap_or(quote!(#krate::DefaultMetadata));
+    let default = quote!(::std::default::Default::default());
+    if is_grammar(&input) {
+        if let Some(parse) = options.parse {
+            return Err(Error::new_spanned(parse, "Grammar attributes generate the parse method themselves"))
+        }
+        let grammar = Grammar::new(&metadata);
+        let (new, parse) = match &input.data {
+            Data::Struct(data) => (construct(quote!(#ident), &input.attrs, &data.fields, &grammar)?, grammar.parse_struct(&input, &data.fields)?),
+            Data::Enum(data) => {
+                // New module starts as the first alternative
+                let new = match data.variants.first() {
+                    Some(variant) => {
+                        let variant_ident = &variant.ident;
+                        construct(quote!(#ident::#variant_ident), &variant.attrs, &variant.fields, &grammar)?
+                    }
+                    None => default
+                };
+                (new, grammar.parse_enum(&input, &data.variants)?)
+            }
+            Data::Union(_) => return Err(Error::new_spanned(ident, "Grammar cannot be derived for unions"))
+        };
+        return Ok(implement(&input, &name, &metadata, new, parse))
+    }
+    let parse = options.parse.unwrap_or_else(|| Ident::new("parse_syntax", ident.span()));
+    let new = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                let fields = fields.named.iter().map(|field| field.ident.as_ref().unwrap());
+                quote!(#ident { #(#fields: #default),* })
+            }
+            Fields::Unnamed(fields) => {
+                let fields = fields.unnamed.iter().map(|_| &default);
+                quote!(#ident(#(#fields),*))
+            }
+            Fields::Unit => quote!(#ident)
+        },
+        // Enums and unions have to choose their default value themselves
+        _ => default.clone()
+    };
+    Ok(implement(&input, &name, &metadata, new, quote!(Self::#parse(self, meta))))
+}
+
+// Constructor that initializes the fields of grammar
+fn construct(path: TokenStream, attrs: &[Attribute], fields: &Fields, grammar: &Grammar) -> Result<TokenStream> {
+    let values = grammar.init(attrs, fields)?;
+    Ok(match fields {
+        Fields::Named(fields) => {
+            let fields = fields.named.iter().map(|field| field.ident.as_ref().unwrap());
+            quote!(#path { #(#fields: #values),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#values),*)),
+        Fields::Unit => path
+    })
+}
+
+fn implement(input: &DeriveInput, name: &LitStr, metadata: &TokenStream, new: TokenStream, parse: TokenStream) -> TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let krate = quote!(::heraclitus_compiler::prelude);
+    quote! {
+        impl #impl_generics #krate::SyntaxModule<#metadata> for #ident #ty_generics #where_clause {
+            fn new() -> Self {
+                #new
+            }
+
+            fn name() -> &'static str {
+                #name
+            }
+
+            fn parse(&mut self, meta: &mut #metadata) -> #krate::SyntaxResult {
+                #parse
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt::{self, Display, Formatter};
+use crate::compiling_rules::Rules;
+use crate::compiling::{Compiler, SeparatorMode, ScopingMode, TraceFormat, LexerDiagnosticHook};
+use crate::compiling::failing::message::Message;
+use crate::compiling::failing::failure::Failure;
+use super::lexer::Lexer;
+
+/// Error that can occur while setting up the compiler
+#[derive(Debug)]
+pub enum CompilerError {
+    /// Source file could not be read
+    Io {
+        /// Path to the file that was supposed to be read
+        path: String,
+        /// Underlying error
+        error: std::io::Error
+    },
+    /// Compiler was built without any source code
+    MissingSource
+}
+
+impl Display for CompilerError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            CompilerError::Io { path, error } => write!(formatter, "Could not read file '{path}': {error}"),
+            CompilerError::MissingSource => write!(formatter, "No source code was supplied to the compiler")
+        }
+    }
+}
+
+impl std::error::Error for CompilerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompilerError::Io { error, .. } => Some(error),
+            CompilerError::MissingSource => None
+        }
+    }
+}
+
+impl From<CompilerError> for Failure {
+    fn from(error: CompilerError) -> Self {
+        Failure::Loud(Message::new_err_msg(error.to_string()))
+    }
+}
+
+/// Builder of the compilation session
+///
+/// Builder is created with `Compiler::builder` and configures the language settings
+/// and the source code that will be compiled.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compiler() -> Result<(), CompilerError> {
+/// # let rules = Rules::new(vec![], vec![], reg![]);
+/// let cc = Compiler::builder("HerbScript", rules)
+///     .indent()
+///     .separator(SeparatorMode::Automatic("\\".to_string()))
+///     .source("let a = 12")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompilerBuilder {
+    name: String,
+    code: Option<String>,
+    path: Option<String>,
+    debug: bool,
+    trace: Option<(String, TraceFormat)>,
+    profile: bool,
+    tolerant: bool,
+    lexer_hook: Option<LexerDiagnosticHook>,
+    lexer: Lexer
+}
+
+impl CompilerBuilder {
+    /// Create a new builder with provided rules of your language
+    pub fn new<T: AsRef<str>>(name: T, rules: Rules) -> Self {
+        CompilerBuilder {
+            name: name.as_ref().to_string(),
+            code: None,
+            path: None,
+            debug: false,
+            trace: None,
+            profile: false,
+            tolerant: false,
+            lexer_hook: None,
+            lexer: Lexer::new(rules)
+        }
+    }
+
+    // Continue configuring already built compiler
+    pub(crate) fn from_compiler(compiler: Compiler) -> Self {
+        CompilerBuilder {
+            name: compiler.name,
+            code: Some(compiler.code),
+            path: compiler.path,
+            debug: compiler.debug,
+            trace: compiler.trace,
+            profile: compiler.profile,
+            tolerant: compiler.tolerant,
+            lexer_hook: compiler.lexer_hook,
+            lexer: compiler.lexer
+        }
+    }
+
+    /// Set the language to use indentations
+    pub fn indent(mut self) -> Self {
+        self.lexer.scoping_mode = ScopingMode::Indent;
+        self
+    }
+
+    /// Set the language separator mode
+    pub fn separator(mut self, mode: SeparatorMode) -> Self {
+        self.lexer.separator_mode = mode;
+        self
+    }
+
+    /// Compile given code
+    pub fn source<T: AsRef<str>>(mut self, code: T) -> Self {
+        self.code = Some(code.as_ref().to_string());
+        self
+    }
+
+    /// Set the path that is reported for the source code
+    ///
+    /// Imported modules are resolved relative to this path.
+    pub fn path<T: AsRef<str>>(mut self, path: T) -> Self {
+        self.path = Some(path.as_ref().to_string());
+        self
+    }
+
+    /// Compile the code read from given file
+    pub fn source_file<T: AsRef<str>>(self, path: T) -> Result<Self, CompilerError> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(code) => Ok(self.source(code).path(path)),
+            Err(error) => Err(CompilerError::Io { path: path.to_string(), error })
+        }
+    }
+
+    /// Parser will display information about the call stack
+    ///
+    /// This also records a structured trace that can be retrieved with `Metadata::get_tracer`
+    pub fn debug(mut self) -> Self {
+        self.debug = true;
+        self
+    }
+
+    /// Parser will record a structured trace and write it to a file in given format
+    ///
+    /// Tracing can also be enabled without recompiling by setting
+    /// the `HERACLITUS_TRACE` environment variable to a path of the output file.
+    pub fn trace<T: AsRef<str>>(mut self, path: T, format: TraceFormat) -> Self {
+        self.trace = Some((path.as_ref().to_string(), format));
+        self
+    }
+
+    /// Parser will collect per module statistics that are returned in `Compilation::profile`
+    ///
+    /// Profiling can also be enabled without recompiling by setting
+    /// the `HERACLITUS_PROFILE` environment variable. The report is then written to the standard error.
+    /// Profiling requires metadata that records the parser trace (see `Metadata::get_tracer`).
+    pub fn profile(mut self) -> Self {
+        self.profile = true;
+        self
+    }
+
+    /// Lexer will report the errors in `Compilation::diagnostics` and keep going
+    ///
+    /// Parser then receives all the tokens the lexer could recognize.
+    pub fn tolerant(mut self) -> Self {
+        self.tolerant = true;
+        self
+    }
+
+    /// Customise the diagnostics reported for lexer errors
+    pub fn lexer_diagnostics(mut self, hook: LexerDiagnosticHook) -> Self {
+        self.lexer_hook = Some(hook);
+        self
+    }
+
+    /// Create the compiler
+    pub fn build(mut self) -> Result<Compiler, CompilerError> {
+        let code = self.code.ok_or(CompilerError::MissingSource)?;
+        self.lexer.path = self.path.clone();
+        Ok(Compiler {
+            name: self.name,
+            code,
+            path: self.path,
+            debug: self.debug,
+            trace: self.trace,
+            profile: self.profile,
+            tolerant: self.tolerant,
+            lexer_hook: self.lexer_hook,
+            lexer: self.lexer
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::compiling::{Compiler, CompilerError};
+    use crate::compiling::failing::failure::Failure;
+    use crate::compiling_rules::{Region, Rules};
+    use crate::reg;
+
+    #[test]
+    fn missing_source() {
+        let result = Compiler::builder("Test", Rules::new(vec![], vec![], reg![])).build();
+        assert!(matches!(result, Err(CompilerError::MissingSource)));
+    }
+
+    #[test]
+    fn missing_file() {
+        let result = Compiler::builder("Test", Rules::new(vec![], vec![], reg![]))
+            .source_file("/nonexistent/heraclitus/main.ab");
+        let Err(error) = result else {
+            panic!("Expected the file to be missing")
+        };
+        assert!(matches!(&error, CompilerError::Io { path, .. } if path == "/nonexistent/heraclitus/main.ab"));
+        assert!(Failure::from(error).is_loud());
+    }
+
+    #[test]
+    fn build_session() {
+        let compiler = Compiler::builder("Test", Rules::new(vec![], vec![], reg![]))
+            .indent()
+            .source("apple banana")
+            .path("main.ab")
+            .build()
+            .unwrap();
+        assert_eq!(compiler.code, "apple banana");
+        assert_eq!(compiler.path, Some("main.ab".to_string()));
+        assert_eq!(compiler.tokenize().unwrap().len(), 2);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_setters() {
+        let mut compiler = Compiler::new("Test", Rules::new(vec![], vec![], reg![]));
+        compiler.use_indents();
+        compiler.load("apple\n  banana");
+        compiler.set_path("main.ab".to_string());
+        compiler.debug();
+        let builder = Compiler::builder("Test", Rules::new(vec![], vec![], reg![]))
+            .indent()
+            .source("apple\n  banana")
+            .path("main.ab")
+            .debug();
+        assert_eq!(compiler, builder.build().unwrap());
+        assert!(compiler.clone().load_file("/nonexistent/heraclitus/main.ab".to_string()).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Column units
+//!
+//! Positions are reported in characters by default, but editors and terminals count columns differently.
+//! Language servers usually expect UTF-16 code units while terminals need the display width of the text.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Unit in which the columns of a line are counted
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let line = "\tlet 🦀 = '日本'";
+/// assert_eq!(ColumnUnit::Chars.measure(line), 13);
+/// assert_eq!(ColumnUnit::Utf16.measure(line), 14);
+/// assert_eq!(ColumnUnit::Display { tab_width: 4 }.measure(line), 19);
+/// assert_eq!(ColumnUnit::Display { tab_width: 4 }.offset(line, 10), Some(5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ColumnUnit {
+    /// Bytes of the UTF-8 encoding
+    Bytes,
+    /// Unicode scalar values
+    #[default]
+    Chars,
+    /// Code units of the UTF-16 encoding (used by the Language Server Protocol)
+    Utf16,
+    /// Extended grapheme clusters (characters as perceived by the user)
+    Graphemes,
+    /// Width of the text in the terminal. Tabs advance to the next tab stop.
+    Display {
+        /// Distance between the tab stops
+        tab_width: usize
+    }
+}
+
+impl ColumnUnit {
+    /// Measure the text that starts at the beginning of a line
+    pub fn measure(self, text: &str) -> usize {
+        self.pieces(text).fold(0, |width, (_, piece)| self.advance(width, piece))
+    }
+
+    /// Get byte index of the column (starting from 1) in the line
+    ///
+    /// Column that points inside of a character resolves to the beginning of it.
+    /// The column right after the last character resolves to the end of the line.
+    pub fn offset(self, line: &str, column: usize) -> Option<usize> {
+        let target = column.checked_sub(1)?;
+        let mut width = 0;
+        for (index, piece) in self.pieces(line) {
+            let next = self.advance(width, piece);
+            if width == target || target < next {
+                return Some(index)
+            }
+            width = next;
+        }
+        (width == target).then_some(line.len())
+    }
+
+    // Split the line into the smallest parts that can be pointed at
+    fn pieces(self, line: &str) -> Box<dyn Iterator<Item = (usize, &str)> + '_> {
+        match self {
+            ColumnUnit::Graphemes | ColumnUnit::Display { .. } => Box::new(line.grapheme_indices(true)),
+            _ => Box::new(line.char_indices().map(|(index, letter)| (index, &line[index..index + letter.len_utf8()])))
+        }
+    }
+
+    // Get the width of the line after the piece that starts at given width
+    fn advance(self, width: usize, piece: &str) -> usize {
+        match self {
+            ColumnUnit::Bytes => width + piece.len(),
+            ColumnUnit::Chars => width + piece.chars().count(),
+            ColumnUnit::Utf16 => width + piece.encode_utf16().count(),
+            ColumnUnit::Graphemes => width + 1,
+            ColumnUnit::Display { tab_width } => match piece {
+                "\t" => (width / tab_width.max(1) + 1) * tab_width.max(1),
+                _ => width + piece.width()
+            }
+        }
+    }
+}
+
+/// Replace the tabs with spaces that reach the next tab stop
+///
+/// The text is expected to start at given display width of the line.
+pub fn expand_tabs(text: &str, start: usize, tab_width: usize) -> String {
+    let unit = ColumnUnit::Display { tab_width };
+    let mut width = start;
+    let mut result = String::new();
+    for piece in text.graphemes(true) {
+        let next = unit.advance(width, piece);
+        match piece {
+            "\t" => result.push_str(&" ".repeat(next - width)),
+            _ => result.push_str(piece)
+        }
+        width = next;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn measure() {
+        let line = "a\tż日🦀e\u{301}";
+        assert_eq!(ColumnUnit::Bytes.measure(line), 14);
+        assert_eq!(ColumnUnit::Chars.measure(line), 7);
+        assert_eq!(ColumnUnit::Utf16.measure(line), 8);
+        assert_eq!(ColumnUnit::Graphemes.measure(line), 6);
+        assert_eq!(ColumnUnit::Display { tab_width: 4 }.measure(line), 10);
+        assert_eq!(ColumnUnit::Display { tab_width: 8 }.measure(line), 14);
+    }
+
+    #[test]
+    fn offset() {
+        let line = "a\tż日🦀e\u{301}";
+        let display = ColumnUnit::Display { tab_width: 4 };
+        assert_eq!(display.offset(line, 1), Some(0));
+        // Column inside of the tab or a wide character points at its beginning
+        assert_eq!(display.offset(line, 3), Some(1));
+        assert_eq!(display.offset(line, 5), Some(2));
+        assert_eq!(display.offset(line, 7), Some(4));
+        assert_eq!(display.offset(line, 8), Some(7));
+        assert_eq!(display.offset(line, 11), Some(line.len()));
+        assert_eq!(display.offset(line, 12), None);
+        assert_eq!(ColumnUnit::Utf16.offset(line, 5), Some(7));
+        assert_eq!(ColumnUnit::Utf16.offset(line, 6), Some(7));
+        assert_eq!(ColumnUnit::Utf16.offset(line, 7), Some(11));
+        assert_eq!(ColumnUnit::Graphemes.offset(line, 6), Some(11));
+        assert_eq!(ColumnUnit::Chars.offset(line, 7), Some(12));
+        assert_eq!(ColumnUnit::Bytes.offset(line, 4), Some(2));
+        assert_eq!(ColumnUnit::Chars.offset(line, 0), None);
+    }
+
+    #[test]
+    fn tabs() {
+        assert_eq!(expand_tabs("\ta\tb", 0, 4), "    a   b");
+        assert_eq!(expand_tabs("\ta", 2, 4), "  a");
+    }
+}
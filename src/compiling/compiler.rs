@@ -1,11 +1,10 @@
 use capitalize::Capitalize;
-use std::fs::File;
-use std::io::prelude::*;
 use crate::compiling_rules::Rules;
-use crate::compiling::{Token, LexerError, LexerErrorType, Metadata, SyntaxModule};
+use crate::compiling::{CompilerBuilder, CompilerError, Token, BorrowedToken, ErrorRegion, Incomplete, LexerError, LexerErrorType, Metadata, SyntaxModule, Tracer, TraceFormat, Profile, SourceMap, ModuleLoader};
 use crate::compiling::failing::message::Message;
 use crate::compiling::failing::failure::Failure;
-use crate::error_pos;
+use crate::compiling::failing::position_info::Position;
+use crate::compiling::failing::catalog::{keys, translate};
 
 use super::lexer::Lexer;
 
@@ -48,6 +47,7 @@ pub enum ScopingMode {
 /// 
 /// Compiler is a central unit of heraclitus.
 /// This structure handles tokenizing and parsing considering all your language settings.
+/// Compiler is created with `Compiler::builder`.
 /// 
 /// # Example
 /// ```
@@ -61,103 +61,624 @@ pub enum ScopingMode {
 /// # fn compiler() -> Result<(), Failure> {
 /// # let rules = Rules::new(vec![], vec![], reg![]);
 /// let mut global_ctx = GlobalContext::new();
-/// let cc = Compiler::new("HerbScript", rules);
-/// let meta = cc.compile(&mut global_ctx)?;
+/// let cc = Compiler::builder("HerbScript", rules)
+///     .source("let a = 12")
+///     .build()?;
+/// let compilation: Compilation<DefaultMetadata> = cc.compile(&mut global_ctx)?;
 /// # Ok(())
 /// # }
 /// ```
 #[derive(Debug, Clone, PartialEq)]
+// Diagnostic hooks are compared by their address
+#[allow(unpredictable_function_pointer_comparisons)]
 pub struct Compiler {
     /// Name of your language
     pub name: String,
     /// Source code in a form of string
-    pub code: Option<String>,
+    pub code: String,
     /// Path to the compiled file if exists
     pub path: Option<String>,
     // Check if user wants to debug parser
-    debug: bool,
+    pub(crate) debug: bool,
+    // File to which the parser trace should be written
+    pub(crate) trace: Option<(String, TraceFormat)>,
+    // Check if user wants to profile parser
+    pub(crate) profile: bool,
+    // Check if lexer errors should be reported as diagnostics
+    pub(crate) tolerant: bool,
+    // Hook that customises the lexer diagnostics
+    pub(crate) lexer_hook: Option<LexerDiagnosticHook>,
     /// Lexer to tokenize the code
-    lexer: Lexer
+    pub(crate) lexer: Lexer
+}
+
+/// Result of the compilation
+#[derive(Debug)]
+pub struct Compilation<M: Metadata> {
+    /// Metadata after parsing
+    pub meta: M,
+    /// Diagnostics that were reported during the compilation
+    pub diagnostics: Vec<Message>,
+    /// Per module statistics of the parser if profiling was requested
+    pub profile: Option<Profile>
+}
+
+impl<M: Metadata> Compilation<M> {
+    /// Tokens produced by the lexer
+    ///
+    /// Tokens are kept by the metadata. This is `None` if the metadata does not expose them (see `Metadata::get_tokens`).
+    pub fn tokens(&self) -> Option<&[Token]> {
+        self.meta.get_tokens()
+    }
+}
+
+/// Status of the code that may not be complete yet
+#[derive(Debug)]
+pub enum InputStatus<M: Metadata> {
+    /// Code has been compiled
+    Complete(Compilation<M>),
+    /// More code is expected to finish the input
+    Incomplete(Incomplete),
+    /// Code contains an error that more code will not fix
+    Invalid(Failure)
 }
 
 impl Compiler {
+    /// Start building a compilation session with provided rules of your language
+    pub fn builder<T: AsRef<str>>(name: T, rules: Rules) -> CompilerBuilder {
+        CompilerBuilder::new(name, rules)
+    }
+
     /// Create a new compiler with provided rules of your language
+    #[deprecated(note = "use `Compiler::builder` instead")]
     pub fn new<T: AsRef<str>>(name: T, rules: Rules) -> Self {
-        Compiler {
-            name: String::from(name.as_ref()),
-            code: None,
-            path: None,
-            debug: false,
-            lexer: Lexer::new(rules)
-        }
+        CompilerBuilder::new(name, rules)
+            .source("")
+            .build()
+            .expect("source code is supplied")
     }
 
     /// Set the language to use indentations
+    #[deprecated(note = "use `CompilerBuilder::indent` instead")]
     pub fn use_indents(&mut self) {
-        self.lexer.scoping_mode = ScopingMode::Indent
+        self.configure(CompilerBuilder::indent)
     }
 
     /// Set the language separator mode
+    #[deprecated(note = "use `CompilerBuilder::separator` instead")]
     pub fn set_separator(&mut self, mode: SeparatorMode) {
-        self.lexer.separator_mode = mode
+        self.configure(|builder| builder.separator(mode))
     }
 
     /// Load file from path
-    pub fn load_file(mut self, file_path: String) -> std::io::Result<()> {
-        let mut file = File::open(&file_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        self.code = Some(contents);
-        self.path = Some(file_path);
-        Ok(())
+    #[deprecated(note = "use `CompilerBuilder::source_file` instead")]
+    pub fn load_file(self, file_path: String) -> std::io::Result<()> {
+        match CompilerBuilder::from_compiler(self).source_file(file_path) {
+            Ok(_) => Ok(()),
+            Err(CompilerError::Io { error, .. }) => Err(error),
+            Err(error) => Err(std::io::Error::other(error))
+        }
     }
 
     /// Load code string
+    #[deprecated(note = "use `CompilerBuilder::source` instead")]
     pub fn load<T: AsRef<str>>(&mut self, code: T) {
-        self.code = Some(String::from(code.as_ref()));
+        self.configure(|builder| builder.source(code))
     }
 
     /// Set source file path
+    #[deprecated(note = "use `CompilerBuilder::path` instead")]
     pub fn set_path(&mut self, file_path: String) {
-        self.path = Some(file_path);
+        self.configure(|builder| builder.path(file_path))
+    }
+
+    /// Parser will display information about the call stack
+    ///
+    /// This also records a structured trace that can be retrieved with `Metadata::get_tracer`
+    #[deprecated(note = "use `CompilerBuilder::debug` instead")]
+    pub fn debug(&mut self) {
+        self.configure(CompilerBuilder::debug)
+    }
+
+    // Change the settings of the compiler through the builder
+    fn configure(&mut self, configure: impl FnOnce(CompilerBuilder) -> CompilerBuilder) {
+        let builder = configure(CompilerBuilder::from_compiler(self.clone()));
+        *self = builder.build().expect("source code is supplied");
     }
 
     /// Run just lexer
     pub fn tokenize(&self) -> Result<Vec<Token>, LexerError> {
-        self.lexer.tokenize(&self.code.clone().unwrap())
+        self.lexer.tokenize(&self.code)
     }
 
-    /// Parser will display information about the call stack
-    pub fn debug(&mut self) {
-        self.debug = true
-    }
-
-    /// Bulk run lexer and parser (used for testing purposes)
-    pub fn compile<M: Metadata>(&self, module: &mut impl SyntaxModule<M>) -> Result<M, Failure> {
-        match self.tokenize() {
-            Ok(lexem) => {
-                let mut meta = M::new(lexem, self.path.clone(), self.code.clone());
-                if self.debug {
-                    module.parse_debug(&mut meta)?;
-                } else {
-                    module.parse(&mut meta)?;
+    /// Run just lexer and collect all the errors instead of stopping on the first one
+    pub fn tokenize_all(&self) -> (Vec<Token>, Vec<LexerError>) {
+        self.lexer.tokenize_all(&self.code)
+    }
+
+    /// Run just lexer and borrow the words of the tokens from the code
+    pub fn tokenize_borrowed(&self) -> Result<Vec<BorrowedToken<'_>>, LexerError> {
+        self.lexer.tokenize_borrowed(&self.code)
+    }
+
+    /// Check if the code is incomplete without running the parser
+    pub fn incomplete(&self) -> Option<Incomplete> {
+        self.lexer.incomplete(&self.code)
+    }
+
+    /// Compile the code unless it's incomplete
+    ///
+    /// This is meant for REPLs that need to know whether to read another line.
+    /// Apart from the cases detected by `Compiler::incomplete` the input is incomplete
+    /// also if the parser has failed at the end of the code.
+    /// # Example
+    /// ```
+    /// # use heraclitus_compiler::prelude::*;
+    /// # struct Print;
+    /// # impl SyntaxModule<DefaultMetadata> for Print {
+    /// #   syntax_name!("Print");
+    /// #   fn new() -> Self { Print }
+    /// #   fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult { Ok(()) }
+    /// # }
+    /// # let rules = Rules::new(vec!['(', ')'], vec![], reg![]).set_brackets(vec![('(', ')')]);
+    /// # let mut module = Print;
+    /// let cc = Compiler::builder("HerbScript", rules)
+    ///     .source("print (1")
+    ///     .build()
+    ///     .unwrap();
+    /// match cc.compile_input::<DefaultMetadata>(&mut module) {
+    ///     InputStatus::Incomplete(reason) => assert_eq!(reason, Incomplete::OpenBracket('(')),
+    ///     _ => unreachable!()
+    /// }
+    /// ```
+    pub fn compile_input<M: Metadata>(&self, module: &mut impl SyntaxModule<M>) -> InputStatus<M> {
+        if let Some(incomplete) = self.incomplete() {
+            return InputStatus::Incomplete(incomplete)
+        }
+        match self.compile(module) {
+            Ok(compilation) => InputStatus::Complete(compilation),
+            Err(failure) => {
+                let info = match &failure {
+                    Failure::Quiet(info) => Some(info),
+                    Failure::Loud(message) => message.trace.first()
+                };
+                // End of file is resolved to the position after the last character of the code
+                let is_eof = info.is_some_and(|info| {
+                    info.position == Position::EOF || info.span.is_some_and(|span| span.start() >= self.code.trim_end().len())
+                });
+                match is_eof {
+                    true => InputStatus::Incomplete(Incomplete::UnexpectedEof),
+                    false => InputStatus::Invalid(failure)
                 }
-                Ok(meta)
             }
-            Err((kind, info)) => {
-                let data = info.data.clone().unwrap().capitalize();
-                // Create an error message
-                let message = match kind {
-                    LexerErrorType::Singleline => format!("{data} cannot be multiline"),
-                    LexerErrorType::Unclosed => format!("{data} unclosed"),
-                };
-                // Send error
-                let meta = M::new(vec![], self.path.clone(), self.code.clone());
-                error_pos!(&meta, info => {
-                    message: message,
-                    comment: "test"
-                })
+        }
+    }
+
+    // Check if profiling was requested in any way
+    fn is_profiling(&self) -> bool {
+        self.profile || Profile::is_enabled_by_env()
+    }
+
+    // Create a tracer if tracing was requested in any way
+    fn create_tracer(&self) -> Option<Tracer> {
+        if let Some((path, format)) = &self.trace {
+            return Some(Tracer::new().output(path, *format))
+        }
+        match Tracer::from_env() {
+            Some(tracer) => Some(tracer),
+            None if self.debug || self.is_profiling() => Some(Tracer::new()),
+            None => None
+        }
+    }
+
+    // Aggregate the profile out of the parser trace if profiling was requested
+    fn create_profile<M: Metadata>(&self, meta: &mut M, diagnostics: &mut Vec<Message>) -> Option<Profile> {
+        if !self.is_profiling() {
+            return None
+        }
+        let Some(tracer) = meta.get_tracer() else {
+            diagnostics.push(Message::new_warn_msg(translate(keys::PROFILE_UNSUPPORTED, &[])));
+            return None
+        };
+        let profile = Profile::from_tracer(tracer);
+        // Profile requested without recompiling cannot be read from the compilation
+        if Profile::is_enabled_by_env() {
+            eprintln!("{profile}");
+        }
+        Some(profile)
+    }
+
+    /// Bulk run lexer and parser
+    ///
+    /// If the compiler is tolerant (see `CompilerBuilder::tolerant`) the lexer errors are reported
+    /// in `Compilation::diagnostics` and the code is parsed anyway. When parsing fails as well,
+    /// the first lexer error is returned as it is the most likely cause of the failure.
+    pub fn compile<M: Metadata>(&self, module: &mut impl SyntaxModule<M>) -> Result<Compilation<M>, Failure> {
+        let mut sources = SourceMap::new();
+        let file = sources.add(self.path.clone(), self.code.clone());
+        let source = sources.get(file).unwrap();
+        let (lexem, errors) = match self.tolerant {
+            true => self.lexer.tokenize_source_all(source),
+            false => match self.lexer.tokenize_source(source) {
+                Ok(lexem) => (lexem, vec![]),
+                Err(error) => {
+                    let mut meta = M::new(vec![], self.path.clone(), Some(self.code.clone()));
+                    meta.set_sources(sources, file);
+                    return Err(lexer_failure(&meta, error, self.lexer_hook))
+                }
             }
+        };
+        let mut meta = M::new(lexem, self.path.clone(), Some(self.code.clone()));
+        meta.set_sources(sources, file);
+        let mut diagnostics = errors.iter()
+            .map(|error| lexer_diagnostic(&meta, error, self.lexer_hook))
+            .collect::<Vec<_>>();
+        let mut loader = ModuleLoader::new(self.lexer.clone());
+        if let Some(hook) = self.lexer_hook {
+            loader = loader.lexer_diagnostics(hook);
         }
+        meta.set_loader(match &self.path {
+            Some(path) => loader.root(path, file),
+            None => loader
+        });
+        if let Some(tracer) = self.create_tracer() {
+            meta.set_tracer(tracer);
+        }
+        if self.debug {
+            meta.set_debug(0);
+        }
+        let result = if self.debug || meta.get_tracer().is_some() {
+            module.parse_debug(&mut meta)
+        } else {
+            module.parse(&mut meta)
+        };
+        // Write the trace even if parsing has failed
+        if let Some(tracer) = meta.get_tracer() {
+            if let Err(error) = tracer.flush() {
+                diagnostics.push(Message::new_warn_msg(translate(keys::TRACE_UNWRITABLE, &[("error", &error.to_string())])));
+            }
+        }
+        let profile = self.create_profile(&mut meta, &mut diagnostics);
+        if let Err(failure) = result {
+            return Err(match errors.is_empty() {
+                true => failure,
+                false => Failure::Loud(diagnostics.remove(0))
+            })
+        }
+        Ok(Compilation {
+            meta,
+            diagnostics,
+            profile
+        })
+    }
+}
+
+/// Hook that customises the diagnostics reported for lexer errors
+///
+/// It receives the lexer error together with the message created by heraclitus
+/// and returns the message that will be reported instead.
+pub type LexerDiagnosticHook = fn(&LexerError, Message) -> Message;
+
+/// Create a diagnostic message out of the lexer error
+///
+/// Regions can customise the message and help text with `RegionMessages`.
+pub fn lexer_message(meta: &impl Metadata, (kind, info): &LexerError) -> Message {
+    let data = info.data.clone().unwrap_or_default().capitalize();
+    let message = Message::new_err_at_position(meta, info.clone());
+    match kind {
+        LexerErrorType::Singleline(region) => message
+            .message(region.message.clone().unwrap_or_else(|| translate_region(keys::SINGLELINE, &data, region)))
+            .comment(region.help.clone().unwrap_or_else(|| translate_region(keys::SINGLELINE_HELP, &data, region))),
+        LexerErrorType::Unclosed(region) => message
+            .message(region.message.clone().unwrap_or_else(|| translate_region(keys::UNCLOSED, &data, region)))
+            .comment(region.help.clone().unwrap_or_else(|| translate_region(keys::UNCLOSED_HELP, &data, region)))
+            .note(region.begin.clone(), translate_region(keys::UNCLOSED_BEGIN, &data, region)),
+        LexerErrorType::UnexpectedCharacter(letter) => message
+            .message(translate(keys::UNEXPECTED_CHARACTER, &[("character", &letter.escape_default().to_string())])),
+        LexerErrorType::InvalidCharacter(letter) => message
+            .message(translate(keys::INVALID_CHARACTER, &[("data", &data), ("character", &letter.escape_default().to_string())])),
+        LexerErrorType::MixedIndentation => message
+            .message(translate(keys::MIXED_INDENTATION, &[]))
+            .comment(translate(keys::MIXED_INDENTATION_HELP, &[])),
+        LexerErrorType::UnterminatedEscape => message
+            .message(translate(keys::UNTERMINATED_ESCAPE, &[("data", &data)])),
+    }
+}
+
+// Translate message that describes the region
+fn translate_region(key: &str, data: &str, region: &ErrorRegion) -> String {
+    let end = show_delimiter(&region.end);
+    translate(key, &[("data", data), ("name", &region.name), ("end", &end)])
+}
+
+// Make whitespace delimiters visible in the messages
+fn show_delimiter(delimiter: &str) -> String {
+    delimiter.replace('\n', "\\n").replace('\t', "\\t")
+}
+
+// Create a diagnostic out of the lexer error
+fn lexer_diagnostic(meta: &impl Metadata, error: &LexerError, hook: Option<LexerDiagnosticHook>) -> Message {
+    let message = lexer_message(meta, error);
+    match hook {
+        Some(hook) => hook(error, message),
+        None => message
+    }
+}
+
+// Create a failure out of the lexer error
+pub(crate) fn lexer_failure(meta: &impl Metadata, error: LexerError, hook: Option<LexerDiagnosticHook>) -> Failure {
+    Failure::Loud(lexer_diagnostic(meta, &error, hook))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    struct Words {}
+
+    impl SyntaxModule<DefaultMetadata> for Words {
+        syntax_name!("Words");
+
+        fn new() -> Self {
+            Words {}
+        }
+
+        fn parse(&mut self, _meta: &mut DefaultMetadata) -> SyntaxResult {
+            Ok(())
+        }
+    }
+
+    fn rules(region: Region) -> Rules {
+        Rules::new(vec![], vec![], reg![region])
+    }
+
+    fn compile(compiler: Compiler) -> Message {
+        match compiler.compile::<DefaultMetadata>(&mut Words::new()) {
+            Err(Failure::Loud(message)) => message,
+            _ => panic!("Expected the lexer to fail")
+        }
+    }
+
+    #[test]
+    fn unclosed_region_message() {
+        let region = reg!(string as "string literal" => {
+            begin: "'",
+            end: "'"
+        });
+        let compiler = Compiler::builder("Test", rules(region))
+            .source("let a = 'text")
+            .build()
+            .unwrap();
+        let message = compile(compiler);
+        assert_eq!(message.message.unwrap(), "String literal unclosed");
+        assert_eq!(message.comment.unwrap(), "Add the missing ''' to close the string literal");
+        // Error points at the end of file and the note at the opening delimiter
+        assert_eq!(message.trace.len(), 1);
+        assert_eq!(message.trace[0].position, Position::Pos(1, 14));
+        assert_eq!(message.notes[0].label, "The string literal begins here");
+        assert_eq!(message.notes[0].position.position, Position::Pos(1, 9));
+        assert_eq!(message.notes[0].position.len, 1);
+    }
+
+    #[test]
+    fn custom_region_message() {
+        let region = reg!(string as "string literal" => {
+            begin: "'",
+            end: "'",
+            singleline: true
+        });
+        let messages = RegionMessages::new()
+            .singleline_message("Strings cannot span multiple lines")
+            .help("Use '\\n' to insert a new line");
+        let compiler = Compiler::builder("Test", rules(region).set_region_messages("string", messages))
+            .source("let a = 'text\n'")
+            .build()
+            .unwrap();
+        let message = compile(compiler);
+        assert_eq!(message.message.unwrap(), "Strings cannot span multiple lines");
+        assert_eq!(message.comment.unwrap(), "Use '\\n' to insert a new line");
+    }
+
+    #[test]
+    fn lexer_diagnostic_hook() {
+        let region = reg!(string as "string literal" => {
+            begin: "'",
+            end: "'"
+        });
+        let compiler = Compiler::builder("Test", rules(region))
+            .source("'text")
+            .lexer_diagnostics(|(kind, _), message| match kind {
+                LexerErrorType::Unclosed(region) => message.comment(format!("Expected '{}'", region.end)),
+                _ => message
+            })
+            .build()
+            .unwrap();
+        let message = compile(compiler);
+        assert_eq!(message.comment.unwrap(), "Expected '''");
+    }
+
+    #[test]
+    fn tolerant_diagnostics() {
+        let region = reg!(string as "string literal" => {
+            begin: "'",
+            end: "'"
+        });
+        let compiler = Compiler::builder("Test", rules(region))
+            .source("let a = 'text")
+            .tolerant()
+            .build()
+            .unwrap();
+        let compilation = compiler.compile::<DefaultMetadata>(&mut Words::new()).unwrap();
+        assert_eq!(compilation.diagnostics.len(), 1);
+        assert_eq!(compilation.diagnostics[0].message.clone().unwrap(), "String literal unclosed");
+        assert_eq!(compilation.tokens().unwrap()[0].word, "let");
+        // Parser failure is reported with the lexer error that most likely caused it
+        let Err(Failure::Loud(message)) = compiler.compile::<DefaultMetadata>(&mut Keyword {}) else {
+            panic!("Expected the parser to fail")
+        };
+        assert_eq!(message.message.unwrap(), "String literal unclosed");
+    }
+
+    struct Keyword {}
+
+    impl SyntaxModule<DefaultMetadata> for Keyword {
+        syntax_name!("Keyword");
+
+        fn new() -> Self {
+            Keyword {}
+        }
+
+        fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+            token(meta, "const")?;
+            Ok(())
+        }
+    }
+
+    // Metadata that does not record the parser trace
+    struct BareMetadata {
+        index: usize,
+        expr: Vec<Token>
+    }
+
+    impl Metadata for BareMetadata {
+        fn new(expr: Vec<Token>, _path: Option<String>, _code: Option<String>) -> Self {
+            BareMetadata { index: 0, expr }
+        }
+        fn get_token_at(&self, index: usize) -> Option<Token> { self.expr.get(index).cloned() }
+        fn get_index(&self) -> usize { self.index }
+        fn set_index(&mut self, index: usize) { self.index = index }
+        fn get_debug(&mut self) -> Option<usize> { None }
+        fn set_debug(&mut self, _indent: usize) {}
+        fn get_path(&self) -> Option<String> { None }
+        fn get_code(&self) -> Option<&String> { None }
+    }
+
+    struct Bare {}
+
+    impl SyntaxModule<BareMetadata> for Bare {
+        syntax_name!("Bare");
+
+        fn new() -> Self {
+            Bare {}
+        }
+
+        fn parse(&mut self, _meta: &mut BareMetadata) -> SyntaxResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn profile_without_tracer() {
+        let compiler = Compiler::builder("Test", Rules::new(vec![], vec![], reg![]))
+            .source("let a")
+            .profile()
+            .build()
+            .unwrap();
+        let compilation = compiler.compile(&mut Bare::new()).unwrap();
+        assert!(compilation.profile.is_none());
+        assert_eq!(compilation.diagnostics.len(), 1);
+        assert!(matches!(compilation.diagnostics[0].kind, MessageType::Warning));
+        let compilation = compiler.compile::<DefaultMetadata>(&mut Words::new()).unwrap();
+        assert!(compilation.profile.is_some() && compilation.diagnostics.is_empty());
+    }
+
+    struct Borrowed {}
+
+    impl SyntaxModule<BorrowedMetadata<'static>> for Borrowed {
+        syntax_name!("Borrowed");
+
+        fn new() -> Self {
+            Borrowed {}
+        }
+
+        fn parse(&mut self, meta: &mut BorrowedMetadata<'static>) -> SyntaxResult {
+            meta.tag_token(0, "keyword", &[]);
+            token(meta, "let")?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn profile_borrowed_metadata() {
+        let compiler = Compiler::builder("Test", Rules::new(vec![], vec![], reg![]))
+            .source("let a")
+            .profile()
+            .build()
+            .unwrap();
+        let compilation = compiler.compile(&mut Borrowed::new()).unwrap();
+        assert!(compilation.profile.is_some() && compilation.diagnostics.is_empty());
+        assert_eq!(compilation.meta.get_code().unwrap(), "let a");
+        assert_eq!(compilation.meta.encode_semantic_tokens().len(), 5);
+    }
+
+    struct Statements {}
+
+    impl SyntaxModule<DefaultMetadata> for Statements {
+        syntax_name!("Statements");
+
+        fn new() -> Self {
+            Statements {}
+        }
+
+        fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+            while let Some(current) = meta.get_current_token() {
+                if current.word.starts_with('\n') {
+                    meta.increment_index();
+                }
+                else if token(meta, "if").is_ok() {
+                    variable(meta, vec![])?;
+                    token(meta, ":")?;
+                    indent(meta)?;
+                }
+                else {
+                    token(meta, "print")?;
+                    let parens = token(meta, "(").is_ok();
+                    variable(meta, vec![])?;
+                    if parens {
+                        token(meta, ")")?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn input_status(code: &str) -> InputStatus<DefaultMetadata> {
+        let region = reg!(string as "string literal" => {
+            begin: "'",
+            end: "'"
+        });
+        let rules = Rules::new(vec!['(', ')', ':'], vec![], reg![region])
+            .set_brackets(vec![('(', ')')]);
+        Compiler::builder("Test", rules)
+            .indent()
+            .source(code)
+            .build()
+            .unwrap()
+            .compile_input(&mut Statements::new())
+    }
+
+    #[test]
+    fn incomplete_input() {
+        let incomplete = |code| match input_status(code) {
+            InputStatus::Incomplete(reason) => Some(reason),
+            _ => None
+        };
+        assert_eq!(incomplete("print 'text"), Some(Incomplete::UnclosedRegion("string literal".to_string())));
+        assert_eq!(incomplete("print 'text\\"), Some(Incomplete::UnclosedRegion("string literal".to_string())));
+        assert_eq!(incomplete("print (a"), Some(Incomplete::OpenBracket('(')));
+        assert_eq!(incomplete("if a:\n  print b"), Some(Incomplete::IndentBlock));
+        assert_eq!(incomplete("if a:\n  print (\nb)"), Some(Incomplete::IndentBlock));
+        assert_eq!(incomplete("if a:"), Some(Incomplete::UnexpectedEof));
+        assert_eq!(incomplete("print"), Some(Incomplete::UnexpectedEof));
+    }
+
+    #[test]
+    fn complete_input() {
+        assert!(matches!(input_status("print (a)"), InputStatus::Complete(_)));
+        assert!(matches!(input_status("if a:\n  print b\n"), InputStatus::Complete(_)));
+        assert!(!matches!(input_status("print (\n  a)"), InputStatus::Incomplete(_)));
+        assert!(matches!(input_status("print a)"), InputStatus::Invalid(_)));
+        assert!(matches!(input_status("print 12"), InputStatus::Invalid(_)));
     }
 }
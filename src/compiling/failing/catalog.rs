@@ -0,0 +1,327 @@
+//! Message catalogs
+//!
+//! This module lets you translate the messages produced by heraclitus
+//! and define your own keyed messages with parameters.
+//! Messages are looked up in the catalog of the current locale and fall back to English.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Locale of the built-in messages
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Keys of the messages produced by heraclitus
+pub mod keys {
+    /// Header of the error message
+    pub const ERROR: &str = "heraclitus.error";
+    /// Header of the warning message
+    pub const WARNING: &str = "heraclitus.warning";
+    /// Header of the info message
+    pub const INFO: &str = "heraclitus.info";
+    /// Location of the message that points to a place in code (`{location}`)
+    pub const AT: &str = "heraclitus.at";
+    /// Location of the message further in the trace (`{location}`)
+    pub const IN: &str = "heraclitus.in";
+    /// Secondary location that the message refers to (`{label}`)
+    pub const NOTE: &str = "heraclitus.note";
+    /// Location of the message that does not point to any place in code
+    pub const UNKNOWN_LOCATION: &str = "heraclitus.unknown_location";
+    /// Position at the end of file
+    pub const END_OF_FILE: &str = "heraclitus.end_of_file";
+    /// Singleline region spans multiple lines (`{data}`)
+    pub const SINGLELINE: &str = "heraclitus.lexer.singleline";
+    /// Help for singleline region that spans multiple lines (`{name}`, `{end}`)
+    pub const SINGLELINE_HELP: &str = "heraclitus.lexer.singleline_help";
+    /// Region has been left unclosed (`{data}`)
+    pub const UNCLOSED: &str = "heraclitus.lexer.unclosed";
+    /// Help for region that has been left unclosed (`{name}`, `{end}`)
+    pub const UNCLOSED_HELP: &str = "heraclitus.lexer.unclosed_help";
+    /// Note at the opening delimiter of the region that has been left unclosed (`{name}`)
+    pub const UNCLOSED_BEGIN: &str = "heraclitus.lexer.unclosed_begin";
+    /// Character that cannot appear in the code (`{character}`)
+    pub const UNEXPECTED_CHARACTER: &str = "heraclitus.lexer.unexpected_character";
+    /// Character that cannot appear inside of a region (`{data}`, `{character}`)
+    pub const INVALID_CHARACTER: &str = "heraclitus.lexer.invalid_character";
+    /// Indentation mixes tabs and spaces
+    pub const MIXED_INDENTATION: &str = "heraclitus.lexer.mixed_indentation";
+    /// Help for indentation that mixes tabs and spaces
+    pub const MIXED_INDENTATION_HELP: &str = "heraclitus.lexer.mixed_indentation_help";
+    /// Escape symbol is the last character of the region (`{data}`)
+    pub const UNTERMINATED_ESCAPE: &str = "heraclitus.lexer.unterminated_escape";
+    /// Metadata does not support imports (`{path}`)
+    pub const IMPORT_UNSUPPORTED: &str = "heraclitus.import.unsupported";
+    /// Help for metadata that does not support imports
+    pub const IMPORT_UNSUPPORTED_HELP: &str = "heraclitus.import.unsupported_help";
+    /// Modules import each other (`{path}`)
+    pub const IMPORT_CYCLE: &str = "heraclitus.import.cycle";
+    /// Imported module could not be read (`{path}`)
+    pub const IMPORT_UNREADABLE: &str = "heraclitus.import.unreadable";
+    /// Imported module could not be parsed (`{path}`)
+    pub const IMPORT_UNPARSABLE: &str = "heraclitus.import.unparsable";
+    /// Imported module did not give the loader back (`{path}`)
+    pub const IMPORT_LOADER_LOST: &str = "heraclitus.import.loader_lost";
+    /// Symbol is already defined in the current scope (`{name}`)
+    pub const REDEFINED_SYMBOL: &str = "heraclitus.scope.redefined";
+    /// Help for symbol that is already defined in the current scope (`{name}`)
+    pub const REDEFINED_SYMBOL_HELP: &str = "heraclitus.scope.redefined_help";
+    /// Note at the previous definition of the symbol (`{name}`)
+    pub const PREVIOUS_DEFINITION: &str = "heraclitus.scope.previous_definition";
+    /// Symbol is not defined in any of the scopes (`{name}`)
+    pub const UNDEFINED_SYMBOL: &str = "heraclitus.scope.undefined";
+    /// Parser trace could not be written (`{error}`)
+    pub const TRACE_UNWRITABLE: &str = "heraclitus.trace.unwritable";
+    /// Metadata does not record the parser trace needed by the profile
+    pub const PROFILE_UNSUPPORTED: &str = "heraclitus.profile.unsupported";
+    /// Required part of the grammar is missing (`{expected}`)
+    pub const EXPECTED: &str = "heraclitus.grammar.expected";
+}
+
+// Built-in English messages
+fn english(key: &str) -> Option<&'static str> {
+    Some(match key {
+        keys::ERROR => " ERROR ",
+        keys::WARNING => " WARN ",
+        keys::INFO => " INFO ",
+        keys::AT => "at {location}",
+        keys::IN => "in {location}",
+        keys::NOTE => "note: {label}",
+        keys::UNKNOWN_LOCATION => "at [unknown]:0:0",
+        keys::END_OF_FILE => " end of file",
+        keys::SINGLELINE => "{data} cannot be multiline",
+        keys::SINGLELINE_HELP => "Close the {name} with '{end}' before the end of the line",
+        keys::UNCLOSED => "{data} unclosed",
+        keys::UNCLOSED_HELP => "Add the missing '{end}' to close the {name}",
+        keys::UNCLOSED_BEGIN => "The {name} begins here",
+        keys::UNEXPECTED_CHARACTER => "Unexpected character '{character}'",
+        keys::INVALID_CHARACTER => "{data} cannot contain character '{character}'",
+        keys::MIXED_INDENTATION => "Indentation cannot mix tabs and spaces",
+        keys::MIXED_INDENTATION_HELP => "Use either tabs or spaces to indent the code",
+        keys::UNTERMINATED_ESCAPE => "{data} ends with an unterminated escape sequence",
+        keys::IMPORT_UNSUPPORTED => "Cannot import module '{path}'",
+        keys::IMPORT_UNSUPPORTED_HELP => "Imports are not supported by this metadata",
+        keys::IMPORT_CYCLE => "Import cycle detected while importing '{path}'",
+        keys::IMPORT_UNREADABLE => "Could not import module '{path}'",
+        keys::IMPORT_UNPARSABLE => "Could not parse module '{path}'",
+        keys::IMPORT_LOADER_LOST => "Module '{path}' took the module loader and did not put it back",
+        keys::REDEFINED_SYMBOL => "'{name}' is already defined in this scope",
+        keys::REDEFINED_SYMBOL_HELP => "Rename '{name}' or remove one of its definitions",
+        keys::PREVIOUS_DEFINITION => "'{name}' was first defined here",
+        keys::UNDEFINED_SYMBOL => "'{name}' is not defined",
+        keys::TRACE_UNWRITABLE => "Could not write the parser trace: {error}",
+        keys::PROFILE_UNSUPPORTED => "Could not profile the parser because the metadata does not record the parser trace",
+        keys::EXPECTED => "Expected {expected}",
+        _ => return None
+    })
+}
+
+/// Replace `{name}` placeholders in the template with given parameters
+///
+/// Template is read once from left to right so the substituted values are never formatted again.
+/// Placeholders without a parameter are left untouched.
+pub fn format_message(template: &str, params: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        let value = placeholder.find('}').and_then(|end| {
+            let name = &placeholder[1..end];
+            params.iter().find(|(key, _)| *key == name).map(|(_, value)| (*value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                result.push_str(value);
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &placeholder[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Messages translated to a single locale
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let catalog = Catalog::new("pl")
+///     .message(keys::ERROR, " BŁĄD ")
+///     .message("amber.undefined", "Zmienna '{name}' nie istnieje");
+/// assert_eq!(catalog.get("amber.undefined"), Some("Zmienna '{name}' nie istnieje"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    locale: String,
+    messages: HashMap<String, String>
+}
+
+impl Catalog {
+    /// Create an empty catalog for given locale
+    pub fn new<T: AsRef<str>>(locale: T) -> Self {
+        Catalog {
+            locale: locale.as_ref().to_string(),
+            messages: HashMap::new()
+        }
+    }
+
+    /// Add a message template under given key
+    pub fn message<K: AsRef<str>, V: AsRef<str>>(mut self, key: K, template: V) -> Self {
+        self.insert(key, template);
+        self
+    }
+
+    /// Insert a message template under given key
+    pub fn insert<K: AsRef<str>, V: AsRef<str>>(&mut self, key: K, template: V) {
+        self.messages.insert(key.as_ref().to_string(), template.as_ref().to_string());
+    }
+
+    /// Get the locale of this catalog
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Get the message template under given key
+    pub fn get<T: AsRef<str>>(&self, key: T) -> Option<&str> {
+        self.messages.get(key.as_ref()).map(String::as_str)
+    }
+}
+
+/// Collection of catalogs with the selected locale
+///
+/// Heraclitus uses a global collection that is managed by
+/// `set_locale`, `add_catalog` and `translate` functions.
+#[derive(Debug, Clone, Default)]
+pub struct Catalogs {
+    locale: String,
+    catalogs: Vec<Catalog>
+}
+
+impl Catalogs {
+    /// Create a collection that uses the built-in English messages
+    pub const fn new() -> Self {
+        Catalogs {
+            locale: String::new(),
+            catalogs: Vec::new()
+        }
+    }
+
+    /// Get the selected locale
+    pub fn get_locale(&self) -> &str {
+        if self.locale.is_empty() { DEFAULT_LOCALE } else { &self.locale }
+    }
+
+    /// Select the locale in which the messages are translated
+    pub fn set_locale<T: AsRef<str>>(&mut self, locale: T) {
+        self.locale = locale.as_ref().to_string();
+    }
+
+    /// Add catalog or merge it with the existing catalog of the same locale
+    pub fn add(&mut self, catalog: Catalog) {
+        match self.catalogs.iter_mut().find(|existing| existing.locale == catalog.locale) {
+            Some(existing) => existing.messages.extend(catalog.messages),
+            None => self.catalogs.push(catalog)
+        }
+    }
+
+    // Find the message template in the catalog of given locale
+    fn lookup(&self, locale: &str, key: &str) -> Option<&str> {
+        self.catalogs.iter()
+            .find(|catalog| catalog.locale == locale)
+            .and_then(|catalog| catalog.get(key))
+    }
+
+    /// Get the message template under given key
+    ///
+    /// The template is looked up in the selected locale (`pl-PL`), its language (`pl`)
+    /// and English. If none of them contains the key, the key itself is returned.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        let locale = self.get_locale();
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        self.lookup(locale, key)
+            .or_else(|| self.lookup(language, key))
+            .or_else(|| self.lookup(DEFAULT_LOCALE, key))
+            .or_else(|| english(key))
+            .unwrap_or(key)
+    }
+
+    /// Translate message under given key and fill in its parameters
+    pub fn translate(&self, key: &str, params: &[(&str, &str)]) -> String {
+        format_message(self.get(key), params)
+    }
+}
+
+static CATALOGS: RwLock<Catalogs> = RwLock::new(Catalogs::new());
+
+/// Select the locale in which the messages are translated
+pub fn set_locale<T: AsRef<str>>(locale: T) {
+    if let Ok(mut catalogs) = CATALOGS.write() {
+        catalogs.set_locale(locale);
+    }
+}
+
+/// Get the selected locale
+pub fn get_locale() -> String {
+    match CATALOGS.read() {
+        Ok(catalogs) => catalogs.get_locale().to_string(),
+        Err(_) => DEFAULT_LOCALE.to_string()
+    }
+}
+
+/// Register catalog of messages
+pub fn add_catalog(catalog: Catalog) {
+    if let Ok(mut catalogs) = CATALOGS.write() {
+        catalogs.add(catalog);
+    }
+}
+
+/// Translate message under given key in the selected locale and fill in its parameters
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// add_catalog(Catalog::new("en").message("herb.undefined", "Variable '{name}' does not exist"));
+/// assert_eq!(translate("herb.undefined", &[("name", "apple")]), "Variable 'apple' does not exist");
+/// ```
+pub fn translate(key: &str, params: &[(&str, &str)]) -> String {
+    match CATALOGS.read() {
+        Ok(catalogs) => catalogs.translate(key, params),
+        Err(_) => format_message(english(key).unwrap_or(key), params)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_parameters() {
+        let result = format_message("{data} cannot contain '{character}'", &[("data", "String"), ("character", "x")]);
+        assert_eq!(result, "String cannot contain 'x'");
+    }
+
+    #[test]
+    fn format_parameters_once() {
+        // Values that look like placeholders are not substituted again
+        let result = format_message("{data} unclosed", &[("data", "{name}"), ("name", "X")]);
+        assert_eq!(result, "{name} unclosed");
+        let result = format_message("{{a}} {missing} {a", &[("a", "1")]);
+        assert_eq!(result, "{1} {missing} {a");
+    }
+
+    #[test]
+    fn fallback_to_english() {
+        let mut catalogs = Catalogs::new();
+        catalogs.add(Catalog::new("pl").message(keys::UNCLOSED, "{data} nie został zamknięty"));
+        catalogs.add(Catalog::new("pl").message("herb.undefined", "Zmienna '{name}' nie istnieje"));
+        assert_eq!(catalogs.translate(keys::UNCLOSED, &[("data", "Tekst")]), "Tekst unclosed");
+        catalogs.set_locale("pl-PL");
+        assert_eq!(catalogs.translate(keys::UNCLOSED, &[("data", "Tekst")]), "Tekst nie został zamknięty");
+        assert_eq!(catalogs.translate("herb.undefined", &[("name", "a")]), "Zmienna 'a' nie istnieje");
+        assert_eq!(catalogs.get(keys::ERROR), " ERROR ");
+        assert_eq!(catalogs.get("herb.missing"), "herb.missing");
+    }
+}
@@ -1,21 +1,34 @@
 //! This is a logger module which is used by compiler to log errors, warnings and info messages
 
+use std::sync::Arc;
 use colored::{Colorize, Color};
 use pad::PadStr;
+use crate::compiling::{expand_tabs, ColumnUnit, SourceFile, SourceMap};
 use crate::compiling::failing::position_info::PositionInfo;
 use crate::compiling::failing::message::MessageType;
+use crate::compiling::failing::catalog::{keys, translate};
 use crate::prelude::Position;
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
+// Number of lines shown at the beginning and the end of a long multiline span
+const SPAN_EDGE_LINES: usize = 2;
+
 /// This is a logger that is used to log messages to the user
 /// The logger is being used internally by the Message struct
 /// when invoking the `show` method
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Logger {
     kind: MessageType,
-    trace: Vec<PositionInfo>
+    trace: Vec<PositionInfo>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sources: SourceMap,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    output: Option<String>,
+    columns: ColumnUnit,
+    tab_width: usize,
+    context: usize
 }
 
 impl Logger {
@@ -23,7 +36,70 @@ impl Logger {
     pub fn new(kind: MessageType, trace: &[PositionInfo]) -> Self {
         Logger {
             kind,
-            trace: trace.to_vec()
+            trace: trace.to_vec(),
+            sources: SourceMap::new(),
+            output: None,
+            columns: ColumnUnit::Chars,
+            tab_width: 4,
+            context: 1
+        }
+    }
+
+    /// Render the message to a string instead of the standard error
+    pub fn capture(mut self) -> Self {
+        self.output = Some(String::new());
+        self
+    }
+
+    /// Get the captured output without the styling
+    pub fn into_output(self) -> String {
+        strip_styles(&self.output.unwrap_or_default())
+    }
+
+    // Write the text either to the captured output or to the standard error
+    fn write(&mut self, text: impl std::fmt::Display) {
+        match &mut self.output {
+            Some(output) => output.push_str(&text.to_string()),
+            None => eprint!("{text}")
+        }
+    }
+
+    /// Resolve positions of the trace through given sources
+    pub fn sources(mut self, sources: &SourceMap) -> Self {
+        self.sources = sources.clone();
+        self
+    }
+
+    /// Report the columns of the location in given unit
+    ///
+    /// Columns are converted only for the positions that point to the sources.
+    pub fn columns(mut self, unit: ColumnUnit) -> Self {
+        self.columns = unit;
+        self
+    }
+
+    /// Set the distance between the tab stops used to display the snippet
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width.max(1);
+        self
+    }
+
+    /// Set the number of lines shown above and below the highlighted code
+    pub fn context_lines(mut self, context: usize) -> Self {
+        self.context = context;
+        self
+    }
+
+    // Get the source that the position points to
+    fn get_source(&self, pos: &PositionInfo) -> Option<&Arc<SourceFile>> {
+        pos.get_source(&self.sources)
+    }
+
+    // Get the path of the position or the path of the source it points to
+    fn get_path(&self, pos: &PositionInfo) -> String {
+        match (&pos.path, self.get_source(pos).and_then(|source| source.path())) {
+            (None, Some(path)) => path.clone(),
+            _ => pos.get_path()
         }
     }
 
@@ -36,202 +112,247 @@ impl Logger {
     }
 
     /// Render header of your information
-    pub fn header(self, kind: MessageType) -> Self {
+    pub fn header(mut self, kind: MessageType) -> Self {
         let name = match kind {
-            MessageType::Error => " ERROR ".to_string(),
-            MessageType::Warning => " WARN ".to_string(),
-            MessageType::Info => " INFO ".to_string()
+            MessageType::Error => translate(keys::ERROR, &[]),
+            MessageType::Warning => translate(keys::WARNING, &[]),
+            MessageType::Info => translate(keys::INFO, &[])
         };
         let formatted = name
             .black()
             .bold()
             .on_color(self.kind_to_color());
-        eprint!("{formatted} ");
+        self.write(format!("{formatted} "));
         self
     }
 
     /// Render text with supplied coloring
-    pub fn text(self, text: Option<String>) -> Self {
+    pub fn text(mut self, text: Option<String>) -> Self {
         if let Some(text) = text {
-            eprint!("{}", text.color(self.kind_to_color()));
+            let text = text.color(self.kind_to_color());
+            self.write(text);
         }
         self
     }
 
     /// Render text with supplied coloring and end it with a newline
-    pub fn line(self, text: Option<String>) -> Self {
+    pub fn line(mut self, text: Option<String>) -> Self {
         if let Some(text) = text {
-            eprintln!("{}", text.color(self.kind_to_color()));
+            let text = text.color(self.kind_to_color());
+            self.write(format!("{text}\n"));
         }
         self
     }
 
     /// Render padded text with a newline, applying the supplied coloring, and end it with another newline
-    pub fn padded_line(self, text: Option<String>) -> Self {
+    pub fn padded_line(mut self, text: Option<String>) -> Self {
         if let Some(text) = text {
-            eprintln!("\n{}", text.color(self.kind_to_color()));
+            let text = text.color(self.kind_to_color());
+            self.write(format!("\n{text}\n"));
         }
         self
     }
 
-    /// Render location details with supplied coloring
-    pub fn path(self) -> Self {
-        let get_row_col = |pos: &PositionInfo| match pos.position {
+    // Get the path together with the row and column of the position
+    fn get_location(&self, pos: &PositionInfo) -> String {
+        let row_col = match self.get_source(pos).map_or(pos.position.clone(), |source| pos.position_in(source, self.columns)) {
             Position::Pos(row, col) => format!("{}:{}", row, col),
-            Position::EOF => " end of file".to_string()
+            Position::EOF => translate(keys::END_OF_FILE, &[])
         };
+        format!("{}:{row_col}", self.get_path(pos))
+    }
+
+    /// Render location details with supplied coloring
+    pub fn path(mut self) -> Self {
+        let location = |key: &str, pos: &PositionInfo| translate(key, &[("location", &self.get_location(pos))]);
         let path = match self.trace.first() {
             Some(pos) => {
                 [
-                    format!("at {}:{}", pos.get_path(), get_row_col(pos)),
+                    location(keys::AT, pos),
                     self.trace.iter()
                         .skip(1)
-                        .map(|pos| format!("in {}:{}", pos.get_path(), get_row_col(pos)))
+                        .map(|pos| location(keys::IN, pos))
                         .collect::<Vec<String>>()
                         .join("\n")
                 ].join("\n")
             },
             None => {
-                "at [unknown]:0:0".to_string()
+                translate(keys::UNKNOWN_LOCATION, &[])
             }
         }.trim_end().to_string();
-        eprintln!("{}", path.color(self.kind_to_color()).dimmed());
+        let path = path.color(self.kind_to_color()).dimmed();
+        self.write(format!("{path}\n"));
         self
     }
 
-    // Returns last row, column and it's length
-    fn get_row_col_len(&self) -> Option<(usize, usize, usize)> {
-        match self.trace.first() {
-            Some(pos) => match pos.position {
-                Position::Pos(row, col) => Some((row, col, pos.len)),
-                Position::EOF => None
-            },
-            None => None
-        }
+    /// Render a secondary location with its label and the snippet of the code around it
+    pub fn note<T: AsRef<str>>(mut self, pos: &PositionInfo, label: &str, code: Option<T>) -> Self {
+        let label = translate(keys::NOTE, &[("label", label)]).color(self.kind_to_color());
+        let location = translate(keys::AT, &[("location", &self.get_location(pos))]).color(self.kind_to_color()).dimmed();
+        self.write(format!("\n{label}\n{location}\n"));
+        self.snippet_at(pos, code)
     }
 
-    // Get max padding size (for line numbering)
-    fn get_max_pad_size(&self, length: usize) -> Option<usize> {
-        let (row, _, _) = self.get_row_col_len()?;
-        if row < length - 1 {
-            Some(format!("{}", row + 1).len())
-        }
-        else {
-            Some(format!("{}", row).len())
+    // Get the byte range of the source that is supposed to be highlighted
+    fn get_highlighted_range(&self, source: &SourceFile, pos: &PositionInfo) -> Option<(usize, usize)> {
+        if let Some(span) = pos.span.filter(|_| self.get_source(pos).is_some()) {
+            let len = source.code().len();
+            // End of file is shown right after the last character instead of after the trailing new line
+            if span.start() >= len {
+                let (row, col) = source.eof_position();
+                let start = source.offset(row, col).unwrap_or(len);
+                return Some((start, start))
+            }
+            return Some((span.start(), span.end().clamp(span.start(), len)))
         }
+        let (row, col) = match pos.position {
+            Position::Pos(row, col) => (row, col.max(1)),
+            Position::EOF => source.eof_position()
+        };
+        let line = source.line(row)?;
+        // Column past the end of line points right after its last character
+        let start = source.offset(row, 1)? + ColumnUnit::Chars.offset(line, col).unwrap_or(line.len());
+        Some((start, source.advance(start, pos.len)))
     }
 
-    // Returns chopped string where fisrt and third part are supposed
-    // to be left as is but the second one is supposed to be highlighted
-    fn get_highlighted_part(&self, line: &str) -> Option<[String;3]> {
-        let (_row, col, len) = self.get_row_col_len()?;
-        let begin = col - 1;
-        let end = begin + len;
-        let mut results: [String; 3] = Default::default();
-        for (index, letter) in line.chars().enumerate() {
-            if index < begin {
-                results[0].push(letter);
-            }
-            else if index >= end {
-                results[2].push(letter);
-            }
-            else {
-                results[1].push(letter);
-            }
-        }
-        Some(results)
-    }
-
-    // Return requested row with appropriate coloring
-    fn get_snippet_row(&self, code: &Vec<String>, index: usize, offset: i8, overflow: &mut usize) -> Option<String> {
-        let (row, col, len) = self.get_row_col_len()?;
-        let max_pad = self.get_max_pad_size(code.len())?;
-        let index = index as i32 + offset as i32;
-        let row = row as i32 + offset as i32;
-        let code = code.get(index as usize)?.clone();
-        let line = format!("{row}").pad_to_width(max_pad);
-        // Case if we are in the same line as the error (or message)
-        if offset == 0 {
-            let slices = self.get_highlighted_part(&code)?;
-            let formatted = format!("{}{}{}", slices[0], slices[1].color(self.kind_to_color()), slices[2]);
-            let end = col.checked_add(len).unwrap_or(len);
-            // If we are at the end of the code snippet and there is still some
-            if end - 1 > code.chars().count() {
-                // We substract here 2 because 1 is the offset of col (starts at 1)
-                // and other 1 is the new line character that we do not display
-                *overflow = (end - 2).checked_sub(code.chars().count()).unwrap_or(0);
-            }
-            Some(format!("{line}| {formatted}"))
-        }
-        // Case if we are in a different line than the error (or message)
-        else {
-            // If there is some overflow value - display it as well
-            if *overflow > 0 {
-                // Case if all line is highlighted
-                if *overflow > code.chars().count() {
-                    Some(format!("{line}| {}", code.color(self.kind_to_color())).dimmed().to_string())
-                }
-                // Case if some line is highlighted
-                else {
-                    let err = code.get(0..*overflow).unwrap().to_string().color(self.kind_to_color());
-                    let rest = code.get(*overflow..).unwrap().to_string();
-                    Some(format!("{line}| {err}{rest}").dimmed().to_string())
-                }
-            }
-            // Case if no overflow
-            else {
-                Some(format!("{line}| {code}").dimmed().to_string())
-            }
-        }
+    // Replace the tabs of the consecutive parts of the line with spaces
+    // and return them together with the display width at which every part ends
+    fn expand_parts<const N: usize>(&self, parts: [&str; N]) -> ([String; N], [usize; N]) {
+        let unit = ColumnUnit::Display { tab_width: self.tab_width };
+        let mut width = 0;
+        let mut ends = [0; N];
+        let expanded = std::array::from_fn(|index| {
+            let part = expand_tabs(parts[index], width, self.tab_width);
+            width += unit.measure(&part);
+            ends[index] = width;
+            part
+        });
+        (expanded, ends)
+    }
+
+    // Render the line with the highlighted part (given in bytes) and return the display width
+    // at which the highlighted part begins and ends
+    fn get_snippet_line(&self, code: &str, highlight: Option<(usize, usize)>) -> (String, usize, usize) {
+        let code = code.trim_end();
+        let (begin, end) = highlight.map_or((code.len(), code.len()), |(begin, end)| (begin.min(code.len()), end.min(code.len())));
+        let ([before, highlighted, after], [begin, end, _]) = self.expand_parts([&code[..begin], &code[begin..end], &code[end..]]);
+        (format!("{before}{}{after}", highlighted.color(self.kind_to_color())), begin, end)
     }
 
     /// Render snippet of the code if the message is contextual to it
+    ///
+    /// The code is taken from the source that the position points to.
+    /// Supplied code is used only if the source could not be resolved.
     pub fn snippet<T: AsRef<str>>(self, code: Option<T>) -> Self {
-        if let Some(pos) = self.trace.first() {
-            if let Ok(code) = std::fs::read_to_string(pos.get_path()) {
-                self.snippet_from_code(code);
-                return self;
-            }
+        match self.trace.first().cloned() {
+            Some(pos) => self.snippet_at(&pos, code),
+            None => self
+        }
+    }
+
+    // Render snippet of the code around given position
+    fn snippet_at<T: AsRef<str>>(mut self, pos: &PositionInfo, code: Option<T>) -> Self {
+        if let Some(source) = self.get_source(pos).cloned() {
+            self.snippet_from_source(&source, pos);
         }
-        if let Some(code) = code {
-            self.snippet_from_code(code.as_ref().to_string());
+        else if let Some(code) = code {
+            let mut sources = SourceMap::new();
+            let file = sources.add(None, code.as_ref());
+            self.snippet_from_source(sources.get(file).unwrap(), pos);
         }
         self
     }
 
-    /// Render snippet of the code based on the code data
-    fn snippet_from_code(&self, code: String) -> Option<()> {
-        let (row, _, _) = self.get_row_col_len()?;
-        let mut overflow = 0;
-        let index = row - 1;
-        let code = code.split('\n')
-            .map(|item| item.trim_end().to_string())
-            .collect::<Vec<String>>();
-        eprintln!();
-        // Show additional code above the snippet
-        if let Some(line) = self.get_snippet_row(&code, index, -1, &mut overflow) {
-            eprintln!("{}", line);
+    /// Render snippet of the code based on the source
+    ///
+    /// The highlighted lines are surrounded with the context lines. Lines of a span that
+    /// reaches over multiple lines are marked in the gutter and the middle of a long span is elided.
+    fn snippet_from_source(&mut self, source: &SourceFile, pos: &PositionInfo) -> Option<()> {
+        let (start, end) = self.get_highlighted_range(source, pos)?;
+        let code = source.code();
+        let (first, _) = source.offset_position(start);
+        // The last highlighted character determines the last highlighted line
+        let (last, _) = match code[start..end].char_indices().last() {
+            Some((index, _)) => source.offset_position(start + index),
+            None => (first, 0)
+        };
+        // Trailing new line does not start a line that could be displayed
+        let line_count = source.line_count() - usize::from(code.ends_with('\n'));
+        let above = first.saturating_sub(self.context).max(1);
+        let below = (last + self.context).min(line_count.max(last));
+        let max_pad = below.to_string().len();
+        let is_multiline = first != last;
+        let is_elided = last - first > 2 * SPAN_EDGE_LINES;
+        let gutter = " ".repeat(max_pad);
+        self.write("\n");
+        for row in above..=below {
+            // Elide the middle of a long span
+            if is_elided && (first + SPAN_EDGE_LINES..=last - SPAN_EDGE_LINES).contains(&row) {
+                if row == first + SPAN_EDGE_LINES {
+                    self.write(format!("{gutter}| {}\n", "| ...".color(self.kind_to_color())));
+                }
+                continue
+            }
+            let line_start = source.offset(row, 1)?;
+            let line = source.line(row)?;
+            let highlight = (first..=last).contains(&row)
+                .then(|| (start.saturating_sub(line_start), (end - line_start).min(line.len())));
+            let (formatted, begin, finish) = self.get_snippet_line(line, highlight);
+            let marker = match is_multiline {
+                true if row == first => "/ ",
+                true if row == last => "\\ ",
+                true if (first..last).contains(&row) => "| ",
+                true => "  ",
+                false => ""
+            };
+            let number = row.to_string().pad_to_width(max_pad);
+            match highlight {
+                Some(_) => self.write(format!("{number}| {}{formatted}\n", marker.color(self.kind_to_color()))),
+                None => self.write(format!("{number}| {marker}{formatted}\n").dimmed())
+            }
+            // Underline the part of the first and the last highlighted line
+            if row == first || row == last {
+                let marker = match is_multiline {
+                    true if row == first => "| ",
+                    true => "  ",
+                    false => ""
+                };
+                let underline = "^".repeat(finish.saturating_sub(begin).max(1));
+                let underline = format!("{marker}{}{underline}", " ".repeat(begin)).color(self.kind_to_color());
+                self.write(format!("{gutter}| {underline}\n"));
+            }
         }
-        // Show the current line of code
-        eprintln!("{}", self.get_snippet_row(&code, index, 0, &mut overflow)?);
-        // Show additional code below the snippet
-        eprintln!("{}", self.get_snippet_row(&code, index, 1, &mut overflow)?);
         Some(())
     }
 }
 
+// Remove the terminal escape sequences that style the text
+fn strip_styles(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(letter) = chars.next() {
+        if letter == '\x1b' {
+            // Sequences end with a letter such as 'm' in "\x1b[1;31m"
+            chars.by_ref().find(|letter| letter.is_ascii_alphabetic());
+        } else {
+            result.push(letter);
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use std::time::Duration;
     use std::thread::sleep;
 
-    use crate::prelude::{DefaultMetadata, Metadata, MessageType, PositionInfo, Token};
+    use crate::prelude::{ColumnUnit, DefaultMetadata, FileId, Metadata, MessageType, PositionInfo, SourceMap, Span, Token};
     #[allow(unused_variables)]
 
     #[test]
     fn test_displayer() {
-        let code = vec![
+        let code = [
             "let a = 12",
             "value = 'this",
             "is mutltiline",
@@ -245,16 +366,14 @@ mod test {
         ];
         super::Logger::new(MessageType::Error, &trace)
             .header(MessageType::Error)
-            .line(Some(format!("Cannot call function \"foobar\" on a number")))
+            .line(Some("Cannot call function \"foobar\" on a number".to_string()))
             .path()
             .snippet(Some(code));
     }
 
     #[test]
     fn test_end_of_line_displayer() {
-        let code = vec![
-            "hello"
-        ].join("\n");
+        let code = ["hello"].join("\n");
         // Uncomment to see the error message
         sleep(Duration::from_secs(1));
         let trace = [
@@ -262,28 +381,178 @@ mod test {
         ];
         super::Logger::new(MessageType::Error, &trace)
             .header(MessageType::Error)
-            .line(Some(format!("Cannot call function \"foobar\" on a number")))
+            .line(Some("Cannot call function \"foobar\" on a number".to_string()))
             .path()
             .snippet(Some(code));
     }
 
     #[test]
     fn test_between_tokens() {
-        let code = vec![
-            "foo(12 + 24)"
-        ].join("\n");
+        let code = ["foo(12 + 24)"].join("\n");
         // Uncomment to see the error message
         sleep(Duration::from_secs(1));
-        let begin = Token { word: "12".to_string(), pos: (1, 5), start: 4 };
-        let end = Token { word: ")".to_string(), pos: (1, 12), start: 11 };
-        let mut meta = DefaultMetadata::new(vec![], Some("/path/to/foo".to_string()), Some(code.clone()));
+        let begin = Token::new("12", Span::new(FileId(0), 4, 6));
+        let end = Token::new(")", Span::new(FileId(0), 11, 12));
+        let meta = DefaultMetadata::new(vec![], Some("/path/to/foo".to_string()), Some(code.clone()));
         let trace = [
-            PositionInfo::from_between_tokens(&mut meta, Some(begin), Some(end))
+            PositionInfo::from_between_tokens(&meta, Some(begin), Some(end))
         ];
         super::Logger::new(MessageType::Error, &trace)
+            .sources(meta.get_sources().unwrap())
             .header(MessageType::Error)
-            .line(Some(format!("Cannot call function \"foobar\" on a number")))
+            .line(Some("Cannot call function \"foobar\" on a number".to_string()))
             .path()
             .snippet(Some(code));
     }
+
+    #[test]
+    fn test_snippet_edge_positions() {
+        let code = "żółw = 'ąę\nęąęąęąęą'\n";
+        let trace = [
+            PositionInfo::at_pos(None, (1, 0), 0),
+            PositionInfo::at_pos(None, (1, 8), 11)
+        ];
+        for pos in trace {
+            super::Logger::new(MessageType::Error, &[pos])
+                .path()
+                .snippet(Some(code));
+        }
+    }
+
+    #[test]
+    fn test_snippet_from_sources() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(Some("/path/to/foo".to_string()), "let a = 12\nlet b = a");
+        let trace = [
+            PositionInfo::at_pos(None, (2, 9), 1).span(Span::new(file, 19, 20))
+        ];
+        let logger = super::Logger::new(MessageType::Error, &trace).sources(&sources);
+        assert_eq!(logger.get_path(&trace[0]), "/path/to/foo");
+        assert_eq!(logger.get_source(&trace[0]).unwrap().line(2), Some("let b = a"));
+        logger
+            .header(MessageType::Error)
+            .line(Some("Variable \"a\" is not a number".to_string()))
+            .path()
+            .snippet(None::<String>);
+    }
+
+    #[test]
+    fn test_snippet_display_width() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(Some("main.ab".to_string()), "\tlet 日本 = 🦀");
+        let trace = [
+            PositionInfo::at_pos(None, (1, 11), 1).span(Span::new(file, 14, 18))
+        ];
+        let render = |logger: super::Logger| logger.capture().path().snippet(None::<String>).into_output();
+        let logger = super::Logger::new(MessageType::Error, &trace).sources(&sources);
+        assert_eq!(render(logger), [
+            "at main.ab:1:11",
+            "",
+            "1|     let 日本 = 🦀",
+            " |                ^^",
+            ""
+        ].join("\n"));
+        let logger = super::Logger::new(MessageType::Error, &trace)
+            .sources(&sources)
+            .columns(ColumnUnit::Utf16)
+            .tab_width(2);
+        assert_eq!(render(logger), [
+            "at main.ab:1:11",
+            "",
+            "1|   let 日本 = 🦀",
+            " |              ^^",
+            ""
+        ].join("\n"));
+        let logger = super::Logger::new(MessageType::Error, &trace)
+            .sources(&sources)
+            .columns(ColumnUnit::Display { tab_width: 8 });
+        assert!(render(logger).starts_with("at main.ab:1:20\n"));
+    }
+
+    // Render the snippet of the span in the code
+    fn render_snippet(code: &str, (start, end): (usize, usize), context: usize) -> String {
+        let mut sources = SourceMap::new();
+        let file = sources.add(None, code);
+        let (row, col) = sources.position(Span::new(file, start, end)).unwrap();
+        let trace = [PositionInfo::at_pos(None, (row, col), 0).span(Span::new(file, start, end))];
+        super::Logger::new(MessageType::Error, &trace)
+            .sources(&sources)
+            .context_lines(context)
+            .capture()
+            .snippet(None::<String>)
+            .into_output()
+    }
+
+    #[test]
+    fn test_snippet_context_lines() {
+        let code = "one\ntwo\nthree\nfour\n";
+        assert_eq!(render_snippet(code, (0, 3), 2), [
+            "",
+            "1| one",
+            " | ^^^",
+            "2| two",
+            "3| three",
+            ""
+        ].join("\n"));
+        assert_eq!(render_snippet(code, (14, 18), 1), [
+            "",
+            "3| three",
+            "4| four",
+            " | ^^^^",
+            ""
+        ].join("\n"));
+        assert_eq!(render_snippet(code, (8, 10), 0), [
+            "",
+            "3| three",
+            " | ^^",
+            ""
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_snippet_end_of_file() {
+        let code = "one\ntwo\n";
+        assert_eq!(render_snippet(code, (8, 8), 1), [
+            "",
+            "1| one",
+            "2| two",
+            " |    ^",
+            ""
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_snippet_multiline_span() {
+        let code = "let a = 'one\ntwo\nthree'\nlet b = a";
+        assert_eq!(render_snippet(code, (8, 24), 1), [
+            "",
+            "1| / let a = 'one",
+            " | |         ^^^^",
+            "2| | two",
+            "3| \\ three'",
+            " |   ^^^^^^",
+            "4|   let b = a",
+            ""
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_snippet_long_span() {
+        let code = (1..=12).map(|index| format!("line {index}")).collect::<Vec<_>>().join("\n");
+        let start = code.find("line 2").unwrap();
+        let end = code.find("line 11").unwrap() + 4;
+        assert_eq!(render_snippet(&code, (start, end), 1), [
+            "",
+            "1 |   line 1",
+            "2 | / line 2",
+            "  | | ^^^^^^",
+            "3 | | line 3",
+            "  | | ...",
+            "10| | line 10",
+            "11| \\ line 11",
+            "  |   ^^^^",
+            "12|   line 12",
+            ""
+        ].join("\n"));
+    }
 }
@@ -5,7 +5,7 @@
 //! However, if you need more specific functionality - it is encouraged to create your
 //! own implementation of such mechanism.
 
-use crate::compiling::{Metadata, Token};
+use crate::compiling::{Metadata, Token, SourceMap, Span};
 use crate::compiling::failing::logger::Logger;
 use crate::compiling::failing::position_info::PositionInfo;
 
@@ -24,11 +24,21 @@ pub enum MessageType {
     Info
 }
 
+/// Secondary location that the message refers to
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Note {
+    /// Location that the note points to
+    pub position: PositionInfo,
+    /// Explanation of the location
+    pub label: String
+}
+
 /// Logger itself
 ///
 /// Log the message you want to show to the user
 /// # Example
-/// ```should_panic
+/// ```
 /// # use heraclitus_compiler::prelude::*;
 /// # let path = Some(format!("path/to/file"));
 /// # let position = PositionInfo::at_pos(path.clone(), (0, 0), 0);
@@ -52,7 +62,16 @@ pub struct Message {
     /// Optionally store message
     pub message: Option<String>,
     /// Optionally store comment
-    pub comment: Option<String>
+    pub comment: Option<String>,
+    /// Other locations that the message refers to
+    ///
+    /// Notes are boxed so that the message and the `Failure` that carries it stay small.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[allow(clippy::box_collection)]
+    pub notes: Box<Vec<Note>>,
+    /// Sources that the trace points to
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sources: SourceMap
 }
 
 impl Message {
@@ -63,7 +82,9 @@ impl Message {
             trace: trace.iter().rev().cloned().collect(),
             code: code.cloned(),
             message: None,
-            comment: None
+            comment: None,
+            notes: Box::default(),
+            sources: SourceMap::new()
         }
     }
 
@@ -74,13 +95,30 @@ impl Message {
             trace: vec![],
             code: None,
             message: Some(message.as_ref().to_string()),
-            comment: None
+            comment: None,
+            notes: Box::default(),
+            sources: SourceMap::new()
         }
     }
 
     /// Create a new error instance at given token position if possible
     pub fn new_at_token(meta: &impl Metadata, token: Option<Token>, kind: MessageType) -> Self {
-        Self::new(meta.get_code(), &Self::get_full_trace(meta, PositionInfo::from_token(meta, token)), kind)
+        Self::new_at_position(meta, PositionInfo::from_token(meta, token), kind)
+    }
+
+    /// Create a new error instance at given position
+    ///
+    /// The code is stored only if the position does not point to any of the sources of the metadata.
+    pub fn new_at_position(meta: &impl Metadata, pos: PositionInfo, kind: MessageType) -> Self {
+        let code = match meta.get_sources() {
+            Some(sources) if pos.get_source(sources).is_some() => None,
+            _ => meta.get_code()
+        };
+        let message = Self::new(code, &Self::get_full_trace(meta, pos), kind);
+        match meta.get_sources() {
+            Some(sources) => message.sources(sources.clone()),
+            None => message
+        }
     }
 
     /* New Error Message */
@@ -121,17 +159,17 @@ impl Message {
 
     /// Create an error by supplying essential information about the location
     pub fn new_err_at_position(meta: &impl Metadata, pos: PositionInfo) -> Self {
-        Self::new(meta.get_code(), &Self::get_full_trace(meta, pos), MessageType::Error)
+        Self::new_at_position(meta, pos, MessageType::Error)
     }
 
     /// Create a warning by supplying essential information about the location
     pub fn new_warn_at_position(meta: &impl Metadata, pos: PositionInfo) -> Self {
-        Self::new(meta.get_code(), &Self::get_full_trace(meta, pos), MessageType::Warning)
+        Self::new_at_position(meta, pos, MessageType::Warning)
     }
 
     /// Create an info by supplying essential information about the location
     pub fn new_info_at_position(meta: &impl Metadata, pos: PositionInfo) -> Self {
-        Self::new(meta.get_code(), &Self::get_full_trace(meta, pos), MessageType::Info)
+        Self::new_at_position(meta, pos, MessageType::Info)
     }
 
     /* Attach additional infromation */
@@ -148,24 +186,57 @@ impl Message {
         self
     }
 
+    /// Point at another location that is related to the message
+    ///
+    /// The note is shown after the message with its own label and snippet of the code.
+    pub fn note<T: AsRef<str>>(mut self, position: PositionInfo, label: T) -> Self {
+        self.notes.push(Note { position, label: label.as_ref().to_string() });
+        self
+    }
+
+    /// Attach sources that the trace points to
+    pub fn sources(mut self, sources: SourceMap) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Get the span of the code that this message points to
+    pub fn get_span(&self) -> Option<Span> {
+        self.trace.first().and_then(|pos| pos.span)
+    }
+
     /// Shows (renders) the message while giving
     /// the ownership to this object away
     pub fn show(&self) {
+        self.log(Logger::new(self.kind.clone(), &self.trace));
+    }
+
+    /// Render the message to a plain string without the styling
+    ///
+    /// This is useful for comparing the diagnostics in tests.
+    pub fn render(&self) -> String {
+        self.log(Logger::new(self.kind.clone(), &self.trace).capture()).into_output()
+    }
+
+    // Log all the parts of the message
+    fn log(&self, logger: Logger) -> Logger {
         // If this error is based in code
         if !self.trace.is_empty() {
-            Logger::new(self.kind.clone(), &self.trace)
+            let logger = logger
+                .sources(&self.sources)
                 .header(self.kind.clone())
                 .line(self.message.clone())
                 .path()
                 .snippet(self.code.clone())
                 .line(self.comment.clone());
+            self.notes.iter().fold(logger, |logger, note| logger.note(&note.position, &note.label, self.code.clone()))
         }
         // If this error is a message error
         else {
-            Logger::new(self.kind.clone(), &self.trace)
+            logger
                 .header(self.kind.clone())
                 .line(self.message.clone())
-                .padded_line(self.comment.clone());
+                .padded_line(self.comment.clone())
         }
     }
 
@@ -194,13 +265,60 @@ mod test {
             "name = false",
             "... further code",
         ].join("\n"));
-        let path = Some(format!("path/to/file"));
+        let path = Some("path/to/file".to_string());
         let position = PositionInfo::at_pos(path.clone(), (2, 1), 4);
         let guess = "type";
-        let mut meta = DefaultMetadata::new(vec![], path, code);
-        Message::new_err_at_position(&mut meta, position)
+        let meta = DefaultMetadata::new(vec![], path, code);
+        Message::new_err_at_position(&meta, position)
             .message("Type of this parameter is invalid")
             .comment(format!("Maybe you meant type {guess} instead"))
             .show();
     }
+
+    #[test]
+    fn test_message_render() {
+        let code = Some("let a = 12\nlet b = c".to_string());
+        let path = Some("path/to/file".to_string());
+        let position = PositionInfo::at_pos(path.clone(), (2, 9), 1);
+        let meta = DefaultMetadata::new(vec![], path, code);
+        let message = Message::new_err_at_position(&meta, position)
+            .message("Variable \"c\" does not exist");
+        // Snippet is read from the sources instead of the copy of the code
+        assert_eq!(message.code, None);
+        let rendered = message.render();
+        let expected = [
+            " ERROR  Variable \"c\" does not exist",
+            "at path/to/file:2:9",
+            "",
+            "1| let a = 12",
+            "2| let b = c",
+        ];
+        assert!(rendered.starts_with(&expected.join("\n")), "{rendered}");
+        assert!(!rendered.contains('\x1b'));
+        let elsewhere = PositionInfo::at_pos(Some("other/file".to_string()), (1, 1), 1);
+        assert_eq!(Message::new_err_at_position(&meta, elsewhere).code, meta.get_code().cloned());
+    }
+
+    #[test]
+    fn test_message_note() {
+        let code = Some("let a = 12\nlet b = 1\nlet a = 2".to_string());
+        let path = Some("path/to/file".to_string());
+        let meta = DefaultMetadata::new(vec![], path.clone(), code);
+        let rendered = Message::new_err_at_position(&meta, PositionInfo::at_pos(path.clone(), (3, 5), 1))
+            .message("'a' is already defined")
+            .note(PositionInfo::at_pos(path, (1, 5), 1), "'a' was first defined here")
+            .render();
+        let expected = [
+            "note: 'a' was first defined here",
+            "at path/to/file:1:5",
+            "",
+            "1| let a = 12",
+            " |     ^",
+            "2| let b = 1",
+            ""
+        ];
+        assert!(rendered.ends_with(&expected.join("\n")), "{rendered}");
+        // Note is not a part of the trace
+        assert!(!rendered.contains("in path/to/file"));
+    }
 }
@@ -6,6 +6,7 @@ pub mod failure;
 pub mod message;
 pub mod position_info;
 pub mod logger;
+pub mod catalog;
 
 /// Macro for sending errors
 /// 
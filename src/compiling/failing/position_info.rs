@@ -5,13 +5,14 @@
 
 use std::fs::File;
 use std::io::*;
-use crate::compiling::{Metadata, Token};
+use std::sync::Arc;
+use crate::compiling::{ColumnUnit, Metadata, SourceFile, SourceMap, Token, Span};
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
 /// Store position of some error
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Position {
     /// Explicit row and column
@@ -21,11 +22,16 @@ pub enum Position {
 }
 
 /// Struct that is used to return a simple error
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PositionInfo {
     /// Path of the file
+    ///
+    /// Positions that point to a source loaded into the `SourceMap`
+    /// take the path of that source.
     pub path: Option<String>,
+    /// Location in the `SourceMap` that this position points to
+    pub span: Option<Span>,
     /// Location of this error
     pub position: Position,
     /// Length of the token
@@ -37,9 +43,18 @@ pub struct PositionInfo {
 impl PositionInfo {
     /// Create a new erorr from scratch
     pub fn new(meta: &impl Metadata, position: Position, len: usize) -> Self {
+        let source = meta.get_source();
+        let span = source.and_then(|source| {
+            let start = match position {
+                Position::Pos(row, col) => source.offset(row, col)?,
+                Position::EOF => source.code().len()
+            };
+            Some(Span::new(source.id(), start, source.advance(start, len)))
+        });
         let info = PositionInfo {
+            path: source.and_then(|source| source.path().cloned()).or_else(|| meta.get_path()),
+            span,
             position,
-            path: meta.get_path(),
             len,
             data: None
         };
@@ -48,19 +63,32 @@ impl PositionInfo {
 
     /// Create a new erorr at the end of file
     pub fn at_eof(meta: &impl Metadata) -> Self {
-        let info = PositionInfo {
-            path: meta.get_path(),
-            position: Position::EOF,
-            len: 0,
-            data: None
-        };
-        info.updated_pos(meta)
+        Self::new(meta, Position::EOF, 0)
     }
 
     /// Create a new erorr at given position
     pub fn at_pos(path: Option<String>, (row, col): (usize, usize), len: usize) -> Self {
         PositionInfo {
             path,
+            span: None,
+            position: Position::Pos(row, col),
+            len,
+            data: None
+        }
+    }
+
+    /// Create a new erorr at given span of length in characters
+    ///
+    /// Row and column are resolved through the sources of the metadata.
+    /// If the span does not point to any of them - the code of the metadata is used instead.
+    pub fn from_span(meta: &impl Metadata, span: Span, len: usize) -> Self {
+        let (path, (row, col)) = match meta.get_sources().and_then(|sources| sources.get(span.file)) {
+            Some(source) => (source.path().cloned(), source.offset_position(span.start())),
+            None => (meta.get_path(), meta.get_code().map_or((0, 0), |code| Self::get_pos_by_offset(code, span.start())))
+        };
+        PositionInfo {
+            path,
+            span: Some(span),
             position: Position::Pos(row, col),
             len,
             data: None
@@ -68,11 +96,20 @@ impl PositionInfo {
     }
 
     fn updated_pos(mut self, meta: &impl Metadata) -> Self {
-        let (row, col) = self.get_pos_by_file_or_code(meta.get_code());
+        let (row, col) = meta.get_sources()
+            .and_then(|sources| self.get_pos_by_sources(sources))
+            .or_else(|| meta.get_code().map(|code| self.get_pos_by_code(code)))
+            .unwrap_or_else(|| self.get_pos_by_code(""));
         self.position = Position::Pos(row, col);
         self
     }
 
+    /// Attach the span of the source that this position points to
+    pub fn span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
     /// Get the path to the file and return [unknown] if not supplied
     pub fn get_path(&self) -> String {
         self.path.clone().unwrap_or_else(|| "[unknown]".to_string())
@@ -85,7 +122,11 @@ impl PositionInfo {
     /// to retrieve token stored under it in metadata's expression.
     /// Then it's position is used to express the ErrorPosition
     pub fn from_metadata(meta: &impl Metadata) -> Self {
-        Self::from_token(meta, meta.get_current_token())
+        let index = meta.get_index();
+        match (meta.get_span_at(index), meta.get_word_at(index)) {
+            (Some(span), Some(word)) => Self::from_span(meta, span, word.chars().count()),
+            _ => PositionInfo::at_eof(meta)
+        }
     }
 
     /// Create an error at position of the provided token
@@ -94,7 +135,7 @@ impl PositionInfo {
     /// and error once you finished parsing the entire expression
     pub fn from_token(meta: &impl Metadata, token_opt: Option<Token>) -> Self {
         match token_opt {
-            Some(token) => PositionInfo::at_pos(meta.get_path(), token.pos, token.word.chars().count()),
+            Some(token) => Self::from_span(meta, token.span, token.word.chars().count()),
             None => PositionInfo::at_eof(meta)
         }
     }
@@ -105,23 +146,100 @@ impl PositionInfo {
     /// which can be used to express an error in a specific range
     pub fn from_between_tokens(meta: &impl Metadata, begin: Option<Token>, end: Option<Token>) -> Self {
         if let Some(begin) = begin {
-            let (row, col) = begin.pos;
-            let end = end.map_or(usize::max_value(), |tok| tok.start);
-            let len = end - begin.start;
-            PositionInfo::at_pos(meta.get_path(), (row, col), len)
+            let source = meta.get_sources().and_then(|sources| sources.get(begin.span.file));
+            // Without the end token the range spans until the end of the code
+            let end = match (end, source) {
+                (Some(end), _) => end.span.start(),
+                (None, Some(source)) => source.code().len(),
+                (None, None) => meta.get_code().map_or(begin.span.end(), |code| code.len())
+            };
+            let span = Span::new(begin.span.file, begin.span.start(), end);
+            let len = match source.and_then(|source| source.slice(span)) {
+                Some(code) => code.chars().count(),
+                None => span.len()
+            };
+            Self::from_span(meta, span, len)
         }
         else {
             PositionInfo::from_metadata(meta)
         }
     }
 
+    /// Get position of the error with the column counted in given unit
+    ///
+    /// The position is resolved through the span if it points to the source
+    /// and through the row and column (counted in characters) otherwise.
+    pub fn position_in(&self, source: &SourceFile, unit: ColumnUnit) -> Position {
+        match self.offset_in(source) {
+            Some(offset) => {
+                let (row, col) = source.offset_position_in(offset, unit);
+                Position::Pos(row, col)
+            }
+            None => self.position.clone()
+        }
+    }
+
+    /// Get length of the error with the length counted in given unit
+    ///
+    /// With the display width the tabs are expanded relative to the beginning of the line.
+    pub fn len_in(&self, source: &SourceFile, unit: ColumnUnit) -> usize {
+        let Some(start) = self.offset_in(source) else {
+            return self.len
+        };
+        let code = source.code();
+        // Span can reach past the end of the source that it was created for
+        let end = match self.span {
+            Some(span) if span.file == source.id() => span.end().min(code.len()).max(start),
+            _ => source.advance(start, self.len)
+        };
+        let (row, _) = source.offset_position(start);
+        let line_start = source.offset(row, 1).unwrap_or(start);
+        unit.measure(&code[line_start..end]) - unit.measure(&code[line_start..start])
+    }
+
+    // Get byte index of the error in the source
+    fn offset_in(&self, source: &SourceFile) -> Option<usize> {
+        match (&self.span, &self.position) {
+            (Some(span), _) if span.file == source.id() => Some(span.start().min(source.code().len())),
+            (_, Position::Pos(row, col)) => source.offset(*row, *col),
+            (_, Position::EOF) => {
+                let (row, col) = source.eof_position();
+                source.offset(row, col)
+            }
+        }
+    }
+
     /// Attach additional data in form of a string
     pub fn data<T: AsRef<str>>(mut self, data: T) -> Self {
         self.data = Some(data.as_ref().to_string());
         self
     }
 
+    /// Get the source that this position points to
+    ///
+    /// This is the source that the span points to
+    /// or the source loaded from the path of this position.
+    pub fn get_source<'a>(&self, sources: &'a SourceMap) -> Option<&'a Arc<SourceFile>> {
+        let file = match self.span {
+            Some(span) => span.file,
+            None => sources.find(self.path.as_ref()?)?
+        };
+        sources.get(file)
+    }
+
+    /// Get position of the error through the sources
+    ///
+    /// End of file is resolved in the source that this position points to.
+    pub fn get_pos_by_sources(&self, sources: &SourceMap) -> Option<(usize, usize)> {
+        match self.position {
+            Position::Pos(row, col) => Some((row, col)),
+            Position::EOF => Some(self.get_source(sources)?.eof_position())
+        }
+    }
+
     /// Get position of the error by either path or code
+    #[deprecated(note = "reads the file from disk, load it into a `SourceMap` and use `get_pos_by_sources` instead")]
+    #[allow(deprecated)]
     pub fn get_pos_by_file_or_code(&self, code: Option<&String>) -> (usize, usize) {
         match self.position {
             Position::Pos(row, col) => (row, col),
@@ -143,6 +261,7 @@ impl PositionInfo {
     }
 
     /// In case of EOF this function ensures you to return concrete position
+    #[deprecated(note = "reads the file from disk, load it into a `SourceMap` and use `get_pos_by_sources` instead")]
     pub fn get_pos_by_file(&self, path: impl AsRef<str>) -> std::io::Result<(usize, usize)> {
         let mut code = String::new();
         let mut file = File::open(path.as_ref())?;
@@ -150,6 +269,14 @@ impl PositionInfo {
         Ok(self.get_pos_by_code(&code))
     }
 
+    // Get position (row, column) of the character under given byte index
+    fn get_pos_by_offset(code: &str, offset: usize) -> (usize, usize) {
+        let before = code.get(..offset).unwrap_or(code);
+        let row = before.matches('\n').count() + 1;
+        let col = before.rsplit('\n').next().map_or(0, |line| line.chars().count()) + 1;
+        (row, col)
+    }
+
     /// In case of EOF this function ensures you to return concrete position
     pub fn get_pos_by_code(&self, code: impl AsRef<str>) -> (usize, usize) {
         let code = code.as_ref();
@@ -172,7 +299,7 @@ impl PositionInfo {
 
 #[cfg(test)]
 mod test {
-    use crate::prelude::DefaultMetadata;
+    use crate::prelude::{DefaultMetadata, FileId, SourceMap};
     use super::*;
 
     #[test]
@@ -182,13 +309,73 @@ mod test {
         assert_eq!(pos.get_pos_by_code("test"), (1, 1));
     }
 
+    #[test]
+    fn test_position_info_by_sources() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(Some("main.ab".to_string()), "let a\nlet b\n");
+        let eof = |path: &str| PositionInfo { position: Position::EOF, ..PositionInfo::at_pos(Some(path.to_string()), (0, 0), 0) };
+        assert_eq!(eof("main.ab").get_pos_by_sources(&sources), Some((2, 6)));
+        assert_eq!(eof("other.ab").get_pos_by_sources(&sources), None);
+        assert_eq!(eof("other.ab").span(Span::new(file, 0, 0)).get_pos_by_sources(&sources), Some((2, 6)));
+        assert_eq!(PositionInfo::at_pos(None, (1, 2), 1).get_pos_by_sources(&sources), Some((1, 2)));
+    }
+
     #[test]
     fn test_position_info_between_tokens() {
-        let begin = Token { word: "begin".to_string(), pos: (1, 1), start: 0 };
-        let to = Token { word: "to".to_string(), pos: (1, 7), start: 6 };
-        let end = Token { word: "end".to_string(), pos: (1, 10), start: 9 };
-        let mut meta = DefaultMetadata::new(vec![begin.clone(), to.clone(), end.clone()], None, Some("begin to end".to_string()));
-        let pos = PositionInfo::from_between_tokens(&mut meta, Some(begin.clone()), Some(end.clone()));
-        assert_eq!(pos.len, end.start - begin.start);
+        let begin = Token::new("begin", Span::new(FileId(0), 0, 5));
+        let to = Token::new("to", Span::new(FileId(0), 6, 8));
+        let end = Token::new("end", Span::new(FileId(0), 9, 12));
+        let meta = DefaultMetadata::new(vec![begin.clone(), to.clone(), end.clone()], None, Some("begin to end".to_string()));
+        let pos = PositionInfo::from_between_tokens(&meta, Some(begin.clone()), Some(end.clone()));
+        assert_eq!(pos.len, end.span.start() - begin.span.start());
+        assert_eq!(pos.span, Some(Span::new(FileId(0), 0, 9)));
+        let pos = PositionInfo::from_between_tokens(&meta, Some(to), None);
+        assert_eq!((pos.position, pos.len), (Position::Pos(1, 7), 6));
+    }
+
+    #[test]
+    fn test_position_info_from_span() {
+        let code = "let a\nlet 🎉 = a".to_string();
+        let token = Token::new("=", Span::new(FileId(0), 15, 16));
+        let meta = DefaultMetadata::new(vec![], Some("main.ab".to_string()), Some(code.clone()));
+        let pos = PositionInfo::from_token(&meta, Some(token.clone()));
+        // Path is resolved through the sources of the metadata
+        assert_eq!((pos.get_path(), pos.position), ("main.ab".to_string(), Position::Pos(2, 7)));
+        // Span that does not point to any of the sources falls back to the code of the metadata
+        let token = Token::new("=", Span::new(FileId(7), 15, 16));
+        let pos = PositionInfo::from_token(&meta, Some(token));
+        assert_eq!((pos.get_path(), pos.position), ("main.ab".to_string(), Position::Pos(2, 7)));
+        // Spans of the other sources take the path of their source
+        let mut sources = meta.sources.clone();
+        let module = sources.add(Some("module.ab".to_string()), "import main");
+        let meta = DefaultMetadata { sources, ..DefaultMetadata::new(vec![], Some("main.ab".to_string()), None) };
+        let pos = PositionInfo::from_span(&meta, Span::new(module, 7, 11), 4);
+        assert_eq!((pos.get_path(), pos.position), ("module.ab".to_string(), Position::Pos(1, 8)));
+        assert_eq!(PositionInfo::at_eof(&meta).get_path(), "main.ab");
+    }
+
+    #[test]
+    fn test_position_info_in_unit() {
+        let code = "let a\n\tlet 🎉 = a";
+        let meta = DefaultMetadata::new(vec![], None, Some(code.to_string()));
+        let source = meta.get_source().unwrap().clone();
+        let emoji = PositionInfo::from_token(&meta, Some(Token::new("🎉", Span::new(source.id(), 11, 15))));
+        let display = ColumnUnit::Display { tab_width: 4 };
+        assert_eq!(emoji.position, Position::Pos(2, 6));
+        assert_eq!(emoji.position_in(&source, ColumnUnit::Utf16), Position::Pos(2, 6));
+        assert_eq!(emoji.position_in(&source, display), Position::Pos(2, 9));
+        assert_eq!(emoji.len_in(&source, ColumnUnit::Utf16), 2);
+        assert_eq!(emoji.len_in(&source, display), 2);
+        assert_eq!(emoji.len_in(&source, ColumnUnit::Bytes), 4);
+        let tab = PositionInfo::at_pos(None, (2, 1), 4);
+        assert_eq!(tab.position_in(&source, display), Position::Pos(2, 1));
+        assert_eq!(tab.len_in(&source, display), 7);
+        let eof = PositionInfo::at_eof(&meta);
+        assert_eq!(eof.position_in(&source, display), Position::Pos(2, 15));
+        // Spans past the end of the source are clamped to it
+        let past = PositionInfo::from_token(&meta, Some(Token::new("= a", Span::new(source.id(), 16, 40))));
+        assert_eq!(past.len_in(&source, ColumnUnit::Bytes), 3);
+        let outside = PositionInfo::from_token(&meta, Some(Token::new("a", Span::new(source.id(), 30, 35))));
+        assert_eq!(outside.len_in(&source, display), 0);
     }
 }
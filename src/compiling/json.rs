@@ -0,0 +1,36 @@
+//! JSON helpers
+//!
+//! Tracer, highlighting grammars and source maps write their JSON by hand
+//! and share the escaping of the strings from here.
+
+use std::fmt::Write;
+
+/// Escape the text to be written inside of a JSON string
+pub(crate) fn escape_json(text: &str) -> String {
+    let mut result = String::new();
+    for letter in text.chars() {
+        match letter {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            letter if letter.is_control() => {
+                let _ = write!(result, "\\u{:04x}", letter as u32);
+            }
+            letter => result.push(letter)
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escapes_json() {
+        assert_eq!(escape_json("say \"hi\"\n"), "say \\\"hi\\\"\\n");
+        assert_eq!(escape_json("a\\b\r\u{1}"), "a\\\\b\\r\\u0001");
+    }
+}
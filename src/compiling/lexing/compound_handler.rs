@@ -55,8 +55,7 @@ impl CompoundHandler {
             // check if future symbol satisfies at least one
             for entry in entries.iter() {
                 // Get future string of current letter and the next one
-                if let Some(future) = reader.get_future(2) {
-                    let future_letter = future.chars().nth(1).unwrap();
+                if let Some(future_letter) = reader.get_future(2).and_then(|future| future.chars().nth(1)) {
                     // Check if next character matches our desired symbol
                     if future_letter == *entry {
                         // If we matched before as well then this means
@@ -107,7 +106,7 @@ mod test {
             CompoundReaction::Begin,
             CompoundReaction::End,
         ];
-        let code = format!("!<=><=");
+        let code = "!<=><=".to_string();
         let mut reader = Reader::new(&code);
         let symbols = vec!['<', '=', '>'];
         let compounds = vec![('<', '='), ('=', '>')];
@@ -2,16 +2,18 @@
 //!
 //! This module contains the  lexer that is used to tokenize the source code
 
+use std::borrow::Cow;
+
 use crate::{
-    compiling_rules::Rules,
-    prelude::{PositionInfo, ScopingMode, SeparatorMode, Token},
+    compiling_rules::{RegionMessages, Rules},
+    prelude::{BorrowedToken, FileId, Interner, PositionInfo, ScopingMode, SeparatorMode, SourceFile, Span, Token},
 };
 
 use super::{
     compound_handler::{CompoundHandler, CompoundReaction},
     reader::Reader,
     region_handler::{RegionHandler, RegionReaction},
-    LexerError, LexerErrorType,
+    ErrorRegion, Incomplete, LexerError, LexerErrorType,
 };
 
 ///  Lexer
@@ -28,14 +30,24 @@ pub struct Lexer {
     pub scoping_mode: ScopingMode,
 }
 
-struct LexState {
+// Token recognized by the lexer before its word is created
+struct LexToken {
+    span: Span,
+    // Text of the token if it differs from the source
+    word: Option<String>,
+}
+
+struct LexState<'a> {
+    file: FileId,
+    path: Option<String>,
     word: String,
     is_indenting: bool,
     is_escaped: bool,
-    token_start_index: usize,
-    position: (usize, usize),
-    reader: Reader,
-    lexem: Vec<Token>,
+    token_start: usize,
+    reader: Reader<'a>,
+    lexem: Vec<LexToken>,
+    errors: Vec<LexerError>,
+    region_begins: Vec<((usize, usize), usize)>,
     region_handler: RegionHandler,
     compound_handler: CompoundHandler,
 }
@@ -59,17 +71,29 @@ impl Lexer {
             return;
         }
 
-        // Getting position by word here would attempt to
-        // substract with overflow since the new line character
-        // technically belongs to the previous line
+        // The indentation token starts with the new line character just like its word
+        // but the error is reported from the beginning of the indented line
         let (row, _col) = lex_state.reader.get_position();
-        lex_state.lexem.push(Token {
-            word: lex_state.word.clone(),
-            pos: (row, 1),
-            start: lex_state.token_start_index,
-        });
-        lex_state.position = (0, 0);
-        lex_state.word = String::new();
+        // Indentation should consist either of tabs or spaces
+        if lex_state.word.contains(' ') && lex_state.word.contains('\t') {
+            let len = lex_state.word.chars().count() - 1;
+            let start = lex_state.token_start + '\n'.len_utf8();
+            self.error(lex_state, LexerErrorType::MixedIndentation, (row, 1), start, len, None);
+        }
+        let end = lex_state.reader.get_end_offset();
+        self.push_token(lex_state, end);
+    }
+
+    /// Push the current word to the lexem as a token that ends at given byte index
+    #[inline]
+    fn push_token(&self, lex_state: &mut LexState, end: usize) {
+        let span = Span::new(lex_state.file, lex_state.token_start, end);
+        let word = match lex_state.reader.code.get(span.range()) {
+            Some(slice) if slice == lex_state.word => None,
+            _ => Some(lex_state.word.clone())
+        };
+        lex_state.lexem.push(LexToken { span, word });
+        lex_state.word.clear();
     }
 
     /// Add word that has been completed in previous iteration to the lexem
@@ -79,13 +103,8 @@ impl Lexer {
             return;
         }
 
-        lex_state.lexem.push(Token {
-            word: lex_state.word.clone(),
-            pos: lex_state.position,
-            start: lex_state.token_start_index,
-        });
-        lex_state.position = (0, 0);
-        lex_state.word = String::new();
+        let end = lex_state.reader.get_offset();
+        self.push_token(lex_state, end);
     }
 
     /// Add word that has been completed in current iteration to the lexem
@@ -95,13 +114,8 @@ impl Lexer {
             return;
         }
 
-        lex_state.lexem.push(Token {
-            word: lex_state.word.clone(),
-            pos: lex_state.position,
-            start: lex_state.token_start_index,
-        });
-        lex_state.position = (0, 0);
-        lex_state.word = String::new()
+        let end = lex_state.reader.get_end_offset();
+        self.push_token(lex_state, end);
     }
 
     /// Pattern code for adding a symbol
@@ -110,11 +124,7 @@ impl Lexer {
     fn pattern_add_symbol(&self, lex_state: &mut LexState, letter: char) {
         self.add_word(lex_state);
 
-        if lex_state.word.is_empty() {
-            lex_state.token_start_index = lex_state.reader.get_index();
-        }
         self.word_push(lex_state, letter);
-        lex_state.position = lex_state.reader.get_position();
 
         self.add_word_inclusively(lex_state);
     }
@@ -138,7 +148,7 @@ impl Lexer {
     /// Push letter to the word and set token start index
     fn word_push(&self, lex_state: &mut LexState, letter: char) {
         if lex_state.word.is_empty() {
-            lex_state.token_start_index = lex_state.reader.get_index();
+            lex_state.token_start = lex_state.reader.get_offset();
         }
         lex_state.word.push(letter);
     }
@@ -147,36 +157,176 @@ impl Lexer {
     ///
     /// Run lexer and tokenize code. The result is stored in the lexem attribute
     pub fn tokenize(&self, input: &str) -> Result<Vec<Token>, LexerError> {
-        let code = input.to_string();
+        let (tokens, errors) = self.tokenize_with(input, self.path.clone(), FileId::default(), false);
+        Self::first_error(Self::owned_tokens(input, tokens), errors)
+    }
+
+    /// Tokenize source loaded into the `SourceMap`
+    ///
+    /// Tokens and errors produced by this method point to the given source.
+    pub fn tokenize_source(&self, source: &SourceFile) -> Result<Vec<Token>, LexerError> {
+        let path = source.path().cloned().or_else(|| self.path.clone());
+        let (tokens, errors) = self.tokenize_with(source.code(), path, source.id(), false);
+        Self::first_error(Self::owned_tokens(source.code(), tokens), errors)
+    }
+
+    /// Tokenize source code without stopping on errors
+    ///
+    /// Lexer records every error it encounters and recovers from it, so that
+    /// all the tokens that could be recognized are returned alongside the errors.
+    pub fn tokenize_all(&self, input: &str) -> (Vec<Token>, Vec<LexerError>) {
+        let (tokens, errors) = self.tokenize_with(input, self.path.clone(), FileId::default(), true);
+        (Self::owned_tokens(input, tokens), errors)
+    }
+
+    /// Tokenize source loaded into the `SourceMap` without stopping on errors
+    pub fn tokenize_source_all(&self, source: &SourceFile) -> (Vec<Token>, Vec<LexerError>) {
+        let path = source.path().cloned().or_else(|| self.path.clone());
+        let (tokens, errors) = self.tokenize_with(source.code(), path, source.id(), true);
+        (Self::owned_tokens(source.code(), tokens), errors)
+    }
+
+    /// Tokenize source code into tokens that borrow their words from it
+    ///
+    /// This avoids allocating a word for every token which makes a difference for large sources.
+    pub fn tokenize_borrowed<'src>(&self, input: &'src str) -> Result<Vec<BorrowedToken<'src>>, LexerError> {
+        let (tokens, errors) = self.tokenize_with(input, self.path.clone(), FileId::default(), false);
+        Self::first_error(Self::borrowed_tokens(input, tokens), errors)
+    }
+
+    /// Tokenize source code into borrowed tokens without stopping on errors
+    pub fn tokenize_borrowed_all<'src>(&self, input: &'src str) -> (Vec<BorrowedToken<'src>>, Vec<LexerError>) {
+        let (tokens, errors) = self.tokenize_with(input, self.path.clone(), FileId::default(), true);
+        (Self::borrowed_tokens(input, tokens), errors)
+    }
+
+    /// Check if the code is incomplete and more lines are expected
+    ///
+    /// Code is incomplete if some region has not been closed, some bracket of the rules
+    /// has not been closed or, in the indentation scoping mode, if the last line is indented.
+    /// Errors that cannot be fixed by supplying more code are not considered incomplete.
+    pub fn incomplete(&self, input: &str) -> Option<Incomplete> {
+        let tokens = match self.tokenize(input) {
+            Ok(tokens) => tokens,
+            Err((LexerErrorType::Unclosed(region), _)) => return Some(Incomplete::UnclosedRegion(region.name)),
+            Err((LexerErrorType::UnterminatedEscape, info)) => return Some(Incomplete::UnclosedRegion(info.data.unwrap_or_default())),
+            Err(_) => return None
+        };
+        let mut brackets = vec![];
+        // Indentation of the last line that does not continue inside of brackets
+        let mut indentation = "";
+        for token in tokens.iter() {
+            if brackets.is_empty() && token.word.starts_with('\n') {
+                indentation = &token.word[1..];
+            }
+            let mut letters = token.word.chars();
+            let (Some(letter), None) = (letters.next(), letters.next()) else {
+                continue
+            };
+            if let Some((begin, _)) = self.rules.brackets.iter().find(|(begin, _)| *begin == letter) {
+                brackets.push(*begin);
+            }
+            else if let Some((begin, _)) = self.rules.brackets.iter().find(|(_, end)| *end == letter) {
+                // Mismatched bracket is an error that more code will not fix
+                if brackets.pop() != Some(*begin) {
+                    return None
+                }
+            }
+        }
+        if let Some(bracket) = brackets.pop() {
+            return Some(Incomplete::OpenBracket(bracket))
+        }
+        // Blank line concludes the indented block
+        if let ScopingMode::Indent = self.scoping_mode {
+            let line = input.rsplit('\n').next().unwrap_or_default();
+            if !indentation.is_empty() && !line.trim().is_empty() {
+                return Some(Incomplete::IndentBlock)
+            }
+        }
+        None
+    }
+
+    // Create tokens with interned words
+    fn owned_tokens(input: &str, tokens: Vec<LexToken>) -> Vec<Token> {
+        let mut interner = Interner::new();
+        tokens.into_iter()
+            .map(|token| {
+                let word = token.word.as_deref().unwrap_or(&input[token.span.range()]);
+                Token::new(interner.intern(word), token.span)
+            })
+            .collect()
+    }
+
+    // Create tokens with words borrowed from the source
+    fn borrowed_tokens(input: &str, tokens: Vec<LexToken>) -> Vec<BorrowedToken<'_>> {
+        tokens.into_iter()
+            .map(|token| match token.word {
+                Some(word) => BorrowedToken::new(word, token.span),
+                None => BorrowedToken::new(Cow::Borrowed(&input[token.span.range()]), token.span)
+            })
+            .collect()
+    }
+
+    // Return the first error if any occurred
+    fn first_error<T>(tokens: Vec<T>, errors: Vec<LexerError>) -> Result<Vec<T>, LexerError> {
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(tokens)
+        }
+    }
+
+    /// Record an error at given position that starts at given byte index
+    fn error(&self, lex_state: &mut LexState, kind: LexerErrorType, pos: (usize, usize), start: usize, len: usize, data: Option<&str>) {
+        let span = Span::new(lex_state.file, start, lex_state.reader.advance(start, len));
+        let mut info = PositionInfo::at_pos(lex_state.path.clone(), pos, len).span(span);
+        if let Some(data) = data {
+            info = info.data(data);
+        }
+        lex_state.errors.push((kind, info));
+    }
+
+    /// Describe the current region for an error
+    fn error_region(&self, lex_state: &LexState, message: impl Fn(&RegionMessages) -> Option<String>) -> Box<ErrorRegion> {
+        let region = lex_state.region_handler.get_region().unwrap();
+        let messages = self.rules.region_messages.get(&region.id);
+        let (begin, start) = lex_state.region_begins.last().copied().unwrap_or(((0, 0), 0));
+        let len = region.begin.chars().count();
+        let span = Span::new(lex_state.file, start, lex_state.reader.advance(start, len));
+        Box::new(ErrorRegion {
+            name: region.name.clone(),
+            end: region.end.clone(),
+            message: messages.and_then(message),
+            help: messages.and_then(|messages| messages.help.clone()),
+            begin: PositionInfo::at_pos(lex_state.path.clone(), begin, len).span(span)
+        })
+    }
+
+    /// Check if the character should never appear in the code
+    #[inline]
+    fn is_invalid_char(letter: char) -> bool {
+        letter.is_control() && !['\n', '\t', '\r'].contains(&letter)
+    }
 
+    fn tokenize_with(&self, input: &str, path: Option<String>, file: FileId, tolerant: bool) -> (Vec<LexToken>, Vec<LexerError>) {
         let mut lex_state = LexState {
+            file,
+            path,
             word: String::new(),
             is_indenting: false,
             is_escaped: false,
-            token_start_index: 0,
-            position: (0, 0),
+            token_start: 0,
             lexem: Vec::new(),
-            reader: Reader::new(&code),
+            errors: Vec::new(),
+            region_begins: Vec::new(),
+            reader: Reader::new(input),
             region_handler: RegionHandler::new(&self.rules),
             compound_handler: CompoundHandler::new(&self.rules),
         };
 
         while let Some(letter) = lex_state.reader.next() {
-            /****************/
-            /* Set Position */
-            /****************/
-
-            // If the new position hasn't been set yet, set it
-            if lex_state.position == (0, 0) {
-                // If separator mode is set to Manual and the letter is a separator,
-                // then skip finding a new position
-                if SeparatorMode::Manual != self.separator_mode || letter != '\n' {
-                    let region = lex_state.region_handler.get_region().unwrap();
-                    // If the region is tokenized, then check if the letter is a separator
-                    if !region.tokenize || !vec![' ', '\t'].contains(&letter) {
-                        lex_state.position = lex_state.reader.get_position();
-                    }
-                }
+            // Stop on the first error unless we want to collect them all
+            if !tolerant && !lex_state.errors.is_empty() {
+                break;
             }
 
             // Reaction stores the reaction of the region handler
@@ -189,10 +339,16 @@ impl Lexer {
 
             lex_state.is_escaped = !lex_state.is_escaped && letter == self.escape_symbol;
 
+            // Region boundary concludes the indentation that precedes it
+            if !matches!(reaction, RegionReaction::Pass) {
+                lex_state.is_indenting = false;
+            }
+
             match reaction {
                 // If the region has been opened
                 // Finish the part that we have been parsing
                 RegionReaction::Begin(tokenize) => {
+                    lex_state.region_begins.push((lex_state.reader.get_position(), lex_state.reader.get_offset()));
                     // Also if the new region is an interpolation that tokenizes
                     // the inner content - separate the region from the content
                     if tokenize {
@@ -213,6 +369,7 @@ impl Lexer {
                 // If the region has been closed
                 // Add the closing region and finish the word
                 RegionReaction::End(tokenize) => {
+                    lex_state.region_begins.pop();
                     // Also if the new region is an interpolation that tokenizes
                     // the inner content - separate the region from the content
                     if tokenize {
@@ -233,6 +390,7 @@ impl Lexer {
                 RegionReaction::Pass => {
                     let region = lex_state.region_handler.get_region().unwrap();
                     let is_tokenized_region = region.tokenize;
+                    let is_singleline_region = region.singleline;
 
                     match lex_state.compound_handler.handle_compound(
                         letter,
@@ -246,92 +404,139 @@ impl Lexer {
                             // Handle region scope
                             if !is_tokenized_region {
                                 // Handle singleline attribute
-                                if region.singleline && letter == '\n' {
+                                if is_singleline_region && letter == '\n' {
                                     let pos = lex_state.reader.get_position();
-                                    return Err((
-                                        LexerErrorType::Singleline,
-                                        PositionInfo::at_pos(self.path.clone(), pos, 0)
-                                            .data(region.name.clone()),
-                                    ));
-                                }
-                                self.word_push(&mut lex_state, letter);
-                            } else {
-                                /******************/
-                                /* Mode modifiers */
-                                /******************/
-
-                                // Create indent regions: '\n   '
-                                if let ScopingMode::Indent = self.scoping_mode {
-                                    // If we are still in the indent region - proceed
-                                    if lex_state.is_indenting && vec![' ', '\t'].contains(&letter) {
-                                        self.word_push(&mut lex_state, letter);
-                                    }
-                                    // If it's the new line - start indent region
-                                    if letter == '\n' {
-                                        lex_state.is_indenting = true;
-                                        self.pattern_begin(&mut lex_state, letter);
-                                    }
-                                    // Check if the current letter
-                                    // concludes current indent region
-                                    if lex_state.is_indenting {
-                                        if let Some(next_char) = lex_state.reader.peek() {
-                                            if !vec![' ', '\t'].contains(&next_char) {
-                                                self.add_indent(&mut lex_state);
-                                                lex_state.is_indenting = false;
-                                            }
-                                        }
-                                        continue;
-                                    }
-                                }
-                                // Skip newline character if we want to manually insert semicolons
-                                if let SeparatorMode::Manual = self.separator_mode {
-                                    if letter == '\n' {
-                                        self.add_word(&mut lex_state);
-                                        continue;
-                                    }
-                                }
-
-                                /*****************/
-                                /* Regular Lexer */
-                                /*****************/
-
-                                // Skip whitespace
-                                if vec![' ', '\t'].contains(&letter) {
+                                    let start = lex_state.reader.get_offset();
+                                    let region = self.error_region(&lex_state, |messages| messages.singleline.clone());
+                                    let name = region.name.clone();
+                                    self.error(&mut lex_state, LexerErrorType::Singleline(region), pos, start, 0, Some(&name));
+                                    // Recover by closing the region at the end of the line
                                     self.add_word(&mut lex_state);
+                                    lex_state.region_handler.close_region();
+                                    lex_state.region_begins.pop();
+                                    self.handle_code(&mut lex_state, letter);
                                 }
-                                // Handle special symbols
-                                else if self.rules.symbols.contains(&letter) || letter == '\n' {
-                                    self.pattern_add_symbol(&mut lex_state, letter);
+                                // Handle characters that cannot appear inside of a region
+                                else if Self::is_invalid_char(letter) {
+                                    let pos = lex_state.reader.get_position();
+                                    let start = lex_state.reader.get_offset();
+                                    let name = lex_state.region_handler.get_region().unwrap().name.clone();
+                                    self.error(&mut lex_state, LexerErrorType::InvalidCharacter(letter), pos, start, 1, Some(&name));
                                 }
-                                // Handle word
                                 else {
                                     self.word_push(&mut lex_state, letter);
                                 }
+                            } else {
+                                self.handle_code(&mut lex_state, letter);
                             }
                         }
                     }
                 }
             }
         }
+        if !tolerant && !lex_state.errors.is_empty() {
+            return (lex_state.lexem, lex_state.errors);
+        }
         self.add_word(&mut lex_state);
+        let region = lex_state.region_handler.get_region().unwrap();
+        // If the code ends with a dangling escape symbol inside of a region
+        if lex_state.is_escaped && !region.tokenize {
+            let pos = lex_state.reader.get_position();
+            let start = lex_state.reader.get_offset();
+            let name = region.name.clone();
+            self.error(&mut lex_state, LexerErrorType::UnterminatedEscape, pos, start, 0, Some(&name));
+        }
         // If some region exists that was not closed
         if let Err((pos, region)) = lex_state.region_handler.is_region_closed(&lex_state.reader) {
-            return Err((
-                LexerErrorType::Unclosed,
-                PositionInfo::at_pos(self.path.clone(), pos, 0).data(region.name),
-            ));
+            let name = region.name.clone();
+            let start = lex_state.reader.get_offset();
+            let region = self.error_region(&lex_state, |messages| messages.unclosed.clone());
+            self.error(&mut lex_state, LexerErrorType::Unclosed(region), pos, start, 0, Some(&name));
+        }
+
+        (lex_state.lexem, lex_state.errors)
+    }
+
+    /// Handle a letter of the code that is tokenized
+    fn handle_code(&self, lex_state: &mut LexState, letter: char) {
+        // Skip characters that cannot appear in the code
+        if Self::is_invalid_char(letter) {
+            self.add_word(lex_state);
+            let pos = lex_state.reader.get_position();
+            let start = lex_state.reader.get_offset();
+            self.error(lex_state, LexerErrorType::UnexpectedCharacter(letter), pos, start, 1, None);
+            return;
+        }
+
+        /******************/
+        /* Mode modifiers */
+        /******************/
+
+        // Create indent regions: '\n   '
+        if let ScopingMode::Indent = self.scoping_mode {
+            // If we are still in the indent region - proceed
+            if lex_state.is_indenting && [' ', '\t'].contains(&letter) {
+                self.word_push(lex_state, letter);
+            }
+            // If it's the new line - start indent region
+            if letter == '\n' {
+                lex_state.is_indenting = true;
+                self.pattern_begin(lex_state, letter);
+            }
+            // Check if the current letter
+            // concludes current indent region
+            if lex_state.is_indenting {
+                if let Some(next_char) = lex_state.reader.peek() {
+                    if ![' ', '\t'].contains(&next_char) {
+                        self.add_indent(lex_state);
+                        lex_state.is_indenting = false;
+                    }
+                }
+                return;
+            }
+        }
+        // Skip newline character if we want to manually insert semicolons
+        if let SeparatorMode::Manual = self.separator_mode {
+            if letter == '\n' {
+                self.add_word(lex_state);
+                return;
+            }
         }
 
-        Ok(lex_state.lexem)
+        /*****************/
+        /* Regular Lexer */
+        /*****************/
+
+        // Skip whitespace
+        if [' ', '\t'].contains(&letter) {
+            self.add_word(lex_state);
+        }
+        // Handle special symbols
+        else if self.rules.symbols.contains(&letter) || letter == '\n' {
+            self.pattern_add_symbol(lex_state, letter);
+        }
+        // Handle word
+        else {
+            self.word_push(lex_state, letter);
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::compiling::ScopingMode;
+    use crate::compiling::{ScopingMode, LexerErrorType};
+    use crate::compiling::failing::position_info::Position;
     use crate::compiling_rules::{Region, Rules};
+    use crate::compiling::{SourceMap, Token};
     use crate::reg;
 
+    // Resolve position of the token in the code
+    fn position(code: &str, token: &Token) -> (usize, usize) {
+        let mut sources = SourceMap::new();
+        let file = sources.add(None, code);
+        token.pos(sources.get(file).unwrap())
+    }
+
     #[test]
     fn test_lexer_base() {
         let symbols = vec!['(', ')'];
@@ -358,10 +563,12 @@ mod test {
         let lexer = super::Lexer::new(rules);
         let mut result = vec![];
         // Simulate lexing
-        let res = lexer.tokenize("let a = (12 + 32)");
+        let code = "let a = (12 + 32)";
+        let res = lexer.tokenize(code);
         assert!(res.is_ok());
         for lex in res.unwrap() {
-            result.push((lex.word, lex.pos.0, lex.pos.1));
+            let (row, col) = position(code, &lex);
+            result.push((lex.word.to_string(), row, col));
         }
         assert_eq!(expected, result);
     }
@@ -398,10 +605,12 @@ mod test {
         let lexer = super::Lexer::new(rules);
         let mut result = vec![];
         // Simulate lexing
-        let res = lexer.tokenize("let a = 'this {'is {adjective} long'} 🎉 text'");
+        let code = "let a = 'this {'is {adjective} long'} 🎉 text'";
+        let res = lexer.tokenize(code);
         assert!(res.is_ok());
         for lex in res.unwrap() {
-            result.push((lex.word, lex.pos.0, lex.pos.1));
+            let (row, col) = position(code, &lex);
+            result.push((lex.word.to_string(), row, col));
         }
         assert_eq!(expected, result);
     }
@@ -414,11 +623,12 @@ mod test {
             ("if".to_string(), (1, 1), 0),
             ("condition".to_string(), (1, 4), 3),
             (":".to_string(), (1, 13), 12),
-            ("\n    ".to_string(), (2, 1), 13),
+            // Indentation starts with the new line character that ends the previous line
+            ("\n    ".to_string(), (1, 14), 13),
             ("if".to_string(), (2, 5), 18),
             ("subcondition".to_string(), (2, 8), 21),
             (":".to_string(), (2, 20), 33),
-            ("\n        ".to_string(), (3, 1), 34),
+            ("\n        ".to_string(), (2, 21), 34),
             ("pass".to_string(), (3, 9), 43),
         ];
         let rules = Rules::new(symbols, vec![], regions);
@@ -427,11 +637,11 @@ mod test {
         lexer.scoping_mode = ScopingMode::Indent;
         let mut result = vec![];
         // Simulate lexing
-        let res = lexer
-            .tokenize(&vec!["if condition:", "    if subcondition:", "        pass"].join("\n"));
+        let code = ["if condition:", "    if subcondition:", "        pass"].join("\n");
+        let res = lexer.tokenize(&code);
         assert!(res.is_ok());
         for lex in res.unwrap() {
-            result.push((lex.word, (lex.pos.0, lex.pos.1), lex.start));
+            result.push((lex.word.to_string(), position(&code, &lex), lex.span.start()));
         }
         assert_eq!(expected, result);
     }
@@ -453,10 +663,12 @@ mod test {
         let lexer = super::Lexer::new(rules);
         let mut result = vec![];
         // Simulate lexing
-        let res = lexer.tokenize(&vec!["let age = 12", "+", "12;"].join("\n"));
+        let code = ["let age = 12", "+", "12;"].join("\n");
+        let res = lexer.tokenize(&code);
         assert!(res.is_ok());
         for lex in res.unwrap() {
-            result.push((lex.word, lex.pos.0, lex.pos.1));
+            let (row, col) = position(&code, &lex);
+            result.push((lex.word.to_string(), row, col));
         }
         assert_eq!(expected, result);
     }
@@ -473,10 +685,12 @@ mod test {
         let lexer = super::Lexer::new(rules);
         let mut result = vec![];
         // Simulate lexing
-        let res = lexer.tokenize(&vec!["'this", "is", "a", "multiline", "string'"].join("\n"));
+        let code = ["'this", "is", "a", "multiline", "string'"].join("\n");
+        let res = lexer.tokenize(&code);
         assert!(res.is_ok());
         for lex in res.unwrap() {
-            result.push((lex.word, lex.pos.0, lex.pos.1));
+            let (row, col) = position(&code, &lex);
+            result.push((lex.word.to_string(), row, col));
         }
         assert_eq!(expected, result);
     }
@@ -493,10 +707,12 @@ mod test {
         let lexer = super::Lexer::new(rules);
         let mut result = vec![];
         // Simulate lexing
-        let res = lexer.tokenize(&vec!["\"this is \\\"escaped\\\" string\""].join("\n"));
+        let code = ["\"this is \\\"escaped\\\" string\""].join("\n");
+        let res = lexer.tokenize(&code);
         assert!(res.is_ok());
         for lex in res.unwrap() {
-            result.push((lex.word, lex.pos.0, lex.pos.1));
+            let (row, col) = position(&code, &lex);
+            result.push((lex.word.to_string(), row, col));
         }
         assert_eq!(expected, result);
     }
@@ -529,12 +745,14 @@ mod test {
 
         let text = r#""\{should not be interpolated // should not be a comment}""#;
 
-        let res = lexer.tokenize(&vec![text].join("\n"));
+        let code = [text].join("\n");
+        let res = lexer.tokenize(&code);
         assert!(res.is_ok());
 
         let mut result = vec![];
         for lex in res.unwrap() {
-            result.push((lex.word, lex.pos.0, lex.pos.1));
+            let (row, col) = position(&code, &lex);
+            result.push((lex.word.to_string(), row, col));
         }
 
         let expected = vec![
@@ -557,4 +775,125 @@ mod test {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_lexer_tolerant_errors() {
+        let symbols = vec!['='];
+        let regions = reg![reg!(string as "String" => {
+            begin: "'",
+            end: "'",
+            singleline: true
+        })];
+        let rules = Rules::new(symbols, vec![], regions);
+        let lexer = super::Lexer::new(rules);
+        let code = ["let a = 'abc", "let b\u{7} = 2", "'dangling"].join("\n");
+        let (tokens, errors) = lexer.tokenize_all(&code);
+        let tokens = tokens.into_iter()
+            .map(|token| {
+                let (row, col) = position(&code, &token);
+                (token.word.to_string(), row, col)
+            })
+            .collect::<Vec<_>>();
+        let expected_tokens = [
+            ("let", 1, 1),
+            ("a", 1, 5),
+            ("=", 1, 7),
+            ("'abc", 1, 9),
+            ("let", 2, 1),
+            ("b", 2, 5),
+            ("=", 2, 8),
+            ("2", 2, 10),
+            ("'dangling", 3, 1),
+        ].iter().map(|(word, row, col)| (word.to_string(), *row, *col)).collect::<Vec<_>>();
+        assert_eq!(tokens, expected_tokens);
+        let errors = errors.into_iter()
+            .map(|(kind, info)| (kind, info.position))
+            .collect::<Vec<_>>();
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(
+            &errors[0],
+            (LexerErrorType::Singleline(region), Position::Pos(1, 13)) if region.begin.position == Position::Pos(1, 9)
+        ));
+        assert_eq!(errors[1], (LexerErrorType::UnexpectedCharacter('\u{7}'), Position::Pos(2, 6)));
+        assert!(matches!(
+            &errors[2],
+            (LexerErrorType::Unclosed(region), Position::Pos(3, 10)) if region.begin.position == Position::Pos(3, 1)
+        ));
+        // Strict mode stops on the first error
+        let (kind, _) = lexer.tokenize(&code).unwrap_err();
+        assert!(matches!(kind, LexerErrorType::Singleline(_)));
+    }
+
+    #[test]
+    fn test_lexer_region_errors() {
+        let regions = reg![reg!(string as "String" => {
+            begin: "'",
+            end: "'"
+        })];
+        let rules = Rules::new(vec![], vec![], regions);
+        let lexer = super::Lexer::new(rules);
+        let (_, errors) = lexer.tokenize_all("'a\u{0}b\\");
+        let errors = errors.into_iter().map(|(kind, _)| kind).collect::<Vec<_>>();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], LexerErrorType::InvalidCharacter('\u{0}'));
+        assert_eq!(errors[1], LexerErrorType::UnterminatedEscape);
+        assert!(matches!(errors[2], LexerErrorType::Unclosed(_)));
+    }
+
+    #[test]
+    fn test_lexer_borrowed_tokens() {
+        let regions = reg![reg!(string as "String" => {
+            begin: "'",
+            end: "'"
+        })];
+        let rules = Rules::new(vec!['='], vec![], regions);
+        let lexer = super::Lexer::new(rules);
+        let code = "let a = 'b\u{0}c'";
+        let (tokens, errors) = lexer.tokenize_borrowed_all(code);
+        assert_eq!(errors.len(), 1);
+        let words = tokens.iter()
+            .map(|token| (token.word.as_ref(), token.is_borrowed()))
+            .collect::<Vec<_>>();
+        // Word of the string differs from the source as the invalid character is skipped
+        assert_eq!(words, vec![("let", true), ("a", true), ("=", true), ("'bc'", false)]);
+        assert_eq!(&code[tokens[3].span.range()], "'b\u{0}c'");
+        let owned = lexer.tokenize_all(code).0;
+        assert_eq!(owned, tokens.iter().map(|token| token.to_token()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_lexer_mixed_indentation() {
+        let rules = Rules::new(vec![':'], vec![], reg![]);
+        let mut lexer = super::Lexer::new(rules);
+        lexer.scoping_mode = ScopingMode::Indent;
+        let code = ["if a:", "  \tpass", "  pass"].join("\n");
+        let (tokens, errors) = lexer.tokenize_all(&code);
+        assert_eq!(tokens.len(), 7);
+        assert_eq!(errors.len(), 1);
+        let (kind, info) = &errors[0];
+        assert_eq!(kind, &LexerErrorType::MixedIndentation);
+        assert_eq!((info.position.clone(), info.len), (Position::Pos(2, 1), 3));
+        // Error starts at the beginning of the line and the indentation token at the new line before it
+        assert_eq!(info.span.unwrap().range(), 6..9);
+        assert_eq!(tokens[3].span.range(), 5..9);
+        // Span covers the whole word so the indentation can be borrowed from the code
+        let (borrowed, _) = lexer.tokenize_borrowed_all(&code);
+        assert!(borrowed[3].is_borrowed());
+    }
+
+    #[test]
+    fn test_lexer_indent_region_boundary() {
+        let rules = Rules::new(vec![], vec![], reg![
+            reg!(block as "Block" => {
+                begin: "9",
+                end: "\t",
+                tokenize: true
+            })
+        ]);
+        let mut lexer = super::Lexer::new(rules);
+        lexer.scoping_mode = ScopingMode::Indent;
+        let tokens = lexer.tokenize("9\n\tab").unwrap();
+        let words = tokens.iter().map(|token| token.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["9", "\n", "\t", "ab"]);
+    }
 }
@@ -12,14 +12,54 @@ mod region_handler;
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
+/// Region in which the lexer error occurred
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ErrorRegion {
+    /// Human-readable name of the region
+    pub name: String,
+    /// String that determines end of the region
+    pub end: String,
+    /// Custom error message defined by the region
+    pub message: Option<String>,
+    /// Custom help text defined by the region
+    pub help: Option<String>,
+    /// Position of the opening delimiter of the region
+    pub begin: PositionInfo
+}
+
 /// Lexer's error type
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LexerErrorType {
     /// Unspillable region has been spilled
-    Singleline,
+    Singleline(Box<ErrorRegion>),
     /// Given region left unclosed
-    Unclosed,
+    Unclosed(Box<ErrorRegion>),
+    /// Character that cannot appear in the code
+    UnexpectedCharacter(char),
+    /// Character that cannot appear inside of a region
+    InvalidCharacter(char),
+    /// Indentation mixes tabs and spaces
+    MixedIndentation,
+    /// Escape symbol is the last character of the code
+    UnterminatedEscape,
+}
+
+/// Reason why the code is not complete yet
+///
+/// Incomplete code could become valid once more lines are supplied (for instance in a REPL).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Incomplete {
+    /// Region with given name (like a string literal) has not been closed
+    UnclosedRegion(String),
+    /// Bracket has been opened but not closed
+    OpenBracket(char),
+    /// Indented block can be continued in the next line
+    IndentBlock,
+    /// Parser has reached the end of the code
+    UnexpectedEof
 }
 
 /// Type containing full error of lexer
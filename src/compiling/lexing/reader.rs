@@ -5,21 +5,25 @@ pub enum ReadMode {
     Future
 }
 
-pub struct Reader {
-    pub code: String,
+pub struct Reader<'a> {
+    pub code: &'a str,
     pub row: usize,
     pub col: usize,
     pub index: usize,
+    pub offset: usize,
+    pub letter_len: usize,
     pub new_line: bool
 }
 
-impl Reader {
-    pub fn new(code: &String) -> Self {
+impl<'a> Reader<'a> {
+    pub fn new(code: &'a str) -> Self {
         Reader {
-            code: code.clone(),
+            code,
             row: BEGINNING.0,
             col: BEGINNING.1,
             index: 0,
+            offset: 0,
+            letter_len: 0,
             new_line: true
         }
     }
@@ -29,27 +33,54 @@ impl Reader {
         if self.row > 0 {
             self.index += 1;
             self.col += 1;
+            self.offset += self.letter_len;
         }
         if self.new_line {
             self.new_line = false;
             self.row += 1;
             self.col = BEGINNING.1;
         }
-        match self.code.chars().nth(self.index) {
+        match self.code[self.offset..].chars().next() {
             Some(letter) => {
                 self.new_line = letter == '\n';
+                self.letter_len = letter.len_utf8();
                 Some(letter)
             }
-            None => None
+            None => {
+                self.letter_len = 0;
+                None
+            }
         }
     }
 
     /// Return current index of the string
     #[inline]
+    #[allow(dead_code)]
     pub fn get_index(&self) -> usize {
         self.index
     }
 
+    /// Return byte index of the current character
+    #[inline]
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Return byte index right after the current character
+    #[inline]
+    pub fn get_end_offset(&self) -> usize {
+        self.offset + self.letter_len
+    }
+
+    /// Return byte index that is given amount of characters after the byte index
+    #[inline]
+    pub fn advance(&self, offset: usize, chars: usize) -> usize {
+        match self.code.get(offset..) {
+            Some(rest) => rest.char_indices().nth(chars).map_or(self.code.len(), |(index, _)| offset + index),
+            None => self.code.len()
+        }
+    }
+
     /// Return current position in code
     #[inline]
     pub fn get_position(&self) -> (usize, usize) {
@@ -98,14 +129,13 @@ impl Reader {
     #[inline]
     pub fn get_future(&self, n: usize) -> Option<String> {
         let begin = self.index;
-        // Handle arithmetic overflow
-        let end = if self.index + n <= self.code.len() { self.index + n } else { return None };
-        // Find the next UTF-8 character boundary for the beginning of the slice
-        Some(self.get_slice(begin, end))
+        let slice = self.get_slice(begin, begin.saturating_add(n));
+        // Index counts characters so the slice is shorter if the code ends earlier
+        if slice.chars().count() == n { Some(slice) } else { None }
     }
 }
 
-impl Iterator for Reader {
+impl Iterator for Reader<'_> {
     type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -117,7 +147,7 @@ impl Iterator for Reader {
 mod test {
     #[test]
     fn letter_position() {
-        let code = vec![
+        let code = [
             "apple",
             "banana",
             "orange"
@@ -142,9 +172,9 @@ mod test {
 
     #[test]
     fn index_position() {
-        let code = vec![
+        let code = [
             "apple",
-            "orange",
+            "orange"
         ].join("\n");
         let expected = vec![
             0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11
@@ -159,10 +189,25 @@ mod test {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn offset_position() {
+        let code = "a🎉\nb";
+        let expected = vec![(0, 1), (1, 5), (5, 6), (6, 7)];
+        let mut reader = super::Reader::new(code);
+        let mut result = vec![];
+        // Simulate lexing
+        while let Some(_) = reader.next() {
+            result.push((reader.get_offset(), reader.get_end_offset()));
+        }
+        assert_eq!(expected, result);
+        assert_eq!(reader.get_offset(), code.len());
+        assert_eq!(reader.advance(0, 2), 5);
+    }
+
     #[test]
     fn correct_history_and_future() {
         const SIZE: usize = 5;
-        let code = vec![
+        let code = [
             "apple",
             "kiwi"
         ].join("\n");
@@ -189,4 +234,17 @@ mod test {
         assert_eq!(expected, result_history);
         assert_eq!(expected, result_future);
     }
+
+    #[test]
+    fn future_multibyte() {
+        let mut reader = super::Reader::new("żółw&");
+        let mut futures = vec![];
+        while reader.next().is_some() {
+            futures.push(reader.get_future(2));
+        }
+        assert_eq!(futures.first().cloned().flatten(), Some("żó".to_string()));
+        // The last character has no character after it
+        assert_eq!(futures.last().cloned().flatten(), None);
+        assert_eq!(reader.get_future(0), Some(String::new()));
+    }
 }
@@ -32,14 +32,23 @@ impl RegionHandler {
         self.region_stack.last()
     }
 
+    // Close current region without matching its end
+    // This is used to recover from lexer errors
+    #[inline]
+    pub fn close_region(&mut self) {
+        if self.region_stack.len() > 1 {
+            self.region_stack.pop();
+        }
+    }
+
     // Error if after code lexing
     // some region was left unclosed
     #[inline]
-    pub fn is_region_closed(&self, reader: &Reader) -> Result<(), ((usize, usize), Region)> {
+    pub fn is_region_closed(&self, reader: &Reader) -> Result<(), ((usize, usize), &Region)> {
         if let Some(region) = self.region_stack.last() {
             if !region.allow_unclosed_region {
                 let pos = reader.get_position();
-                return Err((pos, region.clone()));
+                return Err((pos, region));
             }
         }
         Ok(())
@@ -126,7 +135,7 @@ impl RegionHandler {
             self.match_region_by(
                 reader,
                 |candidate: &Region| &candidate.end,
-                &[region.clone()],
+                std::slice::from_ref(region),
                 ReadMode::History,
             )
         } else {
@@ -155,7 +164,7 @@ mod test {
 
     #[test]
     fn match_region() {
-        let lines = vec!["begin", "\\begin", "end"];
+        let lines = ["begin", "\\begin", "end"];
         let expected = vec![(0, String::from("begin")), (15, String::from("end"))];
         let code = lines.join(" ");
         let mut reader = Reader::new(&code);
@@ -192,7 +201,7 @@ mod test {
 
     #[test]
     fn handle_region() {
-        let lines = vec!["'My name is \\\\\\'{name}.\\\\'"];
+        let lines = ["'My name is \\\\\\'{name}.\\\\'"];
         let expected = vec![0, 16, 21, 25];
         let code = lines.join("\n");
         let region = reg![reg!(string as "String literal" => {
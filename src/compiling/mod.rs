@@ -6,11 +6,20 @@
 mod lexing;
 
 mod compiler;
+mod builder;
 mod token;
 mod parser;
+mod source_map;
+mod span;
+mod column;
+pub(crate) mod json;
 pub mod failing;
 
 pub use lexing::*;
 pub use compiler::*;
+pub use builder::*;
 pub use token::*;
 pub use parser::*;
+pub use source_map::*;
+pub use span::*;
+pub use column::*;
@@ -0,0 +1,171 @@
+use crate::compiling::failing::position_info::PositionInfo;
+use crate::compiling::failing::message::Message;
+use crate::compiling::failing::failure::Failure;
+use crate::compiling::failing::catalog::{keys, translate};
+use super::{Metadata, SyntaxModule, attempt, syntax, token};
+
+/// Parses a new syntax module and returns it
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # struct IfStatement {}
+/// # impl SyntaxModule<DefaultMetadata> for IfStatement {
+/// #   syntax_name!("If");
+/// #   fn new() -> Self { IfStatement {} }
+/// #   fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult { Ok(()) }
+/// # }
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// let ifst: IfStatement = module(meta)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn module<M: Metadata, S: SyntaxModule<M>>(meta: &mut M) -> Result<S, Failure> {
+    let mut module = S::new();
+    syntax(meta, &mut module)?;
+    Ok(module)
+}
+
+/// Matches one token with any of the given words
+///
+/// The words are tried in order and the matched one is returned.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// let operator = one_of(meta, &["+", "-"])?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn one_of<T: AsRef<str>>(meta: &mut impl Metadata, words: &[T]) -> Result<String, Failure> {
+    let mut failure = None;
+    for word in words {
+        match token(meta, word) {
+            Ok(word) => return Ok(word),
+            Err(error) => failure = Some(error)
+        }
+    }
+    Err(failure.unwrap_or_else(|| match meta.get_current_token() {
+        Some(_) => Failure::Quiet(PositionInfo::from_metadata(meta)),
+        None => Failure::Quiet(PositionInfo::at_eof(meta))
+    }))
+}
+
+/// Parses something that may not be there
+///
+/// If parsing fails quietly - the metadata is rolled back and `None` is returned.
+/// Loud failures are returned as they are.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// let public = optional(meta, |meta| token(meta, "pub"))?.is_some();
+/// # Ok(())
+/// # }
+/// ```
+pub fn optional<M: Metadata, T>(meta: &mut M, parse: impl FnOnce(&mut M) -> Result<T, Failure>) -> Result<Option<T>, Failure> {
+    match attempt(meta, parse) {
+        Ok(value) => Ok(Some(value)),
+        Err(Failure::Quiet(_)) => Ok(None),
+        Err(failure) => Err(failure)
+    }
+}
+
+/// Parses something repeatedly until it fails quietly
+///
+/// Elements can be separated with a separator token. If the element after
+/// the separator cannot be parsed - the separator is left unconsumed.
+/// Loud failures are returned as they are.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// token(meta, "(")?;
+/// let arguments = repeat(meta, Some(","), |meta| variable(meta, vec![]))?;
+/// token(meta, ")")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn repeat<M: Metadata, T>(meta: &mut M, separator: Option<&str>, mut parse: impl FnMut(&mut M) -> Result<T, Failure>) -> Result<Vec<T>, Failure> {
+    let mut values = Vec::new();
+    loop {
+        let is_first = values.is_empty();
+        let start = meta.get_index();
+        let result = attempt(meta, |meta| {
+            if let (Some(separator), false) = (separator, is_first) {
+                token(meta, separator)?;
+            }
+            parse(meta)
+        });
+        match result {
+            // Element that consumes nothing would be matched forever
+            Ok(value) if meta.get_index() == start => {
+                values.push(value);
+                return Ok(values)
+            }
+            Ok(value) => values.push(value),
+            Err(Failure::Quiet(_)) => return Ok(values),
+            Err(failure) => return Err(failure)
+        }
+    }
+}
+
+/// Parses something that is required at this point
+///
+/// Quiet failure is turned into a loud one that tells what was expected.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// token(meta, "if")?;
+/// expect(meta, "':'", |meta| token(meta, ":"))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn expect<M: Metadata, T, S: AsRef<str>>(meta: &mut M, expected: S, parse: impl FnOnce(&mut M) -> Result<T, Failure>) -> Result<T, Failure> {
+    match parse(meta) {
+        Err(Failure::Quiet(position)) => {
+            let message = Message::new_err_at_position(meta, position)
+                .message(translate(keys::EXPECTED, &[("expected", expected.as_ref())]));
+            Err(Failure::Loud(message))
+        }
+        result => result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compiling::{DefaultMetadata, Token, Span};
+    use crate::compiling::patterns::variable;
+
+    fn meta(words: &[&str]) -> DefaultMetadata {
+        let tokens = words.iter().map(|word| Token::new(*word, Span::default())).collect();
+        DefaultMetadata::new(tokens, None, None)
+    }
+
+    #[test]
+    fn repeat_separated() {
+        let meta = &mut meta(&["a", ",", "b", ",", ")"]);
+        let values = repeat(meta, Some(","), |meta| variable(meta, vec![])).unwrap();
+        assert_eq!(values, vec!["a", "b"]);
+        // Trailing separator is left for the caller
+        assert_eq!(meta.get_index(), 3);
+    }
+
+    #[test]
+    fn expect_required() {
+        let meta = &mut meta(&["if", "x"]);
+        assert!(optional(meta, |meta| token(meta, "pub")).unwrap().is_none());
+        assert_eq!(one_of(meta, &["while", "if"]).unwrap(), "if");
+        let Err(Failure::Loud(message)) = expect(meta, "':'", |meta| token(meta, ":")) else {
+            panic!("Expected a loud failure")
+        };
+        assert_eq!(message.message.unwrap(), "Expected ':'");
+    }
+}
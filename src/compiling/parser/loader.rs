@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::compiling::FileId;
+use crate::compiling::lexer::Lexer;
+use crate::compiling::failing::position_info::PositionInfo;
+use crate::compiling::failing::message::Message;
+use crate::compiling::failing::failure::Failure;
+use crate::compiling::failing::catalog::{keys, translate};
+use crate::compiling::compiler::{lexer_failure, LexerDiagnosticHook};
+use super::{Metadata, SyntaxModule, syntax};
+
+/// Result of importing a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Import {
+    /// Module was loaded and parsed into the supplied syntax module
+    Parsed(FileId),
+    /// Module has been already parsed before and the supplied syntax module was left untouched
+    Cached(FileId)
+}
+
+impl Import {
+    /// Get the source of the imported module
+    pub fn file(&self) -> FileId {
+        match self {
+            Import::Parsed(file) | Import::Cached(file) => *file
+        }
+    }
+}
+
+// Module that is currently being parsed
+#[derive(Debug, Clone)]
+struct ImportFrame {
+    path: String,
+    canonical: PathBuf,
+    // Position of the import statement in the importing module
+    position: Option<PositionInfo>
+}
+
+/// Loader of modules imported during parsing
+///
+/// Loader lives in the metadata and is used by the `import` pattern to load
+/// additional files on demand. It resolves paths relative to the importing file,
+/// remembers modules that were already parsed and detects import cycles.
+#[derive(Debug, Clone)]
+pub struct ModuleLoader {
+    lexer: Lexer,
+    lexer_hook: Option<LexerDiagnosticHook>,
+    parsed: HashMap<PathBuf, FileId>,
+    chain: Vec<ImportFrame>
+}
+
+impl ModuleLoader {
+    /// Create a new loader that tokenizes modules with given lexer
+    pub fn new(lexer: Lexer) -> Self {
+        ModuleLoader {
+            lexer,
+            lexer_hook: None,
+            parsed: HashMap::new(),
+            chain: vec![]
+        }
+    }
+
+    /// Customise the diagnostics reported for lexer errors in imported modules
+    pub fn lexer_diagnostics(mut self, hook: LexerDiagnosticHook) -> Self {
+        self.lexer_hook = Some(hook);
+        self
+    }
+
+    /// Set the module from which the compilation has started
+    pub fn root<T: AsRef<str>>(mut self, path: T, file: FileId) -> Self {
+        let canonical = Self::canonicalize(Path::new(path.as_ref()));
+        self.parsed.insert(canonical.clone(), file);
+        self.chain = vec![ImportFrame {
+            path: path.as_ref().to_string(),
+            canonical,
+            position: None
+        }];
+        self
+    }
+
+    /// Resolve path of the imported module relative to the importing file
+    pub fn resolve<T: AsRef<str>>(&self, importer: Option<&str>, path: T) -> PathBuf {
+        let path = Path::new(path.as_ref());
+        match importer.and_then(|importer| Path::new(importer).parent()) {
+            Some(parent) if path.is_relative() => parent.join(path),
+            _ => path.to_path_buf()
+        }
+    }
+
+    /// Get the source of the module if it was already parsed
+    pub fn get_parsed<T: AsRef<Path>>(&self, path: T) -> Option<FileId> {
+        self.parsed.get(&Self::canonicalize(path.as_ref())).copied()
+    }
+
+    /// Get positions of the import statements that led to the currently parsed module
+    pub fn get_chain(&self) -> Vec<PositionInfo> {
+        self.chain.iter()
+            .filter_map(|frame| frame.position.clone())
+            .collect()
+    }
+
+    // Render the chain of imports that ends with given path
+    fn render_cycle(&self, canonical: &Path, path: &str) -> String {
+        let begin = self.chain.iter()
+            .position(|frame| frame.canonical == canonical)
+            .unwrap_or(0);
+        self.chain[begin..].iter()
+            .map(|frame| frame.path.as_str())
+            .chain([path])
+            .collect::<Vec<&str>>()
+            .join(" -> ")
+    }
+
+    fn canonicalize(path: &Path) -> PathBuf {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+/// Imports a module from a file
+///
+/// The path is resolved relative to the file that is currently parsed and the position
+/// of the import is taken from the last consumed token. The module is parsed with
+/// a new metadata that shares the loaded sources. If the module was already parsed,
+/// the supplied syntax module is left untouched and `Import::Cached` is returned.
+/// Import cycles result in a loud failure with the chain of imports.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # struct Module {}
+/// # impl SyntaxModule<DefaultMetadata> for Module {
+/// #   syntax_name!("Module");
+/// #   fn new() -> Self { Module {} }
+/// #   fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult { Ok(()) }
+/// # }
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// token(meta, "import")?;
+/// let path = token_by(meta, |word| word.starts_with('"'))?;
+/// let mut module = Module::new();
+/// import(meta, path.trim_matches('"'), &mut module)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn import<M: Metadata, T: AsRef<str>>(meta: &mut M, path: T, module: &mut impl SyntaxModule<M>) -> Result<Import, Failure> {
+    let path = path.as_ref();
+    let last_token = meta.get_token_at(meta.get_index().saturating_sub(1));
+    let position = PositionInfo::from_token(meta, last_token);
+    let Some(mut loader) = meta.take_loader() else {
+        let message = Message::new_err_at_position(meta, position)
+            .message(translate(keys::IMPORT_UNSUPPORTED, &[("path", path)]))
+            .comment(translate(keys::IMPORT_UNSUPPORTED_HELP, &[]));
+        return Err(Failure::Loud(message))
+    };
+    let resolved = loader.resolve(meta.get_path().as_deref(), path);
+    let canonical = ModuleLoader::canonicalize(&resolved);
+    // Detect import cycle
+    if loader.chain.iter().any(|frame| frame.canonical == canonical) {
+        let cycle = loader.render_cycle(&canonical, path);
+        meta.set_loader(loader);
+        let message = Message::new_err_at_position(meta, position)
+            .message(translate(keys::IMPORT_CYCLE, &[("path", path)]))
+            .comment(cycle);
+        return Err(Failure::Loud(message))
+    }
+    // Do not parse the same module twice
+    if let Some(file) = loader.parsed.get(&canonical).copied() {
+        meta.set_loader(loader);
+        return Ok(Import::Cached(file))
+    }
+    let mut sources = meta.get_sources().cloned().unwrap_or_default();
+    let resolved = resolved.to_string_lossy().to_string();
+    let file = match sources.load(&resolved) {
+        Ok(file) => file,
+        Err(error) => {
+            meta.set_loader(loader);
+            let message = Message::new_err_at_position(meta, position)
+                .message(translate(keys::IMPORT_UNREADABLE, &[("path", path)]))
+                .comment(error.to_string());
+            return Err(Failure::Loud(message))
+        }
+    };
+    let source = sources.get(file).unwrap().clone();
+    loader.chain.push(ImportFrame {
+        path: path.to_string(),
+        canonical: canonical.clone(),
+        position: Some(position.clone())
+    });
+    let (tokens, lexer_error) = match loader.lexer.tokenize_source(&source) {
+        Ok(tokens) => (tokens, None),
+        Err(error) => (vec![], Some(error))
+    };
+    let lexer_hook = loader.lexer_hook;
+    let mut child = M::new(tokens, Some(resolved), Some(source.code().clone()));
+    child.set_sources(sources, file);
+    child.set_loader(loader);
+    if let Some(debug) = meta.get_debug() {
+        child.set_debug(debug);
+    }
+    // Imported module is traced together with the importing one
+    if let Some(tracer) = meta.take_tracer() {
+        child.set_tracer(tracer);
+    }
+    let result = match lexer_error {
+        None => match syntax(&mut child, module) {
+            // Imported module has to be parsed as a whole
+            Err(Failure::Quiet(position)) => Err(Failure::Loud(
+                Message::new_err_at_position(&child, position)
+                    .message(translate(keys::IMPORT_UNPARSABLE, &[("path", path)]))
+            )),
+            result => result
+        },
+        Some(error) => Err(lexer_failure(&child, error, lexer_hook))
+    };
+    // Bring the tracer, the loader and the loaded sources back to the importing module
+    if let Some(tracer) = child.take_tracer() {
+        meta.set_tracer(tracer);
+    }
+    if let (Some(sources), Some(current)) = (child.get_sources(), meta.get_file()) {
+        meta.set_sources(sources.clone(), current);
+    }
+    let Some(mut loader) = child.take_loader() else {
+        let message = Message::new_err_at_position(meta, position)
+            .message(translate(keys::IMPORT_LOADER_LOST, &[("path", path)]));
+        return Err(Failure::Loud(message))
+    };
+    loader.chain.pop();
+    // Broken module is parsed again so that every import reports its failure
+    if result.is_ok() {
+        loader.parsed.insert(canonical, file);
+    }
+    meta.set_loader(loader);
+    result.map(|()| Import::Parsed(file))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use crate::compiling::FileId;
+    use crate::compiling::lexer::Lexer;
+    use crate::compiling_rules::{Region, Rules};
+    use crate::reg;
+    use super::ModuleLoader;
+
+    #[test]
+    fn resolve_relative_paths() {
+        let loader = ModuleLoader::new(Lexer::new(Rules::new(vec![], vec![], reg![])));
+        assert_eq!(loader.resolve(Some("src/main.ab"), "lib/std.ab"), PathBuf::from("src/lib/std.ab"));
+        assert_eq!(loader.resolve(Some("main.ab"), "std.ab"), PathBuf::from("std.ab"));
+        assert_eq!(loader.resolve(None, "std.ab"), PathBuf::from("std.ab"));
+        assert_eq!(loader.resolve(Some("src/main.ab"), "/lib/std.ab"), PathBuf::from("/lib/std.ab"));
+    }
+
+    #[test]
+    fn render_cycle() {
+        let loader = ModuleLoader::new(Lexer::new(Rules::new(vec![], vec![], reg![])))
+            .root("main.ab", FileId(0));
+        let canonical = ModuleLoader::canonicalize(&PathBuf::from("main.ab"));
+        assert_eq!(loader.render_cycle(&canonical, "main.ab"), "main.ab -> main.ab");
+        assert_eq!(loader.get_parsed("main.ab"), Some(FileId(0)));
+    }
+}
@@ -1,5 +1,9 @@
-use crate::compiling::Token;
+use std::any::Any;
+use std::borrow::Cow;
+use std::sync::Arc;
+use crate::compiling::{Token, BorrowedToken, FileId, SourceFile, SourceMap, Span};
 use crate::compiling::failing::position_info::PositionInfo;
+use super::{Tracer, ModuleLoader, SemanticTokens};
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
@@ -14,26 +18,66 @@ pub struct DefaultMetadata {
     pub expr: Vec<Token>,
     /// Optionally path of the file
     pub path: Option<String>,
-    /// Optionally code of the file
-    pub code: Option<String>,
     /// Debug value that is used internally
-    pub indent: Option<usize>
+    pub indent: Option<usize>,
+    /// Parser trace recorder
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub tracer: Option<Tracer>,
+    /// All the sources loaded for the compilation
+    ///
+    /// The code of the file is stored only here and is available through `Metadata::get_code`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sources: SourceMap,
+    /// Source that is currently parsed
+    pub file: Option<FileId>,
+    /// Loader of imported modules
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub loader: Option<ModuleLoader>,
+    /// Semantic classes of the tokens
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub semantic: SemanticTokens
+}
+
+impl DefaultMetadata {
+    /// Get the code of the file that is currently parsed
+    #[deprecated(note = "the code is stored in the `sources`, use `Metadata::get_code` instead")]
+    pub fn code(&self) -> Option<&String> {
+        self.get_code()
+    }
 }
 
 impl Metadata for DefaultMetadata {
     fn new(tokens: Vec<Token>, path: Option<String>, code: Option<String>) -> Self {
+        let mut sources = SourceMap::new();
+        let file = code.map(|code| sources.add(path.clone(), code));
         DefaultMetadata {
             index: 0,
             expr: tokens,
             path,
-            code,
-            indent: None
+            indent: None,
+            tracer: None,
+            sources,
+            file,
+            loader: None,
+            semantic: SemanticTokens::default()
         }
     }
 
     fn get_token_at(&self, index: usize) -> Option<Token> {
         self.expr.get(index).cloned()
     }
+
+    fn get_tokens(&self) -> Option<&[Token]> {
+        Some(&self.expr)
+    }
+
+    fn get_word_at(&self, index: usize) -> Option<Cow<'_, str>> {
+        self.expr.get(index).map(|token| Cow::Borrowed(token.word.as_str()))
+    }
+
+    fn get_span_at(&self, index: usize) -> Option<Span> {
+        self.expr.get(index).map(|token| token.span)
+    }
     
     fn set_index(&mut self, index: usize) {
         self.index = index
@@ -56,8 +100,215 @@ impl Metadata for DefaultMetadata {
     }
 
     fn get_code(&self) -> Option<&String> {
-        self.code.as_ref()
+        self.get_source().map(|source| source.code())
+    }
+
+    fn get_tracer(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer)
+    }
+
+    fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    fn get_sources(&self) -> Option<&SourceMap> {
+        Some(&self.sources)
+    }
+
+    fn get_file(&self) -> Option<FileId> {
+        self.file
+    }
+
+    fn set_sources(&mut self, sources: SourceMap, file: FileId) {
+        self.sources = sources;
+        self.file = Some(file);
+    }
+
+    fn get_trace(&self) -> Vec<PositionInfo> {
+        self.loader.as_ref()
+            .map(|loader| loader.get_chain())
+            .unwrap_or_default()
+    }
+
+    fn take_loader(&mut self) -> Option<ModuleLoader> {
+        self.loader.take()
+    }
+
+    fn set_loader(&mut self, loader: ModuleLoader) {
+        self.loader = Some(loader)
+    }
+
+    fn get_semantic_tokens(&self) -> Option<&SemanticTokens> {
+        Some(&self.semantic)
+    }
+
+    fn get_semantic_tokens_mut(&mut self) -> Option<&mut SemanticTokens> {
+        Some(&mut self.semantic)
+    }
+}
+
+/// Metadata that parses tokens borrowed from the source code
+///
+/// Pattern helpers match the borrowed words directly so that parsing
+/// does not allocate a word for every token.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # let rules = Rules::new(vec!['='], vec![], reg![]);
+/// let cc = Compiler::builder("HerbScript", rules).source("let a = 12").build().unwrap();
+/// let tokens = cc.tokenize_borrowed().unwrap();
+/// let mut meta = BorrowedMetadata::from_borrowed(tokens, None, Some(cc.code.clone()));
+/// assert!(token(&mut meta, "let").is_ok());
+/// assert_eq!(variable(&mut meta, vec![]).unwrap(), "a");
+/// assert_eq!(meta.get_code().unwrap(), "let a = 12");
+/// ```
+pub struct BorrowedMetadata<'src> {
+    /// Current index in the token stream
+    pub index: usize,
+    /// Lexem of tokens to parse
+    pub expr: Vec<BorrowedToken<'src>>,
+    /// Optionally path of the file
+    pub path: Option<String>,
+    /// Debug value that is used internally
+    pub indent: Option<usize>,
+    /// Parser trace recorder
+    pub tracer: Option<Tracer>,
+    /// All the sources loaded for the compilation
+    ///
+    /// The code of the file is stored only here and is available through `Metadata::get_code`.
+    pub sources: SourceMap,
+    /// Source that is currently parsed
+    pub file: Option<FileId>,
+    /// Loader of imported modules
+    pub loader: Option<ModuleLoader>,
+    /// Semantic classes of the tokens
+    pub semantic: SemanticTokens
+}
+
+impl<'src> BorrowedMetadata<'src> {
+    /// Load borrowed tokens into metadata
+    pub fn from_borrowed(tokens: Vec<BorrowedToken<'src>>, path: Option<String>, code: Option<String>) -> Self {
+        let mut sources = SourceMap::new();
+        let file = code.map(|code| sources.add(path.clone(), code));
+        BorrowedMetadata {
+            index: 0,
+            expr: tokens,
+            path,
+            indent: None,
+            tracer: None,
+            sources,
+            file,
+            loader: None,
+            semantic: SemanticTokens::default()
+        }
+    }
+}
+
+impl Metadata for BorrowedMetadata<'_> {
+    fn new(tokens: Vec<Token>, path: Option<String>, code: Option<String>) -> Self {
+        let tokens = tokens.iter().map(BorrowedToken::from).collect();
+        Self::from_borrowed(tokens, path, code)
+    }
+
+    /// Tokens are converted to the owned ones - prefer `get_word_at` and `get_span_at`
+    fn get_token_at(&self, index: usize) -> Option<Token> {
+        self.expr.get(index).map(BorrowedToken::to_token)
+    }
+
+    fn get_word_at(&self, index: usize) -> Option<Cow<'_, str>> {
+        self.expr.get(index).map(|token| Cow::Borrowed(token.word.as_ref()))
+    }
+
+    fn get_span_at(&self, index: usize) -> Option<Span> {
+        self.expr.get(index).map(|token| token.span)
+    }
+
+    fn set_index(&mut self, index: usize) {
+        self.index = index
+    }
+
+    fn get_index(&self) -> usize {
+        self.index
+    }
+
+    fn get_debug(&mut self) -> Option<usize> {
+        self.indent
+    }
+
+    fn set_debug(&mut self, indent: usize) {
+        self.indent = Some(indent)
+    }
+
+    fn get_path(&self) -> Option<String> {
+        self.path.clone()
+    }
+
+    fn get_code(&self) -> Option<&String> {
+        self.get_source().map(|source| source.code())
+    }
+
+    fn get_tracer(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
     }
+
+    fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer)
+    }
+
+    fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    fn get_sources(&self) -> Option<&SourceMap> {
+        Some(&self.sources)
+    }
+
+    fn get_file(&self) -> Option<FileId> {
+        self.file
+    }
+
+    fn set_sources(&mut self, sources: SourceMap, file: FileId) {
+        self.sources = sources;
+        self.file = Some(file);
+    }
+
+    fn get_trace(&self) -> Vec<PositionInfo> {
+        self.loader.as_ref()
+            .map(|loader| loader.get_chain())
+            .unwrap_or_default()
+    }
+
+    fn take_loader(&mut self) -> Option<ModuleLoader> {
+        self.loader.take()
+    }
+
+    fn set_loader(&mut self, loader: ModuleLoader) {
+        self.loader = Some(loader)
+    }
+
+    fn get_semantic_tokens(&self) -> Option<&SemanticTokens> {
+        Some(&self.semantic)
+    }
+
+    fn get_semantic_tokens_mut(&mut self) -> Option<&mut SemanticTokens> {
+        Some(&mut self.semantic)
+    }
+}
+
+/// Snapshot of the parsing state
+///
+/// Checkpoint is created with `Metadata::checkpoint` and is either
+/// rolled back with `Metadata::rollback` or accepted with `Metadata::commit`.
+#[derive(Debug)]
+pub struct Checkpoint {
+    /// Index in the token stream at the time of the checkpoint
+    pub index: usize,
+    /// Snapshot of the custom state of the metadata
+    pub state: Option<Box<dyn Any>>
 }
 
 /// Metadata for carrying information through the ASI parsing phases.
@@ -69,6 +320,23 @@ pub trait Metadata {
     fn new(tokens: Vec<Token>, path: Option<String>, code: Option<String>) -> Self;
     /// Return optionally token under desired index in the lexem
     fn get_token_at(&self, index: usize) -> Option<Token>;
+    /// Getter for the whole lexem
+    ///
+    /// Implement this to expose the tokens through `Compilation::tokens`.
+    fn get_tokens(&self) -> Option<&[Token]> {
+        None
+    }
+    /// Return optionally word of the token under desired index
+    ///
+    /// Pattern helpers match tokens by their words. Implement this together with
+    /// `get_span_at` to borrow the words instead of cloning the whole tokens.
+    fn get_word_at(&self, index: usize) -> Option<Cow<'_, str>> {
+        self.get_token_at(index).map(|token| Cow::Owned(token.word.to_string()))
+    }
+    /// Return optionally span of the token under desired index
+    fn get_span_at(&self, index: usize) -> Option<Span> {
+        self.get_token_at(index).map(|token| token.span)
+    }
     /// Get current index
     fn get_index(&self) -> usize;
     /// Set current index
@@ -85,6 +353,69 @@ pub trait Metadata {
     fn get_trace(&self) -> Vec<PositionInfo> {
         vec![]
     }
+    /// Getter for the parser trace recorder
+    ///
+    /// Implement this together with `set_tracer` to support structured parser tracing.
+    fn get_tracer(&mut self) -> Option<&mut Tracer> {
+        None
+    }
+    /// Setter for the parser trace recorder
+    fn set_tracer(&mut self, _tracer: Tracer) {}
+    /// Take the parser trace recorder out of the metadata
+    ///
+    /// Imported modules are traced by moving the recorder to their metadata.
+    fn take_tracer(&mut self) -> Option<Tracer> {
+        None
+    }
+    /// Getter for the map of all loaded sources
+    ///
+    /// Implement this together with `get_file` and `set_sources` so that
+    /// positions and messages can be resolved without reading the filesystem.
+    fn get_sources(&self) -> Option<&SourceMap> {
+        None
+    }
+    /// Getter for the identifier of the source that is currently parsed
+    fn get_file(&self) -> Option<FileId> {
+        None
+    }
+    /// Setter for the map of all loaded sources and the currently parsed source
+    fn set_sources(&mut self, _sources: SourceMap, _file: FileId) {}
+    /// Get the source that is currently parsed
+    fn get_source(&self) -> Option<&Arc<SourceFile>> {
+        self.get_sources()?.get(self.get_file()?)
+    }
+    /// Take the loader of imported modules out of the metadata
+    ///
+    /// Implement this together with `set_loader` to support the `import` pattern.
+    /// It's also recommended to include the `ModuleLoader::get_chain` in the `get_trace`.
+    fn take_loader(&mut self) -> Option<ModuleLoader> {
+        None
+    }
+    /// Put the loader of imported modules back into the metadata
+    fn set_loader(&mut self, _loader: ModuleLoader) {}
+    /// Getter for the semantic classes of the tokens
+    ///
+    /// Implement this together with `get_semantic_tokens_mut` so that syntax modules
+    /// can classify the tokens for the editors with `tag_token`.
+    fn get_semantic_tokens(&self) -> Option<&SemanticTokens> {
+        None
+    }
+    /// Mutable getter for the semantic classes of the tokens
+    fn get_semantic_tokens_mut(&mut self) -> Option<&mut SemanticTokens> {
+        None
+    }
+    /// Tag the token under given index with the semantic type and modifiers
+    fn tag_token(&mut self, index: usize, token_type: &str, modifiers: &[&str]) {
+        if let Some(semantic) = self.get_semantic_tokens_mut() {
+            semantic.tag(index, token_type, modifiers);
+        }
+    }
+    /// Encode the semantic classes of the tokens to the Language Server Protocol format
+    fn encode_semantic_tokens(&self) -> Vec<u32> {
+        self.get_semantic_tokens()
+            .map(|semantic| semantic.encode(self))
+            .unwrap_or_default()
+    }
     /// Optionally set logic of incrementing the index number
     fn increment_index(&mut self) {
         let index = self.get_index();
@@ -95,6 +426,49 @@ pub trait Metadata {
         let index = self.get_index();
         self.get_token_at(index)
     }
+    /// Return word of the token under current index
+    fn get_current_word(&self) -> Option<Cow<'_, str>> {
+        let index = self.get_index();
+        self.get_word_at(index)
+    }
+    /// Take a snapshot of the custom state of the metadata
+    ///
+    /// Implement this together with `restore_state` so that backtracking also rolls back
+    /// your own fields such as symbol tables or recorded diagnostics.
+    ///
+    /// Snapshot is taken by every `syntax` and `attempt` call, so cloning a state that grows
+    /// with the code makes parsing quadratic. Large state should record its changes instead
+    /// and return just a point to roll them back to (see `Scopes::checkpoint`).
+    fn snapshot_state(&mut self) -> Option<Box<dyn Any>> {
+        None
+    }
+    /// Restore the custom state of the metadata from the snapshot
+    fn restore_state(&mut self, _state: Box<dyn Any>) {}
+    /// Accept the changes made to the custom state since the snapshot
+    fn commit_state(&mut self, _state: Box<dyn Any>) {}
+    /// Remember the current state so that it can be rolled back
+    fn checkpoint(&mut self) -> Checkpoint {
+        Checkpoint {
+            index: self.get_index(),
+            state: self.snapshot_state()
+        }
+    }
+    /// Restore the state remembered by the checkpoint
+    fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.set_index(checkpoint.index);
+        if let Some(semantic) = self.get_semantic_tokens_mut() {
+            semantic.truncate(checkpoint.index);
+        }
+        if let Some(state) = checkpoint.state {
+            self.restore_state(state);
+        }
+    }
+    /// Accept the changes made since the checkpoint
+    fn commit(&mut self, checkpoint: Checkpoint) {
+        if let Some(state) = checkpoint.state {
+            self.commit_state(state);
+        }
+    }
     /// Change current index by given offset
     fn offset_index(&mut self, offset: isize) {
         let index = self.get_index();
@@ -2,16 +2,29 @@ mod syntax_module;
 mod pattern;
 mod preset;
 mod metadata;
+mod tracer;
+mod profiler;
+mod loader;
+mod scope;
+mod grammar;
+mod semantic;
 
 pub use syntax_module::*;
 pub use pattern::*;
 pub use metadata::*;
+pub use tracer::*;
+pub use profiler::*;
+pub use loader::*;
+pub use scope::*;
+pub use grammar::*;
+pub use semantic::*;
 pub mod patterns {
     //! Utility functions that help you parse tokens
     //! 
     //! Functions in this module can help you handle tokens in the parsing phase.
     pub use super::pattern::*;
     pub use super::preset::*;
+    pub use super::grammar::*;
 }
 
 /// Macro that helps you capture failures thrown by inner parsing function calls
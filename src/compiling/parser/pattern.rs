@@ -16,11 +16,12 @@ use super::{ Metadata, SyntaxModule };
 /// # }
 /// ```
 pub fn token<T: AsRef<str>>(meta: &mut impl Metadata, text: T) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => if token.word == text.as_ref() {
+    match meta.get_current_word() {
+        Some(word) => if word == text.as_ref() {
+            let word = word.into_owned();
             meta.increment_index();
-            Ok(token.word)
-        } else { Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)))) }
+            Ok(word)
+        } else { Err(Failure::Quiet(PositionInfo::from_metadata(meta))) }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
 }
@@ -39,11 +40,11 @@ pub fn token<T: AsRef<str>>(meta: &mut impl Metadata, text: T) -> Result<String,
 /// # }
 /// ```
 pub fn token_by(meta: &mut impl Metadata, cb: impl Fn(&String) -> bool) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => if cb(&token.word) {
+    match meta.get_current_word().map(|word| word.into_owned()) {
+        Some(word) => if cb(&word) {
             meta.increment_index();
-            Ok(token.word)
-        } else { Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)))) }
+            Ok(word)
+        } else { Err(Failure::Quiet(PositionInfo::from_metadata(meta))) }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
 }
@@ -69,16 +70,43 @@ pub fn token_by(meta: &mut impl Metadata, cb: impl Fn(&String) -> bool) -> Resul
 /// # }
 /// ```
 pub fn syntax<M: Metadata>(meta: &mut M, module: &mut impl SyntaxModule<M>) -> Result<(), Failure> {
-    let index = meta.get_index();
-    // Determine if we shall parse it in debug mode or not
-    let result = match meta.get_debug() {
-        Some(_) => module.parse_debug(meta),
-        None => module.parse(meta)
-    };
-    if let Err(failure) = result {
-        meta.set_index(index);
-        Err(failure)
-    } else { Ok(()) }
+    attempt(meta, |meta| {
+        // Determine if we shall parse it in debug mode or not
+        if meta.get_debug().is_some() || meta.get_tracer().is_some() {
+            module.parse_debug(meta)
+        } else {
+            module.parse(meta)
+        }
+    })
+}
+
+/// Runs parsing function and rolls back the metadata if it fails
+///
+/// The index and the custom state of the metadata are restored with `Metadata::rollback`.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// let name = attempt(meta, |meta| {
+///     token(meta, "let")?;
+///     variable(meta, vec![])
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn attempt<M: Metadata, T>(meta: &mut M, parse: impl FnOnce(&mut M) -> Result<T, Failure>) -> Result<T, Failure> {
+    let checkpoint = meta.checkpoint();
+    match parse(meta) {
+        Ok(value) => {
+            meta.commit(checkpoint);
+            Ok(value)
+        }
+        Err(failure) => {
+            meta.rollback(checkpoint);
+            Err(failure)
+        }
+    }
 }
 
 /// Matches indentation
@@ -116,30 +144,51 @@ pub fn indent(meta: &mut impl Metadata) -> Result<usize, Failure> {
 /// # Ok(())
 /// # }
 /// ```
-pub fn indent_with(meta: &mut impl Metadata, size: usize) -> Result<std::cmp::Ordering, Failure> {
-    let index = meta.get_index();
-    let fun = |word: &String| word.starts_with('\n') && word.get(1..).unwrap().chars().all(|letter| letter == ' ');
-    match token_by(meta, fun) {
-        Ok(word) => {
-            let spaces = word.chars().count() - 1;
-            Ok(spaces.cmp(&size))
-        }
-        Err(details) => {
-            meta.set_index(index);
-            Err(details)
-        }
-    }
+pub fn indent_with<M: Metadata>(meta: &mut M, size: usize) -> Result<std::cmp::Ordering, Failure> {
+    attempt(meta, |meta| {
+        let fun = |word: &String| word.starts_with('\n') && word.get(1..).unwrap().chars().all(|letter| letter == ' ');
+        let word = token_by(meta, fun)?;
+        let spaces = word.chars().count() - 1;
+        Ok(spaces.cmp(&size))
+    })
 }
 
 #[cfg(test)]
 mod test {
-    use crate::compiling::{DefaultMetadata, Token};
+    use std::any::Any;
+    use crate::compiling::{DefaultMetadata, Span, Token};
+    use crate::compiling::parser::preset::variable;
     use super::*;
 
+    struct SymbolMetadata {
+        index: usize,
+        expr: Vec<Token>,
+        symbols: Vec<String>
+    }
+
+    impl Metadata for SymbolMetadata {
+        fn new(expr: Vec<Token>, _path: Option<String>, _code: Option<String>) -> Self {
+            SymbolMetadata { index: 0, expr, symbols: vec![] }
+        }
+        fn get_token_at(&self, index: usize) -> Option<Token> { self.expr.get(index).cloned() }
+        fn get_index(&self) -> usize { self.index }
+        fn set_index(&mut self, index: usize) { self.index = index }
+        fn get_debug(&mut self) -> Option<usize> { None }
+        fn set_debug(&mut self, _indent: usize) {}
+        fn get_path(&self) -> Option<String> { None }
+        fn get_code(&self) -> Option<&String> { None }
+        fn snapshot_state(&mut self) -> Option<Box<dyn Any>> { Some(Box::new(self.symbols.clone())) }
+        fn restore_state(&mut self, state: Box<dyn Any>) {
+            if let Ok(symbols) = state.downcast::<Vec<String>>() {
+                self.symbols = *symbols;
+            }
+        }
+    }
+
     #[test]
     fn indent_test() {
-        let expr = vec![Token {word: format!("\n    "), pos: (0, 0), start: 0 }];
-        let mut meta = DefaultMetadata::new(expr, Some(format!("path/to/file")), None);
+        let expr = vec![Token::new("\n    ", Span::default())];
+        let mut meta = DefaultMetadata::new(expr, Some("path/to/file".to_string()), None);
         let res = indent(&mut meta);
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), 4);
@@ -147,9 +196,35 @@ mod test {
 
     #[test]
     fn indent_with_test() {
-        let expr = vec![Token { word: format!("\n    "), pos: (0, 0), start: 0 }];
-        let mut meta = DefaultMetadata::new(expr, Some(format!("path/to/file")), None);
+        let expr = vec![Token::new("\n    ", Span::default())];
+        let mut meta = DefaultMetadata::new(expr, Some("path/to/file".to_string()), None);
         let res = indent_with(&mut meta, 4);
         assert!(res.is_ok());
     }
+
+    #[test]
+    fn attempt_rollback() {
+        let expr = ["let", "apple", "let", "banana", "="].iter()
+            .map(|word| Token::new(*word, Span::default()))
+            .collect();
+        let mut meta = SymbolMetadata::new(expr, None, None);
+        let declare = |meta: &mut SymbolMetadata| -> Result<(), Failure> {
+            token(meta, "let")?;
+            let name = variable(meta, vec![])?;
+            meta.symbols.push(name);
+            token(meta, ";")?;
+            Ok(())
+        };
+        // Failed declaration does not leave the symbol behind
+        assert!(attempt(&mut meta, declare).is_err());
+        assert_eq!((meta.index, meta.symbols.len()), (0, 0));
+        let result = attempt(&mut meta, |meta| {
+            token(meta, "let")?;
+            let name = variable(meta, vec![])?;
+            meta.symbols.push(name);
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert_eq!((meta.index, meta.symbols.clone()), (2, vec!["apple".to_string()]));
+    }
 }
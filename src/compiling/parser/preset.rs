@@ -7,28 +7,29 @@ use super::Metadata;
 /// Matches one token with a word that would be considered as a variable name.
 /// If desired - one can extend this implementation with other chars.
 pub fn variable(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => {
+    match meta.get_current_word() {
+        Some(word) => {
             // This boolean stores false if we are past
             // the first letter otherwise it's true
             let mut is_later: bool = false;
-            for letter in token.word.chars() {
+            for letter in word.chars() {
                 // Check if rest of the letters are alphanumeric
                 if is_later {
                     if !(letter.is_alphanumeric() || extend.contains(&letter)) {
-                        return Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token))))
+                        return Err(Failure::Quiet(PositionInfo::from_metadata(meta)))
                     }
                 }
                 // Check if first letter is alphabetic
                 else {
                     if !(letter.is_alphabetic() || extend.contains(&letter)) {
-                        return Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token))))
+                        return Err(Failure::Quiet(PositionInfo::from_metadata(meta)))
                     }
                     is_later = true;
                 }
             }
+            let word = word.into_owned();
             meta.increment_index();
-            Ok(token.word)
+            Ok(word)
         }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
@@ -39,12 +40,13 @@ pub fn variable(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, F
 /// Matches one token with a word that consists of letters only.
 /// If desired - one can extend this implementation with other chars.
 pub fn alphabetic(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => {
-            if token.word.chars().all(|letter| letter.is_alphabetic() || extend.contains(&letter)) {
+    match meta.get_current_word() {
+        Some(word) => {
+            if word.chars().all(|letter| letter.is_alphabetic() || extend.contains(&letter)) {
+                let word = word.into_owned();
                 meta.increment_index();
-                Ok(token.word)
-            } else { Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)))) }
+                Ok(word)
+            } else { Err(Failure::Quiet(PositionInfo::from_metadata(meta))) }
         }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
@@ -55,12 +57,13 @@ pub fn alphabetic(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String,
 /// Matches one token with a word that consists of letters or numbers only.
 /// If desired - one can extend this implementation with other chars.
 pub fn alphanumeric(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => {
-            if token.word.chars().all(|letter| letter.is_alphanumeric() || extend.contains(&letter)) {
+    match meta.get_current_word() {
+        Some(word) => {
+            if word.chars().all(|letter| letter.is_alphanumeric() || extend.contains(&letter)) {
+                let word = word.into_owned();
                 meta.increment_index();
-                Ok(token.word)
-            } else { Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)))) }
+                Ok(word)
+            } else { Err(Failure::Quiet(PositionInfo::from_metadata(meta))) }
         }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
@@ -71,12 +74,13 @@ pub fn alphanumeric(meta: &mut impl Metadata, extend: Vec<char>) -> Result<Strin
 /// Matches a token of which word is a string of digits.
 /// If desired - one can extend this implementation with other chars.
 pub fn numeric(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => {
-            if token.word.chars().all(|letter| letter.is_numeric() || extend.contains(&letter)) {
+    match meta.get_current_word() {
+        Some(word) => {
+            if word.chars().all(|letter| letter.is_numeric() || extend.contains(&letter)) {
+                let word = word.into_owned();
                 meta.increment_index();
-                Ok(token.word)
-            } else { Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)))) }
+                Ok(word)
+            } else { Err(Failure::Quiet(PositionInfo::from_metadata(meta))) }
         }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
@@ -87,21 +91,19 @@ pub fn numeric(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Fa
 /// Matches a positive or negetive integer.
 /// If desired - one can extend this implementation with other chars.
 pub fn integer(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => {
-            let mut word = token.word.clone();
+    match meta.get_current_word() {
+        Some(word) => {
             // If it's a negative number - consume
-            if word.starts_with('-') {
-                word = word[1..].chars().collect();
-            }
+            let digits = word.strip_prefix('-').unwrap_or(&word);
             // For each further letter match a digit
-            for letter in word.chars() {
+            for letter in digits.chars() {
                 if !(letter.is_numeric() || extend.contains(&letter)) {
-                    return Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token))))
+                    return Err(Failure::Quiet(PositionInfo::from_metadata(meta)))
                 }
             }
+            let word = word.into_owned();
             meta.increment_index();
-            Ok(token.word)
+            Ok(word)
         }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
@@ -112,27 +114,25 @@ pub fn integer(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Fa
 /// Matches a number that contains a floating point (has a dot in decimal notation)
 /// If desired - one can extend this implementation with other chars.
 pub fn float(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
-    match meta.get_current_token() {
-        Some(token) => {
-            let mut word = token.word.clone();
+    match meta.get_current_word() {
+        Some(word) => {
             // If it's a negative number - consume
-            if word.starts_with('-') {
-                word = word[1..].chars().collect();
-            }
+            let digits = word.strip_prefix('-').unwrap_or(&word);
             // Determine if 'dot' was found
             let mut is_frac = false;
-            for letter in word.chars() {
+            for letter in digits.chars() {
                 if letter == '.' {
                     // Set fraction if dot - exit match otherwise
-                    is_frac = if is_frac { return Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)))) } else { true };
+                    is_frac = if is_frac { return Err(Failure::Quiet(PositionInfo::from_metadata(meta))) } else { true };
                     continue
                 }
                 if !(letter.is_numeric() || extend.contains(&letter)) {
-                    return Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token))))
+                    return Err(Failure::Quiet(PositionInfo::from_metadata(meta)))
                 }
             }
+            let word = word.into_owned();
             meta.increment_index();
-            Ok(token.word)
+            Ok(word)
         }
         None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
     }
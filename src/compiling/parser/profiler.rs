@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+use super::{Tracer, TraceEvent, TraceOutcome};
+
+/// Environment variable that enables parser profiling without recompiling
+pub const PROFILE_ENV: &str = "HERACLITUS_PROFILE";
+
+/// Aggregated statistics of a single syntax module
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileEntry {
+    /// Name of the syntax module
+    pub name: &'static str,
+    /// How many times the module was called
+    pub calls: usize,
+    /// How many calls parsed successfully
+    pub successes: usize,
+    /// How many calls failed quietly
+    pub quiet_failures: usize,
+    /// How many calls failed loudly
+    pub loud_failures: usize,
+    /// Time spent in this module excluding the modules it called
+    pub self_time: Duration,
+    /// Time spent in this module including the modules it called.
+    /// Recursive calls are counted only once.
+    pub total_time: Duration,
+    /// Tokens consumed by successful calls
+    pub tokens_consumed: usize,
+    /// Tokens consumed by failed calls that had to be backtracked
+    pub tokens_backtracked: usize
+}
+
+/// Per module parse profiling report
+///
+/// Profile aggregates the events recorded by the `Tracer` by the name of the syntax module.
+/// It is enabled by `CompilerBuilder::profile` which returns it in `Compilation::profile`
+/// or by setting the `HERACLITUS_PROFILE` environment variable which writes it to the standard error.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let mut tracer = Tracer::new();
+/// tracer.enter("Expr", 0);
+/// tracer.leave(3, TraceOutcome::Ok);
+/// let profile = Profile::from_tracer(&tracer);
+/// assert_eq!(profile.entries()[0].tokens_consumed, 3);
+/// println!("{profile}");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    entries: Vec<ProfileEntry>
+}
+
+impl Profile {
+    /// Check if profiling was requested by the environment variable
+    pub fn is_enabled_by_env() -> bool {
+        std::env::var(PROFILE_ENV).is_ok_and(|value| !value.is_empty() && value != "0")
+    }
+
+    /// Aggregate events recorded by the tracer
+    pub fn from_tracer(tracer: &Tracer) -> Self {
+        Self::from_events(tracer.events())
+    }
+
+    /// Aggregate recorded trace events
+    pub fn from_events(events: &[TraceEvent]) -> Self {
+        let mut children_time = vec![Duration::ZERO; events.len()];
+        for event in events.iter() {
+            if let Some(parent) = event.parent {
+                children_time[parent] += event.duration;
+            }
+        }
+        let mut entries: HashMap<&'static str, ProfileEntry> = HashMap::new();
+        for (id, event) in events.iter().enumerate() {
+            let entry = entries.entry(event.name).or_insert_with(|| ProfileEntry {
+                name: event.name,
+                ..Default::default()
+            });
+            let tokens = event.end.saturating_sub(event.start);
+            entry.calls += 1;
+            match event.outcome {
+                TraceOutcome::Ok => {
+                    entry.successes += 1;
+                    entry.tokens_consumed += tokens;
+                }
+                TraceOutcome::Quiet => {
+                    entry.quiet_failures += 1;
+                    entry.tokens_backtracked += tokens;
+                }
+                TraceOutcome::Loud => {
+                    entry.loud_failures += 1;
+                    entry.tokens_backtracked += tokens;
+                }
+            }
+            entry.self_time += event.duration.saturating_sub(children_time[id]);
+            if !Self::is_recursive(events, event) {
+                entry.total_time += event.duration;
+            }
+        }
+        let mut entries = entries.into_values().collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.self_time.cmp(&a.self_time).then(a.name.cmp(b.name)));
+        Profile { entries }
+    }
+
+    // Check if any of the callers is the same module
+    fn is_recursive(events: &[TraceEvent], event: &TraceEvent) -> bool {
+        let mut parent = event.parent;
+        while let Some(id) = parent {
+            if events[id].name == event.name {
+                return true
+            }
+            parent = events[id].parent;
+        }
+        false
+    }
+
+    /// Get entries sorted by the self time in descending order
+    pub fn entries(&self) -> &[ProfileEntry] {
+        &self.entries
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let width = self.entries.iter()
+            .map(|entry| entry.name.chars().count())
+            .chain([6])
+            .max()
+            .unwrap_or(6);
+        writeln!(
+            formatter,
+            "{:<width$} {:>7} {:>7} {:>7} {:>7} {:>12} {:>12} {:>9} {:>11}",
+            "Module", "Calls", "Ok", "Quiet", "Loud", "Self (us)", "Total (us)", "Consumed", "Backtracked"
+        )?;
+        for entry in self.entries.iter() {
+            writeln!(
+                formatter,
+                "{:<width$} {:>7} {:>7} {:>7} {:>7} {:>12} {:>12} {:>9} {:>11}",
+                entry.name,
+                entry.calls,
+                entry.successes,
+                entry.quiet_failures,
+                entry.loud_failures,
+                entry.self_time.as_micros(),
+                entry.total_time.as_micros(),
+                entry.tokens_consumed,
+                entry.tokens_backtracked
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use super::*;
+
+    fn event(name: &'static str, parent: Option<usize>, range: (usize, usize), outcome: TraceOutcome, duration: u64) -> TraceEvent {
+        TraceEvent {
+            name,
+            depth: 0,
+            parent,
+            start: range.0,
+            end: range.1,
+            outcome,
+            offset: Duration::ZERO,
+            duration: Duration::from_micros(duration)
+        }
+    }
+
+    #[test]
+    fn aggregates_events() {
+        let events = vec![
+            event("Expr", None, (0, 3), TraceOutcome::Ok, 100),
+            event("Add", Some(0), (0, 1), TraceOutcome::Quiet, 30),
+            event("Expr", Some(1), (0, 1), TraceOutcome::Ok, 10),
+            event("Number", Some(0), (0, 3), TraceOutcome::Ok, 20)
+        ];
+        let profile = Profile::from_events(&events);
+        let expr = profile.entries().iter().find(|entry| entry.name == "Expr").unwrap();
+        assert_eq!(expr.calls, 2);
+        assert_eq!(expr.successes, 2);
+        assert_eq!(expr.tokens_consumed, 4);
+        assert_eq!(expr.self_time, Duration::from_micros(60));
+        assert_eq!(expr.total_time, Duration::from_micros(100));
+        let add = profile.entries().iter().find(|entry| entry.name == "Add").unwrap();
+        assert_eq!(add.quiet_failures, 1);
+        assert_eq!(add.tokens_backtracked, 1);
+        assert_eq!(add.self_time, Duration::from_micros(20));
+        assert_eq!(profile.entries()[0].name, "Expr");
+    }
+
+    #[test]
+    fn renders_table() {
+        let events = vec![event("Expr", None, (0, 3), TraceOutcome::Ok, 100)];
+        let table = Profile::from_events(&events).to_string();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Module"));
+        assert!(lines[1].starts_with("Expr"));
+        assert!(lines[1].ends_with("3           0"));
+    }
+}
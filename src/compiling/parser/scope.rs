@@ -0,0 +1,307 @@
+//! Scoped symbol table
+//!
+//! This module lets the parser know which names are declared at the current point of parsing.
+//! This is required by the context-sensitive languages that parse the code differently
+//! depending on whether the name is a variable, type or something else.
+
+use std::collections::HashMap;
+use crate::compiling::failing::position_info::PositionInfo;
+use crate::compiling::failing::message::Message;
+use crate::compiling::failing::failure::Failure;
+use crate::compiling::failing::catalog::{keys, translate};
+use super::Metadata;
+
+/// Symbol defined in the scope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol<T> {
+    /// Name of the symbol
+    pub name: String,
+    /// Data attached to the symbol
+    pub data: T,
+    /// Position of the definition
+    pub position: PositionInfo
+}
+
+/// Stack of scopes with symbols defined in them
+///
+/// The outermost (global) scope cannot be popped. Changes made after `Scopes::checkpoint`
+/// are recorded so that they can be undone with `Scopes::rollback` without cloning the whole table.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let mut scopes = Scopes::new();
+/// let position = PositionInfo::at_pos(None, (1, 5), 1);
+/// scopes.define("a", "number", position.clone()).unwrap();
+/// scopes.push_scope();
+/// scopes.define("a", "text", position.clone()).unwrap();
+/// assert_eq!(scopes.lookup("a").unwrap().data, "text");
+/// scopes.pop_scope();
+/// assert_eq!(scopes.lookup("a").unwrap().data, "number");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scopes<T> {
+    scopes: Vec<HashMap<String, Symbol<T>>>,
+    // Changes made since the outermost checkpoint
+    journal: Vec<Change<T>>,
+    // Amount of checkpoints that were neither rolled back nor committed
+    checkpoints: usize
+}
+
+// Change of the symbol table that can be undone
+#[derive(Debug, Clone)]
+enum Change<T> {
+    Define(String),
+    Update(usize, Symbol<T>),
+    Push,
+    Pop(HashMap<String, Symbol<T>>)
+}
+
+/// Point in the history of the symbol table created with `Scopes::checkpoint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopesCheckpoint(usize);
+
+impl<T: PartialEq> PartialEq for Scopes<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.scopes == other.scopes
+    }
+}
+
+impl<T: Eq> Eq for Scopes<T> {}
+
+impl<T> Default for Scopes<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Scopes<T> {
+    /// Create a symbol table with the global scope
+    pub fn new() -> Self {
+        Scopes { scopes: vec![HashMap::new()], journal: vec![], checkpoints: 0 }
+    }
+
+    // Record the change if it may be rolled back
+    fn record(&mut self, change: Change<T>) {
+        if self.checkpoints > 0 {
+            self.journal.push(change);
+        }
+    }
+
+    /// Enter a new scope
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+        self.record(Change::Push);
+    }
+
+    /// Get amount of scopes including the global one
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Define a symbol in the current scope
+    ///
+    /// If the symbol is already defined in the current scope - the existing symbol is returned as an error.
+    /// Symbols of the outer scopes can be shadowed.
+    pub fn define<S: AsRef<str>>(&mut self, name: S, data: T, position: PositionInfo) -> Result<(), &Symbol<T>> {
+        let name = name.as_ref();
+        if self.scopes.last().unwrap().contains_key(name) {
+            return Err(&self.scopes.last().unwrap()[name])
+        }
+        self.scopes.last_mut().unwrap().insert(name.to_string(), Symbol { name: name.to_string(), data, position });
+        self.record(Change::Define(name.to_string()));
+        Ok(())
+    }
+
+    /// Find the symbol in the innermost scope that defines it
+    pub fn lookup<S: AsRef<str>>(&self, name: S) -> Option<&Symbol<T>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name.as_ref()))
+    }
+
+    /// Find the symbol in the current scope only
+    pub fn lookup_local<S: AsRef<str>>(&self, name: S) -> Option<&Symbol<T>> {
+        self.scopes.last().and_then(|scope| scope.get(name.as_ref()))
+    }
+
+    /// Check if the symbol is defined in any of the scopes
+    pub fn is_defined<S: AsRef<str>>(&self, name: S) -> bool {
+        self.lookup(name).is_some()
+    }
+
+    /// Start recording the changes so that they can be rolled back to this point
+    ///
+    /// Every checkpoint has to be either rolled back or committed.
+    pub fn checkpoint(&mut self) -> ScopesCheckpoint {
+        self.checkpoints += 1;
+        ScopesCheckpoint(self.journal.len())
+    }
+
+    /// Undo the changes made since the checkpoint
+    pub fn rollback(&mut self, checkpoint: ScopesCheckpoint) {
+        while self.journal.len() > checkpoint.0 {
+            match self.journal.pop().unwrap() {
+                Change::Define(name) => {
+                    self.scopes.last_mut().unwrap().remove(&name);
+                }
+                Change::Update(depth, symbol) => {
+                    self.scopes[depth].insert(symbol.name.clone(), symbol);
+                }
+                Change::Push => {
+                    self.scopes.pop();
+                }
+                Change::Pop(scope) => self.scopes.push(scope)
+            }
+        }
+        self.commit(checkpoint);
+    }
+
+    /// Accept the changes made since the checkpoint
+    ///
+    /// Changes are forgotten once there are no checkpoints left that could roll them back.
+    pub fn commit(&mut self, _checkpoint: ScopesCheckpoint) {
+        self.checkpoints = self.checkpoints.saturating_sub(1);
+        if self.checkpoints == 0 {
+            self.journal.clear();
+        }
+    }
+}
+
+impl<T: Clone> Scopes<T> {
+    /// Leave the current scope and return the symbols defined in it
+    ///
+    /// The global scope is never popped.
+    pub fn pop_scope(&mut self) -> Option<HashMap<String, Symbol<T>>> {
+        if self.scopes.len() > 1 {
+            let scope = self.scopes.pop()?;
+            if self.checkpoints > 0 {
+                self.journal.push(Change::Pop(scope.clone()));
+            }
+            Some(scope)
+        } else {
+            None
+        }
+    }
+
+    /// Find the symbol in the innermost scope that defines it to change its data
+    pub fn lookup_mut<S: AsRef<str>>(&mut self, name: S) -> Option<&mut Symbol<T>> {
+        let name = name.as_ref();
+        let depth = self.scopes.iter().rposition(|scope| scope.contains_key(name))?;
+        if self.checkpoints > 0 {
+            let symbol = self.scopes[depth][name].clone();
+            self.journal.push(Change::Update(depth, symbol));
+        }
+        self.scopes[depth].get_mut(name)
+    }
+}
+
+/// Metadata that keeps track of the defined symbols
+///
+/// Implement this trait to use `define` and `resolve` pattern helpers.
+/// Symbol table is rolled back on failed parsing attempts if its checkpoint is included in `Metadata::snapshot_state`.
+pub trait ScopedMetadata<T>: Metadata {
+    /// Getter for the symbol table
+    fn get_scopes(&self) -> &Scopes<T>;
+    /// Mutable getter for the symbol table
+    fn get_scopes_mut(&mut self) -> &mut Scopes<T>;
+}
+
+/// Define a symbol in the current scope or report its redefinition
+///
+/// The error points at the new definition and at the previous one.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn parse<M: ScopedMetadata<()>>(meta: &mut M) -> Result<(), Failure> {
+/// let tok = meta.get_current_token();
+/// let name = variable(meta, vec![])?;
+/// let position = PositionInfo::from_token(meta, tok);
+/// define(meta, name, (), position)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn define<T, M: ScopedMetadata<T>, S: AsRef<str>>(meta: &mut M, name: S, data: T, position: PositionInfo) -> Result<(), Failure> {
+    let name = name.as_ref();
+    let previous = match meta.get_scopes_mut().define(name, data, position.clone()) {
+        Ok(()) => return Ok(()),
+        Err(symbol) => symbol.position.clone()
+    };
+    let message = Message::new_err_at_position(meta, position)
+        .message(translate(keys::REDEFINED_SYMBOL, &[("name", name)]))
+        .comment(translate(keys::REDEFINED_SYMBOL_HELP, &[("name", name)]))
+        .note(previous, translate(keys::PREVIOUS_DEFINITION, &[("name", name)]));
+    Err(Failure::Loud(message))
+}
+
+/// Find the defined symbol or report that it's undefined
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn parse<M: ScopedMetadata<()>>(meta: &mut M) -> Result<(), Failure> {
+/// let tok = meta.get_current_token();
+/// let name = variable(meta, vec![])?;
+/// let position = PositionInfo::from_token(meta, tok);
+/// let symbol = resolve(meta, name, position)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn resolve<T, M: ScopedMetadata<T>, S: AsRef<str>>(meta: &M, name: S, position: PositionInfo) -> Result<&Symbol<T>, Failure> {
+    let name = name.as_ref();
+    match meta.get_scopes().lookup(name) {
+        Some(symbol) => Ok(symbol),
+        None => {
+            let message = Message::new_err_at_position(meta, position)
+                .message(translate(keys::UNDEFINED_SYMBOL, &[("name", name)]));
+            Err(Failure::Loud(message))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shadowing() {
+        let mut scopes = Scopes::new();
+        let first = PositionInfo::at_pos(None, (1, 5), 1);
+        let second = PositionInfo::at_pos(None, (2, 5), 1);
+        assert!(scopes.define("a", 1, first.clone()).is_ok());
+        assert_eq!(scopes.define("a", 2, second.clone()).unwrap_err().position, first);
+        scopes.push_scope();
+        assert!(scopes.lookup_local("a").is_none());
+        assert!(scopes.define("a", 3, second.clone()).is_ok());
+        scopes.lookup_mut("a").unwrap().data = 4;
+        assert_eq!(scopes.lookup("a").unwrap().data, 4);
+        let popped = scopes.pop_scope().unwrap();
+        assert_eq!(popped["a"].position, second);
+        assert_eq!(scopes.lookup("a").unwrap().data, 1);
+        // Global scope stays in place
+        assert!(scopes.pop_scope().is_none());
+        assert_eq!(scopes.depth(), 1);
+        assert!(!scopes.is_defined("b"));
+    }
+
+    #[test]
+    fn rollback() {
+        let position = PositionInfo::at_pos(None, (1, 5), 1);
+        let mut scopes = Scopes::new();
+        scopes.define("a", 1, position.clone()).unwrap();
+        let outer = scopes.checkpoint();
+        scopes.lookup_mut("a").unwrap().data = 2;
+        scopes.push_scope();
+        scopes.define("b", 3, position.clone()).unwrap();
+        let inner = scopes.checkpoint();
+        scopes.pop_scope();
+        scopes.define("c", 4, position.clone()).unwrap();
+        scopes.rollback(inner);
+        assert_eq!(scopes.depth(), 2);
+        assert!(scopes.is_defined("b") && !scopes.is_defined("c"));
+        scopes.rollback(outer);
+        assert_eq!(scopes.depth(), 1);
+        assert_eq!(scopes.lookup("a").unwrap().data, 1);
+        assert!(!scopes.is_defined("b"));
+        // Changes are not recorded without a checkpoint
+        scopes.define("d", 5, position).unwrap();
+        assert!(scopes.journal.is_empty());
+    }
+}
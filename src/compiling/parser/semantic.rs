@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use crate::compiling::{ColumnUnit, SourceFile, Span, Token};
+use super::Metadata;
+
+/// Token types defined by the Language Server Protocol
+pub const STANDARD_TOKEN_TYPES: [&str; 23] = [
+    "namespace", "type", "class", "enum", "interface", "struct", "typeParameter", "parameter",
+    "variable", "property", "enumMember", "event", "function", "method", "macro", "keyword",
+    "modifier", "comment", "string", "number", "regexp", "operator", "decorator"
+];
+
+/// Token modifiers defined by the Language Server Protocol
+pub const STANDARD_TOKEN_MODIFIERS: [&str; 10] = [
+    "declaration", "definition", "readonly", "static", "deprecated", "abstract",
+    "async", "modification", "documentation", "defaultLibrary"
+];
+
+/// Legend of the semantic token types and modifiers
+///
+/// Editors receive the legend once and then refer to the types by their index
+/// and to the modifiers by the bits of a bitset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticLegend {
+    /// Names of the token types
+    pub token_types: Vec<String>,
+    /// Names of the token modifiers
+    pub token_modifiers: Vec<String>
+}
+
+impl SemanticLegend {
+    /// Create a legend with custom token types and modifiers
+    pub fn new<T: AsRef<str>, S: AsRef<str>>(token_types: Vec<T>, token_modifiers: Vec<S>) -> Self {
+        SemanticLegend {
+            token_types: token_types.iter().map(|name| name.as_ref().to_string()).collect(),
+            token_modifiers: token_modifiers.iter().map(|name| name.as_ref().to_string()).collect()
+        }
+    }
+
+    /// Create a legend with the types and modifiers defined by the Language Server Protocol
+    pub fn standard() -> Self {
+        Self::new(STANDARD_TOKEN_TYPES.to_vec(), STANDARD_TOKEN_MODIFIERS.to_vec())
+    }
+
+    /// Get index of the token type
+    pub fn token_type(&self, name: &str) -> Option<u32> {
+        self.token_types.iter().position(|token_type| token_type == name).map(|index| index as u32)
+    }
+
+    /// Get bitset of the token modifiers
+    pub fn token_modifiers(&self, names: &[&str]) -> Option<u32> {
+        names.iter().try_fold(0, |bits, name| {
+            let index = self.token_modifiers.iter().position(|modifier| modifier == name)?;
+            (index < 32).then_some(bits | 1 << index)
+        })
+    }
+}
+
+impl Default for SemanticLegend {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Semantic class of a single token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticTag {
+    /// Index of the token type in the legend
+    pub token_type: u32,
+    /// Bitset of the token modifiers in the legend
+    pub modifiers: u32
+}
+
+/// Semantic classes of the tokens recorded while parsing
+///
+/// Lexer cannot tell whether an identifier is a function, a variable or a type,
+/// but syntax modules can. They tag the tokens by their index and the tags are
+/// encoded to the `semanticTokens` data of the Language Server Protocol.
+/// Tags of the tokens that were consumed by a failed parsing attempt are removed on rollback.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # let rules = Rules::new(vec!['='], vec![], reg![]);
+/// let cc = Compiler::builder("HerbScript", rules).source("let a = 12").build().unwrap();
+/// let mut meta = DefaultMetadata::new(cc.tokenize().unwrap(), None, Some(cc.code.clone()));
+/// meta.tag_token(0, "keyword", &[]);
+/// meta.tag_token(1, "variable", &["declaration"]);
+/// assert_eq!(meta.encode_semantic_tokens(), vec![
+///     0, 0, 3, 15, 0,
+///     0, 4, 1, 8, 1
+/// ]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SemanticTokens {
+    legend: SemanticLegend,
+    tags: BTreeMap<usize, SemanticTag>
+}
+
+impl SemanticTokens {
+    /// Create an empty collection of tags described by the legend
+    pub fn new(legend: SemanticLegend) -> Self {
+        SemanticTokens {
+            legend,
+            tags: BTreeMap::new()
+        }
+    }
+
+    /// Get the legend of the tags
+    pub fn legend(&self) -> &SemanticLegend {
+        &self.legend
+    }
+
+    /// Tag the token under given index
+    ///
+    /// Returns `false` if the type or any of the modifiers is not in the legend.
+    pub fn tag(&mut self, index: usize, token_type: &str, modifiers: &[&str]) -> bool {
+        let tag = self.legend.token_type(token_type)
+            .zip(self.legend.token_modifiers(modifiers))
+            .map(|(token_type, modifiers)| SemanticTag { token_type, modifiers });
+        match tag {
+            Some(tag) => {
+                self.tags.insert(index, tag);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Get the tag of the token under given index
+    pub fn get(&self, index: usize) -> Option<SemanticTag> {
+        self.tags.get(&index).copied()
+    }
+
+    /// Remove the tags of the tokens starting from given index
+    pub fn truncate(&mut self, index: usize) {
+        self.tags.split_off(&index);
+    }
+
+    /// Get amount of tagged tokens
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Check if no token was tagged
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Encode the tags of the tokens of the currently parsed source
+    pub fn encode<M: Metadata + ?Sized>(&self, meta: &M) -> Vec<u32> {
+        match meta.get_source() {
+            Some(source) => self.encode_with(source, |index| meta.get_span_at(index)),
+            None => vec![]
+        }
+    }
+
+    /// Encode the tags of the tokens that come from the source
+    ///
+    /// Every token is described by five integers: line and start character relative to the previous token,
+    /// length, type and modifiers. Characters are counted in UTF-16 code units and tokens that span
+    /// multiple lines are split into one token per line.
+    pub fn encode_tokens(&self, tokens: &[Token], source: &SourceFile) -> Vec<u32> {
+        self.encode_with(source, |index| tokens.get(index).map(|token| token.span))
+    }
+
+    fn encode_with(&self, source: &SourceFile, span_at: impl Fn(usize) -> Option<Span>) -> Vec<u32> {
+        let code = source.code();
+        let mut data = vec![];
+        let mut previous = (0, 0);
+        for (index, tag) in self.tags.iter() {
+            let Some(span) = span_at(*index).filter(|span| span.file == source.id()) else {
+                continue
+            };
+            let Some(text) = code.get(span.range()) else {
+                continue
+            };
+            let mut start = span.start();
+            for part in text.split('\n') {
+                let (row, _) = source.offset_position(start);
+                let line_start = source.offset(row, 1).unwrap_or(start);
+                let line = (row - 1) as u32;
+                let character = ColumnUnit::Utf16.measure(&code[line_start..start]) as u32;
+                let length = ColumnUnit::Utf16.measure(part.strip_suffix('\r').unwrap_or(part)) as u32;
+                start += part.len() + 1;
+                // Tokens have to be sorted by their position
+                if length == 0 || (line, character) < previous {
+                    continue
+                }
+                let delta_start = match line == previous.0 {
+                    true => character - previous.1,
+                    false => character
+                };
+                data.extend([line - previous.0, delta_start, length, tag.token_type, tag.modifiers]);
+                previous = (line, character);
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compiling::{Compiler, DefaultMetadata, SourceMap};
+    use crate::compiling_rules::{Region, Rules};
+    use crate::reg;
+
+    #[test]
+    fn legend() {
+        let legend = SemanticLegend::new(vec!["function", "variable"], vec!["readonly", "static"]);
+        assert_eq!(legend.token_type("variable"), Some(1));
+        assert_eq!(legend.token_type("type"), None);
+        assert_eq!(legend.token_modifiers(&["static", "readonly"]), Some(0b11));
+        assert_eq!(legend.token_modifiers(&["async"]), None);
+    }
+
+    #[test]
+    fn encode_multiline() {
+        let rules = Rules::new(vec!['='], vec![], reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            })
+        ]);
+        let cc = Compiler::builder("Test", rules).source("let ą = 'żó\nx' a").build().unwrap();
+        let mut meta = DefaultMetadata::new(cc.tokenize().unwrap(), None, Some(cc.code.clone()));
+        meta.tag_token(1, "variable", &[]);
+        meta.tag_token(3, "string", &[]);
+        meta.tag_token(4, "variable", &["readonly"]);
+        assert!(!meta.semantic.tag(0, "keyword", &["unknown"]));
+        assert_eq!(meta.encode_semantic_tokens(), vec![
+            0, 4, 1, 8, 0,
+            0, 4, 3, 18, 0,
+            1, 0, 2, 18, 0,
+            0, 3, 1, 8, 4
+        ]);
+    }
+
+    #[test]
+    fn encode_other_source() {
+        let mut sources = SourceMap::new();
+        let first = sources.add(None, "a b");
+        let second = sources.add(None, "c");
+        let tokens = vec![
+            Token::new("a", Span::new(first, 0, 1)),
+            Token::new("c", Span::new(second, 0, 1)),
+            Token::new("b", Span::new(first, 2, 3))
+        ];
+        let mut semantic = SemanticTokens::default();
+        for index in 0..3 {
+            semantic.tag(index, "variable", &[]);
+        }
+        let data = semantic.encode_tokens(&tokens, sources.get(first).unwrap());
+        assert_eq!(data, vec![0, 0, 1, 8, 0, 0, 2, 1, 8, 0]);
+    }
+
+    #[test]
+    fn rollback_tags() {
+        let mut meta = DefaultMetadata::new(vec![], None, None);
+        meta.tag_token(0, "keyword", &[]);
+        meta.set_index(1);
+        let checkpoint = meta.checkpoint();
+        meta.tag_token(1, "function", &[]);
+        meta.tag_token(2, "parameter", &[]);
+        meta.rollback(checkpoint);
+        assert_eq!(meta.semantic.len(), 1);
+        assert_eq!(meta.semantic.get(0), Some(SemanticTag { token_type: 15, modifiers: 0 }));
+    }
+}
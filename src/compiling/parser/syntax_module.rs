@@ -1,7 +1,7 @@
 use crate::compiling::failing::failure::Failure;
 use colored::Colorize;
 
-use super::Metadata;
+use super::{Metadata, TraceOutcome};
 
 #[macro_export]
 /// This macro is a syntax sugar for the name method
@@ -74,7 +74,7 @@ pub trait SyntaxModule<M: Metadata> {
                 println!("{padding}[Entered] {}", Self::name());
                 meta.set_debug(debug + 1);
                 let time = std::time::Instant::now();
-                let result = self.parse(meta);
+                let result = parse_traced(self, meta);
                 match result {
                     Ok(()) => println!("{padding}{} {} ({}ms)", "[Left]".green(), Self::name(), time.elapsed().as_millis()),
                     Err(_) => println!("{padding}{} {} ({}ms)", "[Failed]".red(), Self::name(), time.elapsed().as_millis())
@@ -82,6 +82,8 @@ pub trait SyntaxModule<M: Metadata> {
                 meta.set_debug(debug);
                 result
             }
+            // Tracing alone does not print the call stack
+            None if meta.get_tracer().is_some() => parse_traced(self, meta),
             None => {
                 meta.set_debug(0);
                 self.parse_debug(meta)
@@ -90,12 +92,31 @@ pub trait SyntaxModule<M: Metadata> {
     }
 }
 
+// Parse the module and record the call in the tracer if there is one
+fn parse_traced<M: Metadata, S: SyntaxModule<M> + ?Sized>(module: &mut S, meta: &mut M) -> SyntaxResult {
+    let start = meta.get_index();
+    if let Some(tracer) = meta.get_tracer() {
+        tracer.enter(S::name(), start);
+    }
+    let result = module.parse(meta);
+    let end = meta.get_index();
+    if let Some(tracer) = meta.get_tracer() {
+        let outcome = match &result {
+            Ok(()) => TraceOutcome::Ok,
+            Err(Failure::Quiet(_)) => TraceOutcome::Quiet,
+            Err(Failure::Loud(_)) => TraceOutcome::Loud
+        };
+        tracer.leave(end, outcome);
+    }
+    result
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::compiling::parser::pattern::*;
     use crate::compiling::parser::preset::*;
-    use crate::compiling::{ Token, DefaultMetadata, Metadata };
+    use crate::compiling::{ Token, Span, DefaultMetadata, Metadata };
 
     struct Expression {}
     impl SyntaxModule<DefaultMetadata> for Expression {
@@ -114,20 +135,12 @@ mod test {
     fn test_token_match() {
         let mut exp = Expression {};
         let dataset1 = vec![
-            Token {
-                word: format!("let"),
-                pos: (0, 0),
-                start: 0
-            }
+            Token::new("let", Span::default())
         ];
         let dataset2 = vec![
-            Token {
-                word: format!("tell"),
-                pos: (0, 0),
-                start: 0
-            }
+            Token::new("tell", Span::default())
         ];
-        let path = Some(format!("path/to/file"));
+        let path = Some("path/to/file".to_string());
         let result1 = exp.parse(&mut DefaultMetadata::new(dataset1, path.clone(), None));
         let result2 = exp.parse(&mut DefaultMetadata::new(dataset2, path.clone(), None));
         assert!(result1.is_ok());
@@ -155,17 +168,17 @@ mod test {
         let mut exp = Preset {};
         let dataset = vec![
             // Variable
-            Token { word: format!("_text"), pos: (0, 0), start: 0 },
+            Token::new("_text", Span::default()),
             // Numeric
-            Token { word: format!("12321"), pos: (0, 0), start: 0 },
+            Token::new("12321", Span::default()),
             // Number
-            Token { word: format!("-123.12"), pos: (0, 0), start: 0 },
+            Token::new("-123.12", Span::default()),
             // Integer
-            Token { word: format!("-12"), pos: (0, 0), start: 0 },
+            Token::new("-12", Span::default()),
             // Float
-            Token { word: format!("-.681"), pos: (0, 0), start: 0 }
+            Token::new("-.681", Span::default())
         ];
-        let path = Some(format!("path/to/file"));
+        let path = Some("path/to/file".to_string());
         let result = exp.parse(&mut DefaultMetadata::new(dataset, path, None));
         assert!(result.is_ok());
     }
@@ -179,13 +192,10 @@ mod test {
         #[allow(unused_must_use)]
         fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
             // Any
-            if let Ok(_) = token(meta, "apple") {}
-            else if let Ok(_) = token(meta, "orange") {}
-            else if let Ok(_) = token(meta, "banana") {}
-            else {
-                if let Err(details) = token(meta, "banana") {
-                    return Err(details);
-                }
+            if token(meta, "apple").is_err()
+                && token(meta, "orange").is_err()
+                && token(meta, "banana").is_err() {
+                token(meta, "banana")?;
             }
             // Optional
             token(meta, "optional");
@@ -193,10 +203,10 @@ mod test {
             syntax(meta, &mut Expression::new())?;
             // Repeat
             loop {
-                if let Err(_) = token(meta, "test") {
+                if token(meta, "test").is_err() {
                     break;
                 }
-                if let Err(_) = token(meta, ",") {
+                if token(meta, ",").is_err() {
                     break;
                 }
             }
@@ -211,44 +221,44 @@ mod test {
         let mut exp = PatternModule {};
         // Everything should pass
         let dataset1 = vec![
-            Token { word: format!("orange"), pos: (0, 0), start: 0 },
-            Token { word: format!("optional"), pos: (0, 0), start: 0 },
-            Token { word: format!("let"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token::new("orange", Span::default()),
+            Token::new("optional", Span::default()),
+            Token::new("let", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new(",", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new("end", Span::default())
         ];
         // Token should fail
         let dataset2 = vec![
-            Token { word: format!("kiwi"), pos: (0, 0), start: 0 },
-            Token { word: format!("optional"), pos: (0, 0), start: 0 },
-            Token { word: format!("let"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token::new("kiwi", Span::default()),
+            Token::new("optional", Span::default()),
+            Token::new("let", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new(",", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new("end", Span::default())
         ];
         // Syntax should fail
         let dataset3 = vec![
-            Token { word: format!("orange"), pos: (0, 0), start: 0 },
-            Token { word: format!("tell"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token::new("orange", Span::default()),
+            Token::new("tell", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new(",", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new("end", Span::default())
         ];
         // Token should fail because of repeat matching (this , this) ,
         let dataset4 = vec![
-            Token { word: format!("orange"), pos: (0, 0), start: 0 },
-            Token { word: format!("tell"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token::new("orange", Span::default()),
+            Token::new("tell", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new(",", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new("this", Span::default()),
+            Token::new("end", Span::default())
         ];
-        let path = Some(format!("path/to/file"));
+        let path = Some("path/to/file".to_string());
         let result1 = exp.parse(&mut DefaultMetadata::new(dataset1, path.clone(), None));
         let result2 = exp.parse(&mut DefaultMetadata::new(dataset2, path.clone(), None));
         let result3 = exp.parse(&mut DefaultMetadata::new(dataset3, path.clone(), None));
@@ -0,0 +1,278 @@
+use std::fmt::Write as FmtWrite;
+use std::time::{Duration, Instant};
+use crate::compiling::json::escape_json;
+
+/// Environment variable that enables parser tracing without recompiling
+///
+/// The value is a path to the file where the trace will be written.
+pub const TRACE_ENV: &str = "HERACLITUS_TRACE";
+/// Environment variable that selects the trace format (`chrome` or `tree`)
+///
+/// If it's not set, the format is guessed from the file extension of `HERACLITUS_TRACE`.
+pub const TRACE_FORMAT_ENV: &str = "HERACLITUS_TRACE_FORMAT";
+
+/// Outcome of a single syntax module call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceOutcome {
+    /// Syntax module parsed successfully
+    Ok,
+    /// Syntax module failed with a quiet failure
+    Quiet,
+    /// Syntax module failed with a loud failure
+    Loud
+}
+
+impl TraceOutcome {
+    /// Get the name of this outcome
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceOutcome::Ok => "ok",
+            TraceOutcome::Quiet => "quiet",
+            TraceOutcome::Loud => "loud"
+        }
+    }
+}
+
+/// Format in which the trace can be exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Chrome trace-event JSON that can be opened in `chrome://tracing` or Perfetto
+    Chrome,
+    /// Flat text tree where each line is a single syntax module call
+    Tree
+}
+
+impl TraceFormat {
+    /// Guess the format by the file extension of the output path
+    pub fn from_path(path: impl AsRef<str>) -> Self {
+        if path.as_ref().ends_with(".json") {
+            TraceFormat::Chrome
+        } else {
+            TraceFormat::Tree
+        }
+    }
+
+    /// Parse the format by its name
+    pub fn from_name(name: impl AsRef<str>) -> Option<Self> {
+        match name.as_ref().to_lowercase().as_str() {
+            "chrome" | "json" => Some(TraceFormat::Chrome),
+            "tree" | "text" => Some(TraceFormat::Tree),
+            _ => None
+        }
+    }
+}
+
+/// Record of a single syntax module call
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    /// Name of the syntax module
+    pub name: &'static str,
+    /// How deep in the call stack this module was called
+    pub depth: usize,
+    /// Index of the event of the caller
+    pub parent: Option<usize>,
+    /// Token index at which the module started parsing
+    pub start: usize,
+    /// Token index at which the module finished parsing
+    pub end: usize,
+    /// Outcome of the call
+    pub outcome: TraceOutcome,
+    /// Time since the tracer was created
+    pub offset: Duration,
+    /// How long the call took
+    pub duration: Duration
+}
+
+/// Structured parser trace recorder
+///
+/// Tracer records every syntax module call made in the debug parsing mode.
+/// It is enabled by `Compiler::debug`, `Compiler::trace` or by setting
+/// the `HERACLITUS_TRACE` environment variable to a path of the output file.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let mut tracer = Tracer::new();
+/// tracer.enter("If", 0);
+/// tracer.leave(3, TraceOutcome::Ok);
+/// assert_eq!(tracer.events().len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    epoch: Instant,
+    events: Vec<TraceEvent>,
+    stack: Vec<usize>,
+    output: Option<(String, TraceFormat)>
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tracer {
+    /// Create a new tracer that only records events
+    pub fn new() -> Self {
+        Tracer {
+            epoch: Instant::now(),
+            events: vec![],
+            stack: vec![],
+            output: None
+        }
+    }
+
+    /// Create a new tracer if it was requested by the environment variable
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var(TRACE_ENV).ok().filter(|path| !path.is_empty())?;
+        let format = std::env::var(TRACE_FORMAT_ENV).ok()
+            .and_then(TraceFormat::from_name)
+            .unwrap_or_else(|| TraceFormat::from_path(&path));
+        Some(Tracer::new().output(path, format))
+    }
+
+    /// Set the file to which the trace is written when flushed
+    pub fn output(mut self, path: impl AsRef<str>, format: TraceFormat) -> Self {
+        self.output = Some((path.as_ref().to_string(), format));
+        self
+    }
+
+    /// Record that a syntax module has started parsing
+    pub fn enter(&mut self, name: &'static str, index: usize) {
+        let event = TraceEvent {
+            name,
+            depth: self.stack.len(),
+            parent: self.stack.last().copied(),
+            start: index,
+            end: index,
+            outcome: TraceOutcome::Ok,
+            offset: self.epoch.elapsed(),
+            duration: Duration::ZERO
+        };
+        self.stack.push(self.events.len());
+        self.events.push(event);
+    }
+
+    /// Record that the most recently entered syntax module has finished parsing
+    pub fn leave(&mut self, index: usize, outcome: TraceOutcome) {
+        if let Some(id) = self.stack.pop() {
+            let elapsed = self.epoch.elapsed();
+            let event = &mut self.events[id];
+            event.end = index;
+            event.outcome = outcome;
+            event.duration = elapsed - event.offset;
+        }
+    }
+
+    /// Get all recorded events in the order they were entered
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Render the trace as Chrome trace-event JSON
+    pub fn to_chrome_json(&self) -> String {
+        let events = self.events.iter()
+            .map(|event| format!(
+                "{{\"name\":\"{}\",\"cat\":\"parser\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":1,\"tid\":1,\"args\":{{\"start\":{},\"end\":{},\"outcome\":\"{}\"}}}}",
+                escape_json(event.name),
+                event.offset.as_micros(),
+                event.duration.as_micros(),
+                event.start,
+                event.end,
+                event.outcome.as_str()
+            ))
+            .collect::<Vec<String>>()
+            .join(",\n");
+        format!("{{\"traceEvents\":[\n{events}\n],\"displayTimeUnit\":\"ms\"}}\n")
+    }
+
+    /// Render the trace as a flat text tree
+    pub fn to_tree(&self) -> String {
+        let mut result = String::new();
+        for event in self.events.iter() {
+            let padding = "  ".repeat(event.depth);
+            // Writing to a string cannot fail
+            let _ = writeln!(
+                result,
+                "{padding}{} [{}..{}] {} {}us",
+                event.name,
+                event.start,
+                event.end,
+                event.outcome.as_str(),
+                event.duration.as_micros()
+            );
+        }
+        result
+    }
+
+    /// Render the trace in given format
+    pub fn render(&self, format: TraceFormat) -> String {
+        match format {
+            TraceFormat::Chrome => self.to_chrome_json(),
+            TraceFormat::Tree => self.to_tree()
+        }
+    }
+
+    /// Write the trace to a file in given format
+    pub fn export(&self, path: impl AsRef<str>, format: TraceFormat) -> std::io::Result<()> {
+        std::fs::write(path.as_ref(), self.render(format))
+    }
+
+    /// Write the trace to the output file if one was set
+    pub fn flush(&self) -> std::io::Result<()> {
+        match &self.output {
+            Some((path, format)) => self.export(path, *format),
+            None => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example_tracer() -> Tracer {
+        let mut tracer = Tracer::new();
+        tracer.enter("If", 0);
+        tracer.enter("Expr", 1);
+        tracer.leave(2, TraceOutcome::Ok);
+        tracer.enter("Block", 3);
+        tracer.leave(5, TraceOutcome::Quiet);
+        tracer.leave(5, TraceOutcome::Loud);
+        tracer
+    }
+
+    #[test]
+    fn records_nested_events() {
+        let tracer = example_tracer();
+        let result = tracer.events().iter()
+            .map(|event| (event.name, event.depth, event.parent, event.start, event.end, event.outcome))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            ("If", 0, None, 0, 5, TraceOutcome::Loud),
+            ("Expr", 1, Some(0), 1, 2, TraceOutcome::Ok),
+            ("Block", 1, Some(0), 3, 5, TraceOutcome::Quiet)
+        ];
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn renders_tree() {
+        let tree = example_tracer().to_tree();
+        let lines = tree.lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![
+            "If [0..5] loud",
+            "  Expr [1..2] ok",
+            "  Block [3..5] quiet"
+        ]);
+    }
+
+    #[test]
+    fn renders_chrome_json() {
+        let json = example_tracer().to_chrome_json();
+        assert!(json.starts_with("{\"traceEvents\":["));
+        assert_eq!(json.matches("\"ph\":\"X\"").count(), 3);
+        assert!(json.contains("\"args\":{\"start\":1,\"end\":2,\"outcome\":\"ok\"}"));
+    }
+}
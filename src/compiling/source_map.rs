@@ -0,0 +1,308 @@
+//! Source manager
+//!
+//! This module owns all the sources that were loaded during compilation.
+//! Every source is identified by a compact `FileId` that is stored in tokens and positions
+//! so that diagnostics can be rendered from the loaded code instead of rereading the filesystem.
+
+use std::sync::Arc;
+use super::{ColumnUnit, Span};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+/// Compact identifier of a source stored in the `SourceMap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FileId(pub u32);
+
+/// Single source loaded into the `SourceMap`
+///
+/// Source stores a table of line starts so that the row and column
+/// of any character can be found without scanning the whole code.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SourceFile {
+    id: FileId,
+    path: Option<String>,
+    code: String,
+    // Pairs of byte index and character index of the first character of each line
+    line_starts: Vec<(usize, usize)>
+}
+
+impl SourceFile {
+    fn new(id: FileId, path: Option<String>, code: String) -> Self {
+        let mut line_starts = vec![(0, 0)];
+        for (index, (byte, letter)) in code.char_indices().enumerate() {
+            if letter == '\n' {
+                line_starts.push((byte + 1, index + 1));
+            }
+        }
+        SourceFile { id, path, code, line_starts }
+    }
+
+    /// Get identifier of this source
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+
+    /// Get path of this source if it was loaded from a file
+    pub fn path(&self) -> Option<&String> {
+        self.path.as_ref()
+    }
+
+    /// Get the code of this source
+    pub fn code(&self) -> &String {
+        &self.code
+    }
+
+    /// Get amount of lines in this source
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Get line by its row number (starting from 1) without the new line character
+    pub fn line(&self, row: usize) -> Option<&str> {
+        let (begin, _) = *self.line_starts.get(row.checked_sub(1)?)?;
+        let end = match self.line_starts.get(row) {
+            Some((end, _)) => end - 1,
+            None => self.code.len()
+        };
+        self.code.get(begin..end).map(|line| line.strip_suffix('\r').unwrap_or(line))
+    }
+
+    /// Get position (row, column) of the character under given index
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        let row = self.line_starts.partition_point(|(_, start)| *start <= index);
+        let (_, start) = self.line_starts[row - 1];
+        (row, index - start + 1)
+    }
+
+    /// Get position (row, column) of the character that starts at given byte index
+    pub fn offset_position(&self, offset: usize) -> (usize, usize) {
+        self.offset_position_in(offset, ColumnUnit::Chars)
+    }
+
+    /// Get position (row, column) of the character that starts at given byte index
+    /// with the column counted in given unit
+    pub fn offset_position_in(&self, offset: usize, unit: ColumnUnit) -> (usize, usize) {
+        let offset = offset.min(self.code.len());
+        let row = self.line_starts.partition_point(|(start, _)| *start <= offset);
+        let (start, _) = self.line_starts[row - 1];
+        let col = self.code.get(start..offset).map_or(0, |line| unit.measure(line));
+        (row, col + 1)
+    }
+
+    /// Get byte index of the character at given position (row, column)
+    /// with the column counted in given unit
+    pub fn offset_in(&self, row: usize, col: usize, unit: ColumnUnit) -> Option<usize> {
+        let (start, _) = *self.line_starts.get(row.checked_sub(1)?)?;
+        Some(start + unit.offset(self.line(row)?, col)?)
+    }
+
+    /// Get byte index of the character at given position (row, column)
+    pub fn offset(&self, row: usize, col: usize) -> Option<usize> {
+        let (start, _) = *self.line_starts.get(row.checked_sub(1)?)?;
+        Some(self.advance(start, col.checked_sub(1)?))
+    }
+
+    /// Get byte index that is given amount of characters after the byte index
+    pub fn advance(&self, offset: usize, chars: usize) -> usize {
+        match self.code.get(offset..) {
+            Some(rest) => rest.char_indices().nth(chars).map_or(self.code.len(), |(index, _)| offset + index),
+            None => self.code.len()
+        }
+    }
+
+    /// Get the code that the span points to
+    pub fn slice(&self, span: Span) -> Option<&str> {
+        self.code.get(span.range())
+    }
+
+    /// Get position (row, column) right after the last character of the code
+    pub fn eof_position(&self) -> (usize, usize) {
+        // Trailing new line does not start a line that could be displayed
+        let row = match self.code.ends_with('\n') {
+            true => self.line_count() - 1,
+            false => self.line_count()
+        };
+        match self.line(row) {
+            Some(line) if !self.code.is_empty() => (row, line.chars().count() + 1),
+            _ => (0, 0)
+        }
+    }
+}
+
+/// Map of all the sources that were loaded
+///
+/// Sources are shared between the clones of the map so cloning it is cheap.
+/// The map is serialized as a list of paths and codes of its sources.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let mut sources = SourceMap::new();
+/// let file = sources.add(None, "let a = 12\nlet b = a");
+/// let source = sources.get(file).unwrap();
+/// assert_eq!(source.line(2), Some("let b = a"));
+/// assert_eq!(source.position(15), (2, 5));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    files: Arc<Vec<Arc<SourceFile>>>
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SourceMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.files.iter().map(|file| (&file.path, &file.code)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SourceMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Line starts are computed again when the sources are added back
+        let mut sources = SourceMap::new();
+        for (path, code) in Vec::<(Option<String>, String)>::deserialize(deserializer)? {
+            sources.add(path, code);
+        }
+        Ok(sources)
+    }
+}
+
+impl SourceMap {
+    /// Create an empty source map
+    pub fn new() -> Self {
+        SourceMap { files: Arc::new(vec![]) }
+    }
+
+    /// Add source code that optionally comes from given path
+    ///
+    /// Panics if the map already holds `u32::MAX` sources.
+    pub fn add<T: Into<String>>(&mut self, path: Option<String>, code: T) -> FileId {
+        let id = FileId(u32::try_from(self.files.len()).expect("Source map cannot hold more sources"));
+        Arc::make_mut(&mut self.files).push(Arc::new(SourceFile::new(id, path, code.into())));
+        id
+    }
+
+    /// Load source code from a file
+    ///
+    /// If the file has been already loaded - its existing identifier is returned.
+    pub fn load<T: AsRef<str>>(&mut self, path: T) -> std::io::Result<FileId> {
+        if let Some(id) = self.find(path.as_ref()) {
+            return Ok(id)
+        }
+        let code = std::fs::read_to_string(path.as_ref())?;
+        Ok(self.add(Some(path.as_ref().to_string()), code))
+    }
+
+    /// Find the source that was loaded from given path
+    pub fn find<T: AsRef<str>>(&self, path: T) -> Option<FileId> {
+        self.files.iter()
+            .find(|file| file.path().is_some_and(|file_path| file_path == path.as_ref()))
+            .map(|file| file.id())
+    }
+
+    /// Get source by its identifier
+    pub fn get(&self, id: FileId) -> Option<&Arc<SourceFile>> {
+        self.files.get(id.0 as usize)
+    }
+
+    /// Get position (row, column) of the beginning of the span
+    pub fn position(&self, span: Span) -> Option<(usize, usize)> {
+        Some(self.get(span.file)?.offset_position(span.start()))
+    }
+
+    /// Get all loaded sources
+    pub fn files(&self) -> &[Arc<SourceFile>] {
+        &self.files
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn line_lookup() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(None, "apple\r\nbanana\n\n🎉 kiwi");
+        let source = sources.get(file).unwrap();
+        assert_eq!(source.line_count(), 4);
+        assert_eq!(source.line(1), Some("apple"));
+        assert_eq!(source.line(2), Some("banana"));
+        assert_eq!(source.line(3), Some(""));
+        assert_eq!(source.line(4), Some("🎉 kiwi"));
+        assert_eq!(source.line(0), None);
+        assert_eq!(source.line(5), None);
+    }
+
+    #[test]
+    fn position_lookup() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(None, "apple\nbanana\n🎉 kiwi");
+        let source = sources.get(file).unwrap();
+        assert_eq!(source.position(0), (1, 1));
+        assert_eq!(source.position(5), (1, 6));
+        assert_eq!(source.position(6), (2, 1));
+        assert_eq!(source.position(15), (3, 3));
+        assert_eq!(source.eof_position(), (3, 7));
+    }
+
+    #[test]
+    fn offset_lookup() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(None, "apple\nbanana\n🎉 kiwi");
+        let source = sources.get(file).unwrap();
+        assert_eq!(source.offset_position(0), (1, 1));
+        assert_eq!(source.offset_position(6), (2, 1));
+        assert_eq!(source.offset_position(18), (3, 3));
+        assert_eq!(source.offset(3, 3), Some(18));
+        assert_eq!(source.offset(4, 1), None);
+        assert_eq!(source.advance(13, 2), 18);
+        assert_eq!(source.slice(Span::new(file, 18, 22)), Some("kiwi"));
+        assert_eq!(sources.position(Span::new(file, 7, 9)), Some((2, 2)));
+    }
+
+    #[test]
+    fn unit_lookup() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(None, "a\n\t🎉 kiwi");
+        let source = sources.get(file).unwrap();
+        let display = ColumnUnit::Display { tab_width: 4 };
+        assert_eq!(source.offset_position_in(8, ColumnUnit::Utf16), (2, 5));
+        assert_eq!(source.offset_position_in(8, display), (2, 8));
+        assert_eq!(source.offset_position_in(8, ColumnUnit::Bytes), (2, 7));
+        assert_eq!(source.offset_in(2, 8, display), Some(8));
+        assert_eq!(source.offset_in(2, 6, display), Some(3));
+        assert_eq!(source.offset_in(3, 1, display), None);
+    }
+
+    #[test]
+    fn eof_position() {
+        let mut sources = SourceMap::new();
+        let trailing = sources.add(None, "let a\n");
+        let empty = sources.add(None, "");
+        assert_eq!(sources.get(trailing).unwrap().eof_position(), (1, 6));
+        assert_eq!(sources.get(empty).unwrap().eof_position(), (0, 0));
+    }
+
+    #[test]
+    fn find_by_path() {
+        let mut sources = SourceMap::new();
+        sources.add(None, "in memory");
+        let file = sources.add(Some("main.ab".to_string()), "on disk");
+        assert_eq!(sources.find("main.ab"), Some(file));
+        assert_eq!(sources.find("other.ab"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use crate::prelude::{DefaultMetadata, Metadata};
+        let meta = DefaultMetadata::new(vec![], Some("main.ab".to_string()), Some("let a\nlet b".to_string()));
+        let json = serde_json::to_string(&meta).unwrap();
+        let meta: DefaultMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(meta.get_code().map(String::as_str), Some("let a\nlet b"));
+        assert_eq!(meta.get_source().unwrap().line(2), Some("let b"));
+        assert_eq!(meta.sources.find("main.ab"), meta.file);
+    }
+}
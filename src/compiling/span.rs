@@ -0,0 +1,104 @@
+//! Spans of the source code
+//!
+//! This module defines `Span` which is a compact byte range in one of the sources
+//! stored in the `SourceMap`. Row and column are not stored in the span -
+//! they are computed only when needed through the line index of the source.
+
+use std::ops::Range;
+use super::FileId;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Byte range in the source code
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let mut sources = SourceMap::new();
+/// let file = sources.add(None, "let a = 12\nlet b = a");
+/// let span = Span::new(file, 15, 16);
+/// let source = sources.get(file).unwrap();
+/// assert_eq!(source.slice(span), Some("b"));
+/// assert_eq!(source.offset_position(span.start()), (2, 5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    /// Source that this span points to
+    pub file: FileId,
+    /// Byte index of the first character
+    pub start: u32,
+    /// Byte index right after the last character
+    pub end: u32
+}
+
+impl Span {
+    /// Create a new span in given source
+    ///
+    /// Panics if the span reaches past 4 GiB of the source.
+    pub fn new(file: FileId, start: usize, end: usize) -> Self {
+        let offset = |index: usize| u32::try_from(index).expect("Span does not fit sources larger than 4 GiB");
+        Span {
+            file,
+            start: offset(start),
+            end: offset(end.max(start))
+        }
+    }
+
+    /// Get byte index of the first character
+    pub fn start(&self) -> usize {
+        self.start as usize
+    }
+
+    /// Get byte index right after the last character
+    pub fn end(&self) -> usize {
+        self.end as usize
+    }
+
+    /// Get length of the span in bytes
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start) as usize
+    }
+
+    /// Check if the span does not cover any character
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Get the byte range of this span
+    pub fn range(&self) -> Range<usize> {
+        self.start()..self.end()
+    }
+
+    /// Create a span that covers both of the spans
+    pub fn join(&self, other: Span) -> Self {
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn join_spans() {
+        let left = Span::new(FileId(0), 4, 6);
+        let right = Span::new(FileId(0), 9, 12);
+        assert_eq!(left.join(right), Span::new(FileId(0), 4, 12));
+        assert_eq!(right.join(left).range(), 4..12);
+        assert_eq!(left.len(), 2);
+        assert!(Span::new(FileId(0), 3, 3).is_empty());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "Span does not fit sources larger than 4 GiB")]
+    fn span_overflow() {
+        Span::new(FileId(0), 0, u32::MAX as usize + 1);
+    }
+}
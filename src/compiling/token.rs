@@ -1,36 +1,152 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fmt::{Formatter, Display, Result, Debug};
+use std::ops::Deref;
+use std::sync::Arc;
+use super::{SourceFile, Span};
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
+/// Text of the token
+///
+/// Words are interned by the lexer so that tokens with the same text
+/// share a single allocation. Cloning a word is cheap.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "String", into = "String"))]
+pub struct Word(Arc<str>);
+
+impl Word {
+    /// Get the text of this word
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Word {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Word {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Word {
+    fn from(word: &str) -> Self {
+        Word(Arc::from(word))
+    }
+}
+
+impl From<String> for Word {
+    fn from(word: String) -> Self {
+        Word(Arc::from(word))
+    }
+}
+
+impl From<Word> for String {
+    fn from(word: Word) -> Self {
+        word.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Word {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Word {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Word {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl Display for Word {
+    fn fmt(&self, formatter: &mut Formatter) -> Result {
+        Display::fmt(&self.0, formatter)
+    }
+}
+
+impl Debug for Word {
+    fn fmt(&self, formatter: &mut Formatter) -> Result {
+        Debug::fmt(&self.0, formatter)
+    }
+}
+
+/// Storage of the interned words
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    words: HashSet<Arc<str>>
+}
+
+impl Interner {
+    /// Create an empty interner
+    pub fn new() -> Self {
+        Interner { words: HashSet::new() }
+    }
+
+    /// Get the word with given text that shares the allocation with the same words
+    pub fn intern(&mut self, word: &str) -> Word {
+        match self.words.get(word) {
+            Some(word) => Word(word.clone()),
+            None => {
+                let word: Arc<str> = Arc::from(word);
+                self.words.insert(word.clone());
+                Word(word)
+            }
+        }
+    }
+
+    /// Get amount of distinct words
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Check if no words were interned
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
 /// The building block of the AST
 #[derive(Clone, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Token {
     /// Value of the token
-    pub word: String,
-    /// Position of the token (row, column)
-    pub pos: (usize, usize),
-    /// Index of the character in the file that the token starts
-    pub start: usize,
+    pub word: Word,
+    /// Location of the token in the source
+    pub span: Span,
 }
 
 impl Token {
+    /// Create a new token
+    pub fn new<T: Into<Word>>(word: T, span: Span) -> Self {
+        Token {
+            word: word.into(),
+            span
+        }
+    }
+
+    /// Get the position (row, column) of the token in the source it comes from
+    pub fn pos(&self, source: &SourceFile) -> (usize, usize) {
+        source.offset_position(self.span.start())
+    }
+
     fn format(&self, formatter: &mut Formatter) -> Result {
-        let word = match self.word.as_str() {
-            "\n" => String::from("<new_line>"),
-            "\t" => String::from("<tab>"),
-            " " => String::from("<space>"),
-            sym @ (
-                "[" | "]" |
-                "<" | ">" |
-                "{" | "}" |
-                "(" | ")" |
-                ":" | ";"
-            ) => format!("<symbol: {} >", sym),
-            _ => self.word.clone()
-        };
-        write!(formatter, "Tok[{} {}:{}]", word, self.pos.0, self.pos.1)
+        format_token(&self.word, self.span, formatter)
     }
 }
 
@@ -46,17 +162,117 @@ impl Debug for Token {
     }
 }
 
+/// Token that borrows its word from the source code
+///
+/// Words are slices of the source unless their text differs from it
+/// (for instance when the lexer skipped an invalid character) - then they are owned.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # let rules = Rules::new(vec!['='], vec![], reg![]);
+/// let cc = Compiler::builder("HerbScript", rules).source("let a = 12").build().unwrap();
+/// let tokens = cc.tokenize_borrowed().unwrap();
+/// assert_eq!(tokens[1].word, "a");
+/// assert!(tokens[1].is_borrowed());
+/// ```
+#[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BorrowedToken<'src> {
+    /// Value of the token
+    pub word: Cow<'src, str>,
+    /// Location of the token in the source
+    pub span: Span,
+}
+
+impl<'src> BorrowedToken<'src> {
+    /// Create a new token
+    pub fn new<T: Into<Cow<'src, str>>>(word: T, span: Span) -> Self {
+        BorrowedToken {
+            word: word.into(),
+            span
+        }
+    }
+
+    /// Check if the word of this token is a slice of the source
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.word, Cow::Borrowed(_))
+    }
+
+    /// Get the position (row, column) of the token in the source it comes from
+    pub fn pos(&self, source: &SourceFile) -> (usize, usize) {
+        source.offset_position(self.span.start())
+    }
+
+    /// Create a token that owns its word
+    pub fn to_token(&self) -> Token {
+        Token::new(self.word.as_ref(), self.span)
+    }
+}
+
+impl From<&Token> for BorrowedToken<'_> {
+    fn from(token: &Token) -> Self {
+        BorrowedToken::new(token.word.to_string(), token.span)
+    }
+}
+
+impl Display for BorrowedToken<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> Result {
+        format_token(&self.word, self.span, formatter)
+    }
+}
+
+impl Debug for BorrowedToken<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> Result {
+        format_token(&self.word, self.span, formatter)
+    }
+}
+
+fn format_token(word: &str, span: Span, formatter: &mut Formatter) -> Result {
+    let word = match word {
+        "\n" => String::from("<new_line>"),
+        "\t" => String::from("<tab>"),
+        " " => String::from("<space>"),
+        sym @ (
+            "[" | "]" |
+            "<" | ">" |
+            "{" | "}" |
+            "(" | ")" |
+            ":" | ";"
+        ) => format!("<symbol: {} >", sym),
+        _ => word.to_string()
+    };
+    write!(formatter, "Tok[{} {}..{}]", word, span.start, span.end)
+}
+
 #[cfg(test)]
 mod test {
+    use crate::compiling::{FileId, SourceMap, Span};
+    use super::{Interner, Token};
+
     #[test]
     fn display_token() {
-        let mut token = super::Token {
-            word: String::from("keyword"),
-            pos: (1, 2),
-            start: 0
-        };
-        assert_eq!(format!("{}", token), String::from("Tok[keyword 1:2]"));
-        token.word = String::from("[");
-        assert_eq!(format!("{}", token), String::from("Tok[<symbol: [ > 1:2]"));
+        let mut token = Token::new("keyword", Span::new(FileId(0), 4, 11));
+        assert_eq!(format!("{}", token), String::from("Tok[keyword 4..11]"));
+        token.word = "[".into();
+        assert_eq!(format!("{}", token), String::from("Tok[<symbol: [ > 4..11]"));
+    }
+
+    #[test]
+    fn token_position() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(None, "let a\nlet b");
+        let token = Token::new("b", Span::new(file, 10, 11));
+        assert_eq!(token.pos(sources.get(file).unwrap()), (2, 5));
+    }
+
+    #[test]
+    fn interned_words() {
+        let mut interner = Interner::new();
+        let first = interner.intern("apple");
+        let second = interner.intern("apple");
+        interner.intern("banana");
+        assert_eq!(first, "apple");
+        assert!(std::ptr::eq(first.as_str(), second.as_str()));
+        assert_eq!(interner.len(), 2);
     }
 }
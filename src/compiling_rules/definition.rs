@@ -0,0 +1,408 @@
+//! Language definition files
+//!
+//! Lexing rules of the language can be described in a TOML or JSON file instead of the Rust code.
+//! Loading the definitions requires the `definitions` feature.
+//! Errors in TOML definitions always point to their location. JSON definitions are parsed without
+//! the locations of the values, so only the errors found by the JSON parser itself have one.
+//! The definition consists of the following keys:
+//!
+//! | Key          | Value                                                                   |
+//! |--------------|-------------------------------------------------------------------------|
+//! | `name`       | Name of the language (required)                                         |
+//! | `symbols`    | Array of single characters that are separated from the words            |
+//! | `compounds`  | Array of two symbols that are merged together (like `"&&"`)             |
+//! | `brackets`   | Array of opening and closing symbols (like `"()"`)                      |
+//! | `escape`     | Escape symbol (`"\\"` by default)                                       |
+//! | `keywords`   | Array of the keywords of the language                                   |
+//! | `separator`  | `"manual"`, `{ semi_automatic = ";" }` or `{ automatic = "\\" }`        |
+//! | `scoping`    | `"block"` (default) or `"indent"`                                       |
+//! | `regions`    | Array of regions                                                        |
+//!
+//! Each region requires `id`, `name`, `begin` and `end`. Optionally it can set `tokenize`,
+//! `singleline`, `allow_unclosed`, `unclosed_message`, `singleline_message` and `help`.
+//! Region can either interpolate nested `regions` or reference other region by its id with `ref`
+//! (the id of the global region is `global`).
+//! # Example
+//! ```toml
+//! name = "HerbScript"
+//! symbols = ["+", "-", "&", "|", "{", "}"]
+//! compounds = ["&&", "||"]
+//! brackets = ["{}"]
+//! keywords = ["let", "if"]
+//! separator = { semi_automatic = ";" }
+//!
+//! [[regions]]
+//! id = "string"
+//! name = "String Literal"
+//! begin = "'"
+//! end = "'"
+//!
+//! [[regions.regions]]
+//! id = "interpolation"
+//! name = "String Interpolation"
+//! begin = "{"
+//! end = "}"
+//! tokenize = true
+//! ref = "global"
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+use crate::compiling::{Compiler, CompilerBuilder, SeparatorMode, ScopingMode};
+use crate::compiling::failing::position_info::PositionInfo;
+use crate::compiling::failing::message::{Message, MessageType};
+use crate::compiling::failing::failure::Failure;
+use super::document::{locate, parse_json, parse_toml, Document, RegionDocument, Scoping, Separator, Text};
+use super::{HighlightGrammar, Region, RegionMessages, Rules};
+
+/// Error in the language definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefinitionError {
+    /// Description of the error
+    pub message: String,
+    /// Row and column in the definition where the error occured
+    ///
+    /// Errors found while validating the values of a JSON definition have no position.
+    pub position: Option<(usize, usize)>,
+    /// Path to the definition file
+    pub path: Option<String>,
+    code: Option<String>
+}
+
+impl DefinitionError {
+    /// Create a new error at given position
+    pub fn new<T: AsRef<str>>(message: T, position: Option<(usize, usize)>) -> Self {
+        DefinitionError {
+            message: message.as_ref().to_string(),
+            position,
+            path: None,
+            code: None
+        }
+    }
+
+    // Attach the definition that this error was found in
+    fn within(mut self, path: Option<&str>, code: &str) -> Self {
+        self.path = path.map(String::from);
+        self.code = Some(code.to_string());
+        self
+    }
+}
+
+impl Display for DefinitionError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match (&self.path, self.position) {
+            (Some(path), Some((row, col))) => write!(formatter, "{path}:{row}:{col}: {}", self.message),
+            (None, Some((row, col))) => write!(formatter, "{row}:{col}: {}", self.message),
+            (Some(path), None) => write!(formatter, "{path}: {}", self.message),
+            (None, None) => write!(formatter, "{}", self.message)
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+impl From<DefinitionError> for Failure {
+    fn from(error: DefinitionError) -> Self {
+        match error.position {
+            Some(position) => {
+                let trace = [PositionInfo::at_pos(error.path.clone(), position, 1)];
+                Failure::Loud(Message::new(error.code.as_ref(), &trace, MessageType::Error).message(error.message))
+            }
+            None => Failure::Loud(Message::new_err_msg(error.to_string()))
+        }
+    }
+}
+
+/// Language described by the definition file
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let definition = LanguageDefinition::from_toml(r#"
+///     name = "HerbScript"
+///     symbols = ["="]
+///     keywords = ["let"]
+/// "#).unwrap();
+/// let cc = definition.builder().source("let a = 12").build().unwrap();
+/// assert_eq!(cc.tokenize().unwrap().len(), 4);
+/// assert!(definition.is_keyword("let"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageDefinition {
+    /// Name of the language
+    pub name: String,
+    /// Lexing rules of the language
+    pub rules: Rules,
+    /// Keywords of the language
+    pub keywords: Vec<String>,
+    /// How the expressions are separated
+    pub separator_mode: SeparatorMode,
+    /// How the scopes are expressed
+    pub scoping_mode: ScopingMode
+}
+
+impl LanguageDefinition {
+    /// Load the definition written in TOML
+    pub fn from_toml<T: AsRef<str>>(code: T) -> Result<Self, DefinitionError> {
+        let code = code.as_ref();
+        parse_toml(code)
+            .and_then(|document| Self::decode(code, document))
+            .map_err(|error| error.within(None, code))
+    }
+
+    /// Load the definition written in JSON
+    ///
+    /// Syntax errors, unknown keys and values of a wrong type are reported with their position.
+    /// Values are not located in the document though, so the errors of invalid values
+    /// (like a compound of unknown symbols) have no position. Use TOML if they should have one.
+    pub fn from_json<T: AsRef<str>>(code: T) -> Result<Self, DefinitionError> {
+        let code = code.as_ref();
+        parse_json(code)
+            .and_then(|document| Self::decode(code, document))
+            .map_err(|error| error.within(None, code))
+    }
+
+    /// Load the definition from a `.toml` or `.json` file
+    pub fn from_file<T: AsRef<str>>(path: T) -> Result<Self, DefinitionError> {
+        let path = path.as_ref();
+        let error = |message: String| DefinitionError { path: Some(path.to_string()), ..DefinitionError::new(message, None) };
+        let code = std::fs::read_to_string(path)
+            .map_err(|io| error(format!("Could not read the definition: {io}")))?;
+        let definition = match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&code),
+            Some("json") => Self::from_json(&code),
+            _ => return Err(error("Definition has to be a .toml or .json file".to_string()))
+        };
+        definition.map_err(|error| error.within(Some(path), &code))
+    }
+
+    /// Check if the word is a keyword of the language
+    pub fn is_keyword<T: AsRef<str>>(&self, word: T) -> bool {
+        self.keywords.iter().any(|keyword| keyword == word.as_ref())
+    }
+
+    /// Create the compiler builder configured with this language
+    pub fn builder(&self) -> CompilerBuilder {
+        let builder = Compiler::builder(&self.name, self.rules.clone())
+            .separator(self.separator_mode.clone());
+        match self.scoping_mode {
+            ScopingMode::Indent => builder.indent(),
+            ScopingMode::Block => builder
+        }
+    }
+
+    /// Create the syntax highlighting grammar of this language
+    pub fn highlighting(&self) -> HighlightGrammar<'_> {
+        HighlightGrammar::new(&self.name, &self.rules).keywords(self.keywords.clone())
+    }
+
+    fn decode<S: Text>(code: &str, document: Document<S>) -> Result<Self, DefinitionError> {
+        let decoder = Decoder { code };
+        // Symbols
+        let mut symbols = Vec::new();
+        for value in &document.symbols {
+            let symbol = decoder.character(value)?;
+            if symbols.contains(&symbol) {
+                return Err(decoder.error(format!("Duplicate symbol '{symbol}'"), value))
+            }
+            symbols.push(symbol);
+        }
+        let compounds = document.compounds.iter()
+            .map(|value| decoder.symbol_pair(value, &symbols, "Compound"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let brackets = document.brackets.iter()
+            .map(|value| decoder.symbol_pair(value, &symbols, "Bracket"))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Keywords
+        let mut keywords: Vec<String> = Vec::new();
+        for value in &document.keywords {
+            let keyword = value.text();
+            if keyword.is_empty() || keyword.chars().any(char::is_whitespace) {
+                return Err(decoder.error("Keyword cannot be empty or contain whitespace", value))
+            }
+            if keywords.iter().any(|name| name == keyword) {
+                return Err(decoder.error(format!("Duplicate keyword '{keyword}'"), value))
+            }
+            keywords.push(keyword.to_string());
+        }
+        // Modes
+        let separator_mode = match &document.separator {
+            None | Some(Separator::Manual) => SeparatorMode::Manual,
+            Some(Separator::SemiAutomatic(value) | Separator::Automatic(value)) if value.text().is_empty() => {
+                return Err(decoder.error("Separator symbol cannot be empty", value))
+            }
+            Some(Separator::SemiAutomatic(value)) => SeparatorMode::SemiAutomatic(value.text().to_string()),
+            Some(Separator::Automatic(value)) => SeparatorMode::Automatic(value.text().to_string())
+        };
+        let scoping_mode = match document.scoping {
+            Scoping::Block => ScopingMode::Block,
+            Scoping::Indent => ScopingMode::Indent
+        };
+        // Regions
+        let mut ids = Vec::new();
+        let mut references = Vec::new();
+        let mut messages = HashMap::new();
+        let regions = document.regions.iter()
+            .map(|region| decoder.region(region, &mut ids, &mut references, &mut messages))
+            .collect::<Result<Vec<_>, _>>()?;
+        for reference in references {
+            if reference.text() != "global" && !ids.iter().any(|id| id == reference.text()) {
+                return Err(decoder.error(format!("Region '{}' is not defined", reference.text()), reference))
+            }
+        }
+        let mut rules = Rules::new(symbols, compounds, Region::new_global(regions)).set_brackets(brackets);
+        rules.region_messages = messages;
+        if let Some(value) = &document.escape {
+            rules = rules.set_escape(decoder.character(value)?);
+        }
+        Ok(LanguageDefinition { name: document.name, rules, keywords, separator_mode, scoping_mode })
+    }
+}
+
+// Validates the values of the document and reports the errors at their locations
+struct Decoder<'a> {
+    code: &'a str
+}
+
+impl Decoder<'_> {
+    fn error<S: Text>(&self, message: impl AsRef<str>, value: &S) -> DefinitionError {
+        DefinitionError::new(message, value.span().map(|span| locate(self.code, span.start)))
+    }
+
+    fn character<S: Text>(&self, value: &S) -> Result<char, DefinitionError> {
+        let mut chars = value.text().chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) => Ok(letter),
+            _ => Err(self.error("Expected a single character", value))
+        }
+    }
+
+    // Decode two symbols of a compound or a bracket pair
+    fn symbol_pair<S: Text>(&self, value: &S, symbols: &[char], kind: &str) -> Result<(char, char), DefinitionError> {
+        let chars = value.text().chars().collect::<Vec<_>>();
+        let [left, right] = chars[..] else {
+            return Err(self.error(format!("{kind} has to consist of two symbols"), value))
+        };
+        if let Some(letter) = [left, right].into_iter().find(|letter| !symbols.contains(letter)) {
+            return Err(self.error(format!("{kind} uses '{letter}' that is not a symbol"), value))
+        }
+        Ok((left, right))
+    }
+
+    // Decode the region and collect its id and the reference that it uses
+    fn region<'a, S: Text>(&self, region: &'a RegionDocument<S>, ids: &mut Vec<String>, references: &mut Vec<&'a S>, messages: &mut HashMap<String, RegionMessages>) -> Result<Region, DefinitionError> {
+        let id = region.id.text();
+        if id == "global" || ids.iter().any(|name| name == id) {
+            return Err(self.error(format!("Region '{id}' is already defined"), &region.id))
+        }
+        ids.push(id.to_string());
+        for (key, delimiter) in [("begin", &region.begin), ("end", &region.end)] {
+            if delimiter.text().is_empty() {
+                return Err(self.error(format!("Region '{key}' cannot be empty"), delimiter))
+            }
+        }
+        if let (Some(reference), Some(_)) = (&region.reference, &region.regions) {
+            return Err(self.error("Region cannot both reference other region and interpolate regions", reference))
+        }
+        let interp = region.regions.iter().flatten()
+            .map(|region| self.region(region, ids, references, messages))
+            .collect::<Result<Vec<_>, _>>()?;
+        let reference = region.reference.as_ref().map(|reference| reference.text());
+        let mut result = Region::new(id, region.name.as_str(), region.begin.text(), region.end.text(), interp, reference);
+        references.extend(&region.reference);
+        result.tokenize = region.tokenize;
+        result.singleline = region.singleline;
+        result.allow_unclosed_region = region.allow_unclosed;
+        let custom = RegionMessages {
+            unclosed: region.unclosed_message.clone(),
+            singleline: region.singleline_message.clone(),
+            help: region.help.clone()
+        };
+        if custom != RegionMessages::default() {
+            messages.insert(id.to_string(), custom);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reg;
+
+    fn error(code: &str) -> (String, Option<(usize, usize)>) {
+        let error = LanguageDefinition::from_toml(code).unwrap_err();
+        (error.message, error.position)
+    }
+
+    #[test]
+    fn region_tree() {
+        let definition = LanguageDefinition::from_json(r#"{
+            "name": "Herb",
+            "symbols": ["&"],
+            "compounds": ["&&"],
+            "escape": "^",
+            "scoping": "indent",
+            "separator": { "automatic": "\\" },
+            "regions": [{
+                "id": "string", "name": "String", "begin": "'", "end": "'", "singleline": true,
+                "regions": [{ "id": "interp", "name": "Interpolation", "begin": "{", "end": "}", "tokenize": true, "ref": "global" }]
+            }]
+        }"#).unwrap();
+        let expected = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'",
+                singleline: true
+            } => [
+                reg!(interp as "Interpolation" => {
+                    begin: "{",
+                    end: "}",
+                    tokenize: true
+                } ref global)
+            ])
+        ];
+        assert_eq!(definition.rules, Rules::new(vec!['&'], vec![('&', '&')], expected).set_escape('^'));
+        assert_eq!(definition.scoping_mode, ScopingMode::Indent);
+        assert_eq!(definition.separator_mode, SeparatorMode::Automatic("\\".to_string()));
+    }
+
+    #[test]
+    fn validation_errors() {
+        assert_eq!(error("symbols = []"), ("missing field `name`".to_string(), Some((1, 1))));
+        let (message, position) = error("name = 'a'\nsymbol = []");
+        assert!(message.starts_with("unknown field `symbol`"), "{message}");
+        assert_eq!(position, Some((2, 1)));
+        assert_eq!(error("name = 'a'\nsymbols = ['+', '--']"), ("Expected a single character".to_string(), Some((2, 17))));
+        assert_eq!(error("name = 'a'\nsymbols = ['&']\ncompounds = ['&|']"), ("Compound uses '|' that is not a symbol".to_string(), Some((3, 14))));
+        assert_eq!(error("name = 'a'\nsymbols = ['(']\nbrackets = ['()']"), ("Bracket uses ')' that is not a symbol".to_string(), Some((3, 13))));
+        assert_eq!(error("name = 'a'\nscoping = 1"), ("wanted string or table".to_string(), Some((2, 11))));
+        assert_eq!(error("name = 'a'\nscoping = 'tabs'"), ("unknown variant `tabs`, expected `block` or `indent`".to_string(), Some((2, 11))));
+        assert_eq!(error("name = 'a'\nseparator = { automatic = '' }"), ("Separator symbol cannot be empty".to_string(), Some((2, 27))));
+        let regions = "name = 'a'\n[[regions]]\nid = 's'\nname = 'S'\nbegin = '\"'\nend = '\"'\nref = 'x'";
+        assert_eq!(error(regions), ("Region 'x' is not defined".to_string(), Some((7, 7))));
+        let regions = "name = 'a'\n[[regions]]\nid = 's'\nname = 'S'\nbegin = '\"'";
+        assert_eq!(error(regions), ("missing field `end`".to_string(), Some((2, 1))));
+    }
+
+    #[test]
+    fn json_validation_errors() {
+        let error = |code: &str| LanguageDefinition::from_json(code).map(|_| ()).unwrap_err();
+        // Errors of the JSON parser are located
+        let parsed = error("{ \"name\": \"a\",\n  \"symbol\": [] }");
+        assert!(parsed.message.starts_with("unknown field `symbol`"), "{}", parsed.message);
+        assert_eq!(parsed.position, Some((2, 10)));
+        // Values are not spanned so the errors found while validating them are not
+        let validated = error("{ \"name\": \"a\",\n  \"symbols\": [\"&\"],\n  \"compounds\": [\"&|\"] }");
+        assert_eq!((validated.message.as_str(), validated.position), ("Compound uses '|' that is not a symbol", None));
+    }
+
+    #[test]
+    fn display_error() {
+        let error = DefinitionError::new("Unknown key 'a'", Some((2, 1)));
+        assert_eq!(error.to_string(), "2:1: Unknown key 'a'");
+        let error = DefinitionError { path: Some("lang.toml".to_string()), ..error };
+        assert_eq!(error.to_string(), "lang.toml:2:1: Unknown key 'a'");
+    }
+}
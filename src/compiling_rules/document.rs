@@ -0,0 +1,149 @@
+//! Documents with language definitions
+//!
+//! Definitions are deserialized with serde. TOML documents keep the spans of the values
+//! (through `toml::Spanned`) so that the definition can be validated with precise error locations.
+//! JSON documents report the location only for the errors found by the JSON parser itself.
+
+use std::ops::Range;
+use serde::Deserialize;
+use toml::Spanned;
+use super::DefinitionError;
+
+/// Row and column of the value in the document
+pub type Location = (usize, usize);
+
+/// Text of the document that may know where it was written
+pub trait Text {
+    /// Get the text itself
+    fn text(&self) -> &str;
+    /// Get the byte range of the text in the document
+    fn span(&self) -> Option<Range<usize>>;
+}
+
+impl Text for String {
+    fn text(&self) -> &str {
+        self
+    }
+
+    fn span(&self) -> Option<Range<usize>> {
+        None
+    }
+}
+
+impl Text for Spanned<String> {
+    fn text(&self) -> &str {
+        self.get_ref()
+    }
+
+    fn span(&self) -> Option<Range<usize>> {
+        Some(Spanned::span(self))
+    }
+}
+
+/// Root of the language definition
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, bound(deserialize = "S: Deserialize<'de>"))]
+pub struct Document<S> {
+    pub name: String,
+    #[serde(default)]
+    pub symbols: Vec<S>,
+    #[serde(default)]
+    pub compounds: Vec<S>,
+    #[serde(default)]
+    pub brackets: Vec<S>,
+    pub escape: Option<S>,
+    #[serde(default)]
+    pub keywords: Vec<S>,
+    pub separator: Option<Separator<S>>,
+    #[serde(default)]
+    pub scoping: Scoping,
+    #[serde(default)]
+    pub regions: Vec<RegionDocument<S>>
+}
+
+/// Separator written as `"manual"`, `{ semi_automatic = ";" }` or `{ automatic = "\\" }`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Separator<S> {
+    Manual,
+    SemiAutomatic(S),
+    Automatic(S)
+}
+
+/// Scoping written as `"block"` or `"indent"`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scoping {
+    #[default]
+    Block,
+    Indent
+}
+
+/// Region of the language definition
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, bound(deserialize = "S: Deserialize<'de>"))]
+pub struct RegionDocument<S> {
+    pub id: S,
+    pub name: String,
+    pub begin: S,
+    pub end: S,
+    #[serde(default)]
+    pub tokenize: bool,
+    #[serde(default)]
+    pub singleline: bool,
+    #[serde(default)]
+    pub allow_unclosed: bool,
+    #[serde(rename = "ref")]
+    pub reference: Option<S>,
+    pub regions: Option<Vec<RegionDocument<S>>>,
+    pub unclosed_message: Option<String>,
+    pub singleline_message: Option<String>,
+    pub help: Option<String>
+}
+
+/// Parse the definition written in TOML
+pub fn parse_toml(code: &str) -> Result<Document<Spanned<String>>, DefinitionError> {
+    toml::from_str(code).map_err(|error| {
+        let position = error.span().map(|span| locate(code, span.start));
+        DefinitionError::new(error.message(), position)
+    })
+}
+
+/// Parse the definition written in JSON
+pub fn parse_json(code: &str) -> Result<Document<String>, DefinitionError> {
+    serde_json::from_str(code).map_err(|error| {
+        let (line, column) = (error.line(), error.column());
+        let message = error.to_string();
+        // Location is reported separately instead of being a part of the message
+        let message = message.strip_suffix(&format!(" at line {line} column {column}")).unwrap_or(&message);
+        DefinitionError::new(message, (line > 0).then_some((line, column)))
+    })
+}
+
+/// Get the row and column of the byte offset in the document
+pub fn locate(code: &str, offset: usize) -> Location {
+    let before = &code[..offset.min(code.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn locate_offsets() {
+        let code = "name = 'ą'\nsymbols = []";
+        assert_eq!(locate(code, 0), (1, 1));
+        assert_eq!(locate(code, code.find(']').unwrap()), (2, 12));
+        assert_eq!(locate(code, code.find('\'').unwrap() + 3), (1, 10));
+        assert_eq!(locate(code, 100), (2, 13));
+    }
+
+    #[test]
+    fn json_errors() {
+        let error = parse_json("{ \"name\": \"a\",\n  \"symbols\": [1] }").unwrap_err();
+        assert_eq!(error.message, "invalid type: integer `1`, expected a string");
+        assert_eq!(error.position, Some((2, 15)));
+    }
+}
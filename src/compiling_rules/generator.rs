@@ -0,0 +1,309 @@
+//! Random source code generator
+//!
+//! Generator walks the region tree of the rules and produces code that consists of
+//! words, whitespace, symbols, compounds and properly closed regions. Delimiters and escape
+//! symbols also appear outside of the regions where they cannot open nor close one. Together with
+//! `verify_tokens` it's used to check the lexer invariants on many different inputs.
+
+use crate::compiling::Token;
+use super::{Region, RegionMap, Rules};
+
+const LETTERS: [&str; 12] = ["a", "b", "x", "y", "z", "_", "0", "1", "9", "ą", "ż", "🦀"];
+const WHITESPACE: [&str; 4] = [" ", " ", "\t", "\n"];
+
+/// Generator of random source code that follows the rules
+///
+/// Generated code is deterministic for the given seed.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let rules = Rules::new(vec!['+'], vec![], reg![
+///     reg!(string as "String" => {
+///         begin: "'",
+///         end: "'"
+///     })
+/// ]);
+/// let code = SourceGenerator::new(&rules, 42).generate(50);
+/// let cc = Compiler::builder("HerbScript", rules.clone()).source(&code).build().unwrap();
+/// let tokens = cc.tokenize().unwrap();
+/// assert!(verify_tokens(&code, &tokens).is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SourceGenerator<'a> {
+    rules: &'a Rules,
+    regions: RegionMap,
+    state: u64,
+    max_depth: usize,
+    // Characters that could accidentally open or close a region (sorted to keep the output deterministic)
+    reserved: Vec<char>
+}
+
+impl<'a> SourceGenerator<'a> {
+    /// Create a generator for the rules with given seed
+    pub fn new(rules: &'a Rules, seed: u64) -> Self {
+        let regions = rules.region_tree.generate_region_map();
+        let mut reserved = regions.values()
+            .filter(|region| region.id != rules.region_tree.id)
+            .flat_map(|region| region.begin.chars().take(1).chain(region.end.chars().take(1)))
+            .chain([rules.escape_symbol])
+            .collect::<Vec<_>>();
+        reserved.sort_unstable();
+        reserved.dedup();
+        SourceGenerator {
+            rules,
+            regions,
+            // State of the xorshift has to be non-zero
+            state: (seed ^ 0x9e37_79b9_7f4a_7c15).max(1),
+            max_depth: 3,
+            reserved
+        }
+    }
+
+    /// Set how deep the regions can be nested (3 by default)
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Generate code that consists of given amount of fragments
+    pub fn generate(&mut self, size: usize) -> String {
+        let mut code = String::new();
+        let global = &self.rules.region_tree;
+        for _ in 0..size {
+            self.code_fragment(&mut code, global, 0, false);
+        }
+        code
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound.max(1) as u64) as usize
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> Option<T> {
+        match items.is_empty() {
+            true => None,
+            false => Some(items[self.below(items.len())])
+        }
+    }
+
+    // Regions that can be opened inside of given region
+    fn interpolations(&self, region: &'a Region) -> Vec<&'a Region> {
+        let interp = match &region.references {
+            Some(id) if *id == self.rules.region_tree.id => &self.rules.region_tree.interp,
+            Some(id) => match self.regions.get(id) {
+                Some(reference) => &reference.interp,
+                None => return vec![]
+            },
+            None => &region.interp
+        };
+        // References point to the regions of the map so they are found in the tree by their id
+        interp.iter().filter_map(|region| self.rules.region_tree.find(&region.id)).collect()
+    }
+
+    fn word(&mut self, code: &mut String) {
+        for _ in 0..=self.below(4) {
+            let letter = self.pick(&LETTERS).unwrap_or("a");
+            if !letter.chars().any(|letter| self.reserved.contains(&letter)) {
+                code.push_str(letter);
+            }
+        }
+    }
+
+    // Fragment of the code that is tokenized
+    fn code_fragment(&mut self, code: &mut String, region: &'a Region, depth: usize, singleline: bool) {
+        let symbols = self.rules.symbols.iter()
+            .filter(|symbol| !self.reserved.contains(symbol))
+            .copied()
+            .collect::<Vec<_>>();
+        let compounds = self.rules.compounds.iter()
+            .filter(|(left, right)| !self.reserved.contains(left) && !self.reserved.contains(right))
+            .copied()
+            .collect::<Vec<_>>();
+        let interpolations = self.interpolations(region);
+        match self.below(6) {
+            0 => self.word(code),
+            1 => match self.pick(&WHITESPACE) {
+                Some("\n") if singleline => code.push(' '),
+                Some(whitespace) => code.push_str(whitespace),
+                None => {}
+            },
+            2 => code.extend(self.pick(&symbols)),
+            3 => code.extend(self.pick(&compounds).map(|(left, right)| format!("{left}{right}"))),
+            4 => self.stray(code, region, &interpolations, singleline),
+            _ => match self.pick(&interpolations) {
+                Some(region) if depth < self.max_depth => self.region(code, region, depth + 1, singleline),
+                _ => self.word(code)
+            }
+        }
+    }
+
+    // Reserved character in the code that is either escaped or cannot open nor close a region here
+    fn stray(&mut self, code: &mut String, region: &Region, interpolations: &[&Region], singleline: bool) {
+        let index = self.below(self.reserved.len());
+        let Some(&letter) = self.reserved.get(index) else {
+            return
+        };
+        if letter == '\n' && singleline {
+            return
+        }
+        // Escaped character is never treated as a delimiter
+        if self.below(2) == 0 {
+            code.push(self.rules.escape_symbol);
+            code.push(letter);
+            return
+        }
+        let is_delimiter = interpolations.iter().any(|region| region.begin.starts_with(letter)) || region.end.starts_with(letter);
+        if !is_delimiter && letter != self.rules.escape_symbol {
+            code.push(letter);
+        }
+    }
+
+    fn region(&mut self, code: &mut String, region: &'a Region, depth: usize, singleline: bool) {
+        let singleline = singleline || region.singleline || region.end.contains('\n');
+        code.push_str(&region.begin);
+        // Separate the content from the beginning of the region
+        if region.tokenize {
+            code.push(' ');
+        }
+        let interpolations = self.interpolations(region);
+        for _ in 0..self.below(6) {
+            if region.tokenize {
+                self.code_fragment(code, region, depth, singleline);
+                continue
+            }
+            match self.below(4) {
+                // Escaped new line would not end the region
+                0 if !region.end.starts_with('\n') => {
+                    code.push(self.rules.escape_symbol);
+                    code.extend(region.end.chars().next());
+                }
+                1 => code.push(' '),
+                2 => match self.pick(&interpolations) {
+                    Some(region) if depth < self.max_depth => self.region(code, region, depth + 1, singleline),
+                    _ => self.word(code)
+                },
+                _ => self.word(code)
+            }
+        }
+        if region.tokenize {
+            code.push(' ');
+        }
+        code.push_str(&region.end);
+    }
+}
+
+/// Check the invariants of the tokens produced by the lexer
+///
+/// Tokens have to point to valid ranges of the code in increasing order
+/// and their words have to match the code if the whitespace is not taken into account.
+pub fn verify_tokens(code: &str, tokens: &[Token]) -> Result<(), String> {
+    let mut previous = 0;
+    for token in tokens.iter() {
+        let span = token.span;
+        if code.get(span.range()).is_none() {
+            return Err(format!("Token {token:?} does not point to the code of length {}", code.len()))
+        }
+        if span.start() < previous {
+            return Err(format!("Token {token:?} starts before the previous token"))
+        }
+        previous = span.start();
+    }
+    let words = tokens.iter()
+        .flat_map(|token| token.word.chars())
+        .filter(|letter| !letter.is_whitespace())
+        .collect::<String>();
+    let expected = code.chars()
+        .filter(|letter| !letter.is_whitespace())
+        .collect::<String>();
+    match words == expected {
+        true => Ok(()),
+        false => Err(format!("Words {words:?} do not match the code {expected:?}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compiling::{Compiler, FileId, SeparatorMode, Span};
+    use crate::reg;
+
+    fn rules() -> Rules {
+        Rules::new(vec!['+', '-', '=', '&', '(', ')', '{', '}', '#'], vec![('&', '&'), ('=', '=')], reg![
+            reg!(string as "String" => {
+                begin: "\"",
+                end: "\""
+            } => [
+                reg!(interp as "Interpolation" => {
+                    begin: "{",
+                    end: "}",
+                    tokenize: true
+                } ref global)
+            ]),
+            reg!(char as "Character" => {
+                begin: "'",
+                end: "'",
+                singleline: true
+            }),
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n",
+                allow_unclosed_region: true
+            })
+        ])
+    }
+
+    #[test]
+    fn deterministic() {
+        let rules = rules();
+        let first = SourceGenerator::new(&rules, 7).generate(100);
+        let second = SourceGenerator::new(&rules, 7).generate(100);
+        assert_eq!(first, second);
+        assert_ne!(first, SourceGenerator::new(&rules, 8).generate(100));
+    }
+
+    #[test]
+    fn lexer_invariants() {
+        let rules = rules();
+        for seed in 0..300 {
+            let code = SourceGenerator::new(&rules, seed).generate(60);
+            for mode in [SeparatorMode::Manual, SeparatorMode::Automatic(";".to_string())] {
+                let cc = Compiler::builder("Test", rules.clone())
+                    .source(&code)
+                    .separator(mode)
+                    .build()
+                    .unwrap();
+                let tokens = cc.tokenize().unwrap_or_else(|error| panic!("Seed {seed} failed for {code:?}: {error:?}"));
+                if let Err(error) = verify_tokens(&code, &tokens) {
+                    panic!("Seed {seed} failed for {code:?}: {error}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stray_delimiters() {
+        let rules = rules();
+        let codes = (0..50).map(|seed| SourceGenerator::new(&rules, seed).generate(60)).collect::<Vec<_>>();
+        // Comment delimiter is escaped only outside of the regions
+        assert!(codes.iter().any(|code| code.contains("\\#")));
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        let mut tokens = vec![Token::new("a", Span::new(FileId(0), 2, 3))];
+        assert!(verify_tokens("b a", &tokens).is_err());
+        tokens.insert(0, Token::new("b", Span::new(FileId(0), 0, 1)));
+        assert!(verify_tokens("b a", &tokens).is_ok());
+        tokens.swap(0, 1);
+        assert!(verify_tokens("b a", &tokens).is_err());
+        tokens.push(Token::new("c", Span::new(FileId(0), 4, 5)));
+        assert!(verify_tokens("b a", &tokens).is_err());
+    }
+}
@@ -0,0 +1,460 @@
+//! Syntax highlighting grammars
+//!
+//! Editors can highlight languages built with heraclitus using grammars generated from `Rules`.
+//! Regions become scoped patterns that nest their interpolations while symbols, compounds and
+//! keywords are matched with regular expressions. Grammars can be exported to the TextMate
+//! format (`.tmLanguage.json`) used by VS Code and to the Sublime Text format (`.sublime-syntax`).
+
+use std::collections::HashMap;
+use crate::compiling::json::escape_json;
+use super::{Region, Rules};
+
+// Names of the generated patterns
+const KEYWORD: &str = "__keyword";
+const SYMBOL: &str = "__symbol";
+const ESCAPE: &str = "__escape";
+
+/// Exporter of syntax highlighting grammars
+///
+/// Scopes are assigned by the id of the region or by the `__keyword`, `__symbol` and `__escape`
+/// names of the patterns generated for the keywords, symbols and escape sequences.
+/// Generated names are prefixed so that they do not collide with the ids of the regions.
+/// Regions without a custom scope are scoped as comments when their id or name mentions a comment,
+/// as embedded code when they are tokenized and as strings otherwise.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let rules = Rules::new(vec!['+', '='], vec![], reg![
+///     reg!(string as "String Literal" => {
+///         begin: "'",
+///         end: "'"
+///     })
+/// ]);
+/// let grammar = HighlightGrammar::new("HerbScript", &rules)
+///     .file_types(vec!["herb"])
+///     .keywords(vec!["let", "if"])
+///     .scope("string", "string.quoted.single.herb");
+/// assert!(grammar.to_textmate().contains(r#""scopeName": "source.herbscript""#));
+/// assert!(grammar.to_sublime().contains("meta_scope: 'string.quoted.single.herb'"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct HighlightGrammar<'a> {
+    name: String,
+    rules: &'a Rules,
+    scope_name: String,
+    file_types: Vec<String>,
+    keywords: Vec<String>,
+    scopes: HashMap<String, String>
+}
+
+impl<'a> HighlightGrammar<'a> {
+    /// Create a grammar of the language with given rules
+    pub fn new<T: AsRef<str>>(name: T, rules: &'a Rules) -> Self {
+        let name = name.as_ref().to_string();
+        let suffix = name.to_lowercase().chars().filter(|letter| letter.is_alphanumeric()).collect::<String>();
+        HighlightGrammar {
+            name,
+            rules,
+            scope_name: format!("source.{suffix}"),
+            file_types: vec![],
+            keywords: vec![],
+            scopes: HashMap::new()
+        }
+    }
+
+    /// Set the root scope of the grammar (`source.<name>` by default)
+    pub fn scope_name<T: AsRef<str>>(mut self, scope_name: T) -> Self {
+        self.scope_name = scope_name.as_ref().to_string();
+        self
+    }
+
+    /// Set the file extensions of the language
+    pub fn file_types<T: AsRef<str>>(mut self, file_types: Vec<T>) -> Self {
+        self.file_types = file_types.iter().map(|file_type| file_type.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Set the keywords of the language
+    pub fn keywords<T: AsRef<str>>(mut self, keywords: Vec<T>) -> Self {
+        self.keywords = keywords.iter().map(|keyword| keyword.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Set the scope of the region with given id or the `__keyword`, `__symbol` or `__escape` pattern
+    pub fn scope<T: AsRef<str>, S: AsRef<str>>(mut self, id: T, scope: S) -> Self {
+        self.scopes.insert(id.as_ref().to_string(), scope.as_ref().to_string());
+        self
+    }
+
+    // Last part of the root scope that is appended to the default scopes
+    fn suffix(&self) -> &str {
+        self.scope_name.rsplit('.').next().unwrap_or_default()
+    }
+
+    fn scope_of(&self, id: &str, default: &str) -> String {
+        self.scopes.get(id).cloned().unwrap_or_else(|| format!("{default}.{}", self.suffix()))
+    }
+
+    fn region_scope(&self, region: &Region) -> String {
+        let is_comment = [&region.id, &region.name].iter().any(|text| text.to_lowercase().contains("comment"));
+        let default = match (is_comment, region.tokenize) {
+            (true, _) => format!("comment.block.{}", region.id),
+            (false, true) => format!("meta.embedded.{}", region.id),
+            (false, false) => format!("string.{}", region.id)
+        };
+        self.scope_of(&region.id, &default)
+    }
+
+    // Regions interpolated in given region (including the interpolations of the referenced region)
+    fn interpolations(&self, region: &'a Region) -> &'a [Region] {
+        match &region.references {
+            Some(id) if id == "global" => &self.rules.region_tree.interp,
+            Some(id) => match self.rules.region_tree.find(id) {
+                Some(reference) => &reference.interp,
+                None => &[]
+            },
+            None => &region.interp
+        }
+    }
+
+    // All the regions of the tree except the global one
+    fn regions(&self) -> Vec<&'a Region> {
+        fn collect<'r>(region: &'r Region, regions: &mut Vec<&'r Region>) {
+            for child in region.interp.iter() {
+                regions.push(child);
+                collect(child, regions);
+            }
+        }
+        let mut regions = vec![];
+        collect(&self.rules.region_tree, &mut regions);
+        regions
+    }
+
+    fn keyword_pattern(&self) -> Option<String> {
+        if self.keywords.is_empty() {
+            return None
+        }
+        let keywords = self.keywords.iter().map(|keyword| escape_regex(keyword)).collect::<Vec<_>>();
+        Some(format!("\\b(?:{})\\b", keywords.join("|")))
+    }
+
+    fn symbol_pattern(&self) -> Option<String> {
+        let mut symbols = self.rules.compounds.iter()
+            .map(|(left, right)| format!("{left}{right}"))
+            .collect::<Vec<_>>();
+        symbols.extend(self.rules.symbols.iter().map(|symbol| symbol.to_string()));
+        if symbols.is_empty() {
+            return None
+        }
+        // Compounds have to be matched before the symbols they consist of
+        symbols.sort_by_key(|symbol| std::cmp::Reverse(symbol.chars().count()));
+        Some(symbols.iter().map(|symbol| escape_regex(symbol)).collect::<Vec<_>>().join("|"))
+    }
+
+    fn end_pattern(&self, region: &Region) -> String {
+        match region.singleline {
+            true => format!("{}|$", escape_regex(&region.end)),
+            false => escape_regex(&region.end)
+        }
+    }
+
+    // Names of the patterns that can appear inside of given region
+    fn inner_patterns(&self, region: &'a Region) -> Vec<String> {
+        let mut patterns = self.interpolations(region).iter()
+            .map(|region| region.id.clone())
+            .collect::<Vec<_>>();
+        if region.tokenize {
+            patterns.extend(self.keyword_pattern().map(|_| KEYWORD.to_string()));
+            patterns.extend(self.symbol_pattern().map(|_| SYMBOL.to_string()));
+        } else {
+            patterns.insert(0, ESCAPE.to_string());
+        }
+        patterns
+    }
+
+    /// Export the grammar to the TextMate format (`.tmLanguage.json`)
+    pub fn to_textmate(&self) -> String {
+        let include = |name: &String| Tree::Map(vec![("include", Tree::text(format!("#{name}")))]);
+        let mut repository = vec![];
+        if let Some(pattern) = self.keyword_pattern() {
+            repository.push((KEYWORD.to_string(), Tree::Map(vec![
+                ("name", Tree::text(self.scope_of(KEYWORD, "keyword.control"))),
+                ("match", Tree::text(pattern))
+            ])));
+        }
+        if let Some(pattern) = self.symbol_pattern() {
+            repository.push((SYMBOL.to_string(), Tree::Map(vec![
+                ("name", Tree::text(self.scope_of(SYMBOL, "keyword.operator"))),
+                ("match", Tree::text(pattern))
+            ])));
+        }
+        repository.push((ESCAPE.to_string(), Tree::Map(vec![
+            ("name", Tree::text(self.scope_of(ESCAPE, "constant.character.escape"))),
+            ("match", Tree::text(format!("{}.", escape_regex(&self.rules.escape_symbol.to_string()))))
+        ])));
+        for region in self.regions() {
+            repository.push((region.id.clone(), Tree::Map(vec![
+                ("name", Tree::text(self.region_scope(region))),
+                ("begin", Tree::text(escape_regex(&region.begin))),
+                ("end", Tree::text(self.end_pattern(region))),
+                ("patterns", Tree::List(self.inner_patterns(region).iter().map(include).collect()))
+            ])));
+        }
+        let grammar = Tree::Map(vec![
+            ("name", Tree::text(&self.name)),
+            ("scopeName", Tree::text(&self.scope_name)),
+            ("fileTypes", Tree::List(self.file_types.iter().map(Tree::text).collect())),
+            ("patterns", Tree::List(self.inner_patterns(&self.rules.region_tree).iter().map(include).collect())),
+            ("repository", Tree::Object(repository))
+        ]);
+        let mut output = String::new();
+        grammar.write_json(&mut output, 0);
+        output.push('\n');
+        output
+    }
+
+    /// Export the grammar to the Sublime Text format (`.sublime-syntax`)
+    pub fn to_sublime(&self) -> String {
+        let include = |name: &String| Tree::Map(vec![("include", Tree::text(name))]);
+        let mut contexts = vec![
+            ("main".to_string(), Tree::List(self.inner_patterns(&self.rules.region_tree).iter().map(include).collect()))
+        ];
+        if let Some(pattern) = self.keyword_pattern() {
+            contexts.push((KEYWORD.to_string(), Tree::List(vec![Tree::Map(vec![
+                ("match", Tree::text(pattern)),
+                ("scope", Tree::text(self.scope_of(KEYWORD, "keyword.control")))
+            ])])));
+        }
+        if let Some(pattern) = self.symbol_pattern() {
+            contexts.push((SYMBOL.to_string(), Tree::List(vec![Tree::Map(vec![
+                ("match", Tree::text(pattern)),
+                ("scope", Tree::text(self.scope_of(SYMBOL, "keyword.operator")))
+            ])])));
+        }
+        contexts.push((ESCAPE.to_string(), Tree::List(vec![Tree::Map(vec![
+            ("match", Tree::text(format!("{}.", escape_regex(&self.rules.escape_symbol.to_string())))),
+            ("scope", Tree::text(self.scope_of(ESCAPE, "constant.character.escape")))
+        ])])));
+        for region in self.regions() {
+            let mut body = vec![
+                Tree::Map(vec![("meta_scope", Tree::text(self.region_scope(region)))]),
+                Tree::Map(vec![
+                    ("match", Tree::text(self.end_pattern(region))),
+                    ("pop", Tree::Bool(true))
+                ])
+            ];
+            body.extend(self.inner_patterns(region).iter().map(include));
+            contexts.push((region.id.clone(), Tree::List(vec![Tree::Map(vec![
+                ("match", Tree::text(escape_regex(&region.begin))),
+                ("push", Tree::List(body))
+            ])])));
+        }
+        let grammar = Tree::Map(vec![
+            ("name", Tree::text(&self.name)),
+            ("file_extensions", Tree::List(self.file_types.iter().map(Tree::text).collect())),
+            ("scope", Tree::text(&self.scope_name)),
+            ("contexts", Tree::Object(contexts))
+        ]);
+        let mut output = String::from("%YAML 1.2\n---\n");
+        grammar.write_yaml(&mut output, 0);
+        output
+    }
+}
+
+// Escape characters that have a special meaning in regular expressions
+fn escape_regex(text: &str) -> String {
+    let mut result = String::new();
+    for letter in text.chars() {
+        match letter {
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' | '/' | '-' => {
+                result.push('\\');
+                result.push(letter);
+            }
+            _ => result.push(letter)
+        }
+    }
+    result
+}
+
+// Document that can be written as JSON or YAML
+enum Tree {
+    Text(String),
+    Bool(bool),
+    List(Vec<Tree>),
+    // Map with static keys
+    Map(Vec<(&'static str, Tree)>),
+    // Map with keys computed at runtime
+    Object(Vec<(String, Tree)>)
+}
+
+impl Tree {
+    fn text<T: AsRef<str>>(text: T) -> Self {
+        Tree::Text(text.as_ref().to_string())
+    }
+
+    fn entries(&self) -> Vec<(&str, &Tree)> {
+        match self {
+            Tree::Map(entries) => entries.iter().map(|(key, value)| (*key, value)).collect(),
+            Tree::Object(entries) => entries.iter().map(|(key, value)| (key.as_str(), value)).collect(),
+            _ => vec![]
+        }
+    }
+
+    fn write_json(&self, output: &mut String, indent: usize) {
+        let padding = "  ".repeat(indent + 1);
+        match self {
+            Tree::Text(text) => output.push_str(&json_string(text)),
+            Tree::Bool(value) => output.push_str(&value.to_string()),
+            Tree::List(items) if items.is_empty() => output.push_str("[]"),
+            Tree::List(items) => {
+                output.push_str("[\n");
+                for (index, item) in items.iter().enumerate() {
+                    output.push_str(&padding);
+                    item.write_json(output, indent + 1);
+                    output.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                output.push_str(&"  ".repeat(indent));
+                output.push(']');
+            }
+            Tree::Map(_) | Tree::Object(_) => {
+                let entries = self.entries();
+                output.push_str("{\n");
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    output.push_str(&format!("{padding}{}: ", json_string(key)));
+                    value.write_json(output, indent + 1);
+                    output.push_str(if index + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                output.push_str(&"  ".repeat(indent));
+                output.push('}');
+            }
+        }
+    }
+
+    // Write the tree as a value of the YAML key or list item
+    fn write_yaml(&self, output: &mut String, indent: usize) {
+        let padding = "  ".repeat(indent);
+        match self {
+            Tree::Text(text) => output.push_str(&format!(" '{}'\n", text.replace('\'', "''"))),
+            Tree::Bool(value) => output.push_str(&format!(" {value}\n")),
+            Tree::List(items) if items.is_empty() => output.push_str(" []\n"),
+            Tree::List(items) => {
+                output.push('\n');
+                for item in items {
+                    output.push_str(&format!("{padding}-"));
+                    match item {
+                        // First entry of the map is written on the same line as the dash
+                        Tree::Map(_) | Tree::Object(_) => item.write_yaml_entries(output, indent + 1, true),
+                        _ => item.write_yaml(output, indent + 1)
+                    }
+                }
+            }
+            Tree::Map(_) | Tree::Object(_) => {
+                if indent > 0 {
+                    output.push('\n');
+                }
+                self.write_yaml_entries(output, indent, false)
+            }
+        }
+    }
+
+    fn write_yaml_entries(&self, output: &mut String, indent: usize, inline: bool) {
+        for (index, (key, value)) in self.entries().into_iter().enumerate() {
+            if index == 0 && inline {
+                output.push(' ');
+            } else {
+                output.push_str(&"  ".repeat(indent));
+            }
+            output.push_str(key);
+            output.push(':');
+            value.write_yaml(output, indent + 1);
+        }
+    }
+}
+
+fn json_string(text: &str) -> String {
+    format!("\"{}\"", escape_json(text))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reg;
+
+    fn rules() -> Rules {
+        Rules::new(vec!['&', '|', '+'], vec![('&', '&')], reg![
+            reg!(string as "String Literal" => {
+                begin: "'",
+                end: "'",
+                singleline: true
+            } => [
+                reg!(interp as "String Interpolation" => {
+                    begin: "${",
+                    end: "}",
+                    tokenize: true
+                } ref global)
+            ]),
+            reg!(comment as "Comment" => {
+                begin: "//",
+                end: "\n"
+            })
+        ])
+    }
+
+    #[test]
+    fn textmate_grammar() {
+        let rules = rules();
+        let grammar = HighlightGrammar::new("Herb Script", &rules)
+            .keywords(vec!["let"])
+            .scope("interp", "meta.interpolation.herb");
+        let output = grammar.to_textmate();
+        // Generated grammar has to be a valid JSON
+        let root: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(root["scopeName"], "source.herbscript");
+        assert!(output.contains(r#""match": "&&|&|\\||\\+""#));
+        assert!(output.contains(r#""match": "\\b(?:let)\\b""#));
+        assert!(output.contains(r#""name": "comment.block.comment.herbscript""#));
+        assert!(output.contains(r#""end": "'|$""#));
+        assert!(output.contains(r#""name": "meta.interpolation.herb""#));
+    }
+
+    #[test]
+    fn sublime_grammar() {
+        let rules = rules();
+        let output = HighlightGrammar::new("Herb", &rules).file_types(vec!["herb"]).to_sublime();
+        let expected = [
+            "  interp:",
+            "    - match: '\\$\\{'",
+            "      push:",
+            "        - meta_scope: 'meta.embedded.interp.herb'",
+            "        - match: '\\}'",
+            "          pop: true",
+            "        - include: 'string'",
+            "        - include: 'comment'",
+            "        - include: '__symbol'",
+        ].join("\n");
+        assert!(output.starts_with("%YAML 1.2\n---\nname: 'Herb'\nfile_extensions:\n  - 'herb'\n"));
+        assert!(output.contains(&expected));
+    }
+
+    #[test]
+    fn region_named_like_pattern() {
+        let rules = Rules::new(vec!['+'], vec![], reg![
+            reg!(symbol as "Symbol Literal" => {
+                begin: ":",
+                end: " "
+            })
+        ]);
+        let grammar = HighlightGrammar::new("Herb", &rules)
+            .scope("symbol", "constant.other.symbol.herb")
+            .scope("__symbol", "keyword.operator.arithmetic.herb");
+        let root: serde_json::Value = serde_json::from_str(&grammar.to_textmate()).unwrap();
+        // Region keeps its own entry next to the generated pattern for the symbols
+        assert_eq!(root["repository"]["symbol"]["name"], "constant.other.symbol.herb");
+        assert_eq!(root["repository"]["__symbol"]["name"], "keyword.operator.arithmetic.herb");
+        let sublime = grammar.to_sublime();
+        assert!(sublime.contains("meta_scope: 'constant.other.symbol.herb'"));
+        assert!(sublime.contains("scope: 'keyword.operator.arithmetic.herb'"));
+    }
+}
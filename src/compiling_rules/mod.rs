@@ -6,6 +6,16 @@
 #[macro_use]
 mod rules;
 mod region;
+#[cfg(feature = "definitions")]
+mod document;
+#[cfg(feature = "definitions")]
+mod definition;
+mod highlight;
+mod generator;
 
 pub use rules::*;
-pub use region::*;
\ No newline at end of file
+pub use region::*;
+#[cfg(feature = "definitions")]
+pub use definition::*;
+pub use highlight::*;
+pub use generator::*;
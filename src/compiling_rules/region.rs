@@ -112,6 +112,8 @@ macro_rules! reg {
 ///  - `tokenize`
 ///  - `allow_unclosed_region`
 ///  - `singleline`
+///
+/// Errors reported for the region can be customised with `RegionMessages`.
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Region {
@@ -181,6 +183,67 @@ impl Region {
         }
         generate_region_rec(self.clone(), HashMap::new())
     }
+
+    /// Find the region with given id in the tree of this region
+    pub(crate) fn find(&self, id: &str) -> Option<&Region> {
+        if self.id == id {
+            return Some(self)
+        }
+        self.interp.iter().find_map(|child| child.find(id))
+    }
+}
+
+/// Custom diagnostics of the errors reported for a region
+///
+/// Messages are assigned to the region by its id with `Rules::set_region_messages`.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let region = reg![
+///     reg!(str as "string literal" => {
+///         begin: "'",
+///         end: "'"
+///     })
+/// ];
+/// let messages = RegionMessages::new()
+///     .unclosed_message("String is never closed")
+///     .help("Add the closing quote");
+/// Rules::new(vec![], vec![], region).set_region_messages("str", messages);
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RegionMessages {
+    /// Message of the error reported when the region is left unclosed
+    pub unclosed: Option<String>,
+    /// Message of the error reported when the singleline region spans multiple lines
+    pub singleline: Option<String>,
+    /// Help text that is attached to the errors reported for the region
+    pub help: Option<String>
+}
+
+impl RegionMessages {
+    /// Create messages that do not customise anything yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the message of the error reported when the region is left unclosed
+    pub fn unclosed_message<T: AsRef<str>>(mut self, message: T) -> Self {
+        self.unclosed = Some(message.as_ref().to_string());
+        self
+    }
+
+    /// Set the message of the error reported when the singleline region spans multiple lines
+    pub fn singleline_message<T: AsRef<str>>(mut self, message: T) -> Self {
+        self.singleline = Some(message.as_ref().to_string());
+        self
+    }
+
+    /// Set the help text attached to the errors reported for the region
+    pub fn help<T: AsRef<str>>(mut self, help: T) -> Self {
+        self.help = Some(help.as_ref().to_string());
+        self
+    }
 }
 
 #[cfg(test)]
@@ -191,28 +254,28 @@ mod test {
     #[test]
     fn region_parses_correctly() {
         let expected = Region {
-            id: format!("global"),
-            name: format!("Global context"),
-            begin: format!(""),
-            end: format!(""),
+            id: "global".to_string(),
+            name: "Global context".to_string(),
+            begin: String::new(),
+            end: String::new(),
             interp: vec![
                 Region {
-                    id: format!("string"),
-                    name: format!("String Literal"),
-                    begin: format!("'"),
-                    end: format!("'"),
+                    id: "string".to_string(),
+                    name: "String Literal".to_string(),
+                    begin: "'".to_string(),
+                    end: "'".to_string(),
                     interp: vec![
                         Region {
-                            id: format!("string_interp"),
-                            name: format!("String Interpolation"),
-                            begin: format!("${{"),
-                            end: format!("}}"),
+                            id: "string_interp".to_string(),
+                            name: "String Interpolation".to_string(),
+                            begin: "${".to_string(),
+                            end: "}".to_string(),
                             interp: vec![],
                             tokenize: true,
                             allow_unclosed_region: false,
                             singleline: false,
                             global: false,
-                            references: Some(format!("global"))
+                            references: Some("global".to_string())
                         }],
                     tokenize: false,
                     allow_unclosed_region: false,
@@ -1,4 +1,5 @@
-use super::region::Region;
+use std::collections::HashMap;
+use super::region::{Region, RegionMessages};
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
@@ -40,7 +41,14 @@ pub struct Rules {
     /// Escape symbol
     pub escape_symbol: char,
     /// Vector of pairs of symbols that should be merged together
-    pub compounds: Vec<(char, char)>
+    pub compounds: Vec<(char, char)>,
+    /// Pairs of opening and closing brackets (like `(` and `)`).
+    /// These are used to detect incomplete input - for instance in a REPL.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub brackets: Vec<(char, char)>,
+    /// Custom diagnostics of the regions by their id
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub region_messages: HashMap<String, RegionMessages>
 }
 
 impl Rules {
@@ -50,7 +58,9 @@ impl Rules {
             symbols,
             compounds,
             region_tree,
-            escape_symbol: '\\'
+            escape_symbol: '\\',
+            brackets: vec![],
+            region_messages: HashMap::new()
         }
     }
 
@@ -59,4 +69,16 @@ impl Rules {
         self.escape_symbol = symbol;
         self
     }
+
+    /// Set pairs of brackets that have to be balanced for the input to be complete
+    pub fn set_brackets(mut self, brackets: Vec<(char, char)>) -> Self {
+        self.brackets = brackets;
+        self
+    }
+
+    /// Customise the errors reported for the region with given id
+    pub fn set_region_messages<T: AsRef<str>>(mut self, id: T, messages: RegionMessages) -> Self {
+        self.region_messages.insert(id.as_ref().to_string(), messages);
+        self
+    }
 }
\ No newline at end of file
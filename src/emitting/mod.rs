@@ -0,0 +1,13 @@
+//! Module for emitting the output of your compiler
+//!
+//! This module supplies you with tools that lay out the code of formatters
+//! and code generators, among them - the pretty-printer `Doc` and the `CodeWriter`
+//! that maps the generated code back to the source.
+//! Combinators that build the documents live in the `pretty` module so that
+//! their short names do not clash with the names in your code.
+
+pub mod pretty;
+mod writer;
+
+pub use pretty::Doc;
+pub use writer::*;
@@ -0,0 +1,324 @@
+//! Pretty-printer documents
+//!
+//! Import the combinators with `use heraclitus_compiler::emitting::pretty::*`
+//! or call them through the module like `pretty::text("...")`.
+
+use std::ops::Range;
+use crate::compiling::Metadata;
+
+/// Document that can be laid out to fit in the desired width
+///
+/// Documents are built with the `text`, `line`, `softline`, `hardline`, `group`,
+/// `nest` and `concat` combinators. Every group is printed on a single line if it fits
+/// in the remaining width. Otherwise the lines of the group are broken.
+/// # Example
+/// ```
+/// use heraclitus_compiler::emitting::pretty::*;
+/// let call = group(concat(vec![
+///     text("print("),
+///     nest(4, concat(vec![softline(), join(vec![text("first"), text("second")], concat(vec![text(","), line()]))])),
+///     softline(),
+///     text(")")
+/// ]));
+/// assert_eq!(call.pretty(80), "print(first, second)");
+/// assert_eq!(call.pretty(10), "print(\n    first,\n    second\n)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Doc {
+    /// Empty document
+    #[default]
+    Nil,
+    /// Text that is printed as it is
+    Text(String),
+    /// Space or a new line if the group is broken
+    Line,
+    /// Nothing or a new line if the group is broken
+    SoftLine,
+    /// New line that always breaks the group
+    HardLine,
+    /// Documents printed one after another
+    Concat(Vec<Doc>),
+    /// Document with lines indented by given amount of spaces
+    Nest(usize, Box<Doc>),
+    /// Document that is printed on a single line if it fits
+    Group(Box<Doc>)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break
+}
+
+impl Doc {
+    /// Append the document after this one
+    pub fn append(self, doc: impl Into<Doc>) -> Doc {
+        match (self, doc.into()) {
+            (Doc::Nil, doc) | (doc, Doc::Nil) => doc,
+            (Doc::Concat(mut docs), doc) => {
+                docs.push(doc);
+                Doc::Concat(docs)
+            }
+            (this, doc) => Doc::Concat(vec![this, doc])
+        }
+    }
+
+    /// Lay out the document in given width
+    ///
+    /// Width is counted in characters. Text that is longer than the width overflows it.
+    pub fn pretty(&self, width: usize) -> String {
+        let mut output = String::new();
+        let mut column = 0;
+        let mut stack = vec![(0, Mode::Break, self)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Nil => {}
+                Doc::Text(text) => {
+                    output.push_str(text);
+                    column = match text.rfind('\n') {
+                        Some(index) => text[index + 1..].chars().count(),
+                        None => column + text.chars().count()
+                    };
+                }
+                Doc::Line if mode == Mode::Flat => {
+                    output.push(' ');
+                    column += 1;
+                }
+                Doc::SoftLine if mode == Mode::Flat => {}
+                Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                    // Lines do not end with the whitespace
+                    output.truncate(output.trim_end_matches(' ').len());
+                    output.push('\n');
+                    output.push_str(&" ".repeat(indent));
+                    column = indent;
+                }
+                Doc::Concat(docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+                }
+                Doc::Nest(size, doc) => stack.push((indent + size, mode, doc)),
+                Doc::Group(doc) => {
+                    let remaining = width as isize - column as isize;
+                    let mode = match mode == Mode::Flat || fits(remaining, (indent, Mode::Flat, doc), &stack) {
+                        true => Mode::Flat,
+                        false => Mode::Break
+                    };
+                    stack.push((indent, mode, doc));
+                }
+            }
+        }
+        output.truncate(output.trim_end_matches(' ').len());
+        output
+    }
+
+    /// Turn the range of tokens back into a document
+    ///
+    /// Tokens that were separated by whitespace are separated by a single space
+    /// and tokens that were on different lines are separated by a hard line.
+    /// A single empty line between the tokens is preserved. Words are printed as they are.
+    /// # Example
+    /// ```
+    /// # use heraclitus_compiler::prelude::*;
+    /// # let rules = Rules::new(vec!['=', '(', ')'], vec![], reg![]);
+    /// let cc = Compiler::builder("HerbScript", rules).source("let  a=(1)\n\n\n\nprint a").build().unwrap();
+    /// let meta = DefaultMetadata::new(cc.tokenize().unwrap(), None, Some(cc.code.clone()));
+    /// let doc = Doc::from_tokens(&meta, 0..8);
+    /// assert_eq!(doc.pretty(80), "let a=(1)\n\nprint a");
+    /// ```
+    pub fn from_tokens<M: Metadata>(meta: &M, range: Range<usize>) -> Doc {
+        let code = meta.get_source().map(|source| source.code().as_str());
+        let mut docs = vec![];
+        let mut newlines = 0;
+        let mut previous: Option<usize> = None;
+        for index in range {
+            let (Some(word), Some(span)) = (meta.get_word_at(index), meta.get_span_at(index)) else {
+                break
+            };
+            // Skip the new line token that overlaps the end of the previous token
+            if previous.is_some_and(|end| span.start() < end) {
+                continue
+            }
+            let gap = previous.and_then(|end| code.and_then(|code| code.get(end..span.start())));
+            match gap {
+                Some(gap) => newlines += gap.matches('\n').count(),
+                None if previous.is_some_and(|end| end < span.start()) => docs.push(text(" ")),
+                None => {}
+            }
+            previous = Some(span.end());
+            // Words such as comments can end with the new line
+            let trimmed = word.trim_end_matches('\n');
+            let trailing = word.len() - trimmed.len();
+            if !trimmed.is_empty() {
+                if !docs.is_empty() {
+                    match newlines {
+                        0 if gap.is_some_and(|gap| !gap.is_empty()) => docs.push(text(" ")),
+                        0 => {}
+                        1 => docs.push(hardline()),
+                        _ => docs.extend([hardline(), hardline()])
+                    }
+                }
+                docs.push(text(trimmed));
+                newlines = 0;
+            }
+            newlines += trailing;
+        }
+        concat(docs)
+    }
+}
+
+// Check if the document fits in the remaining width until the next line break
+fn fits(mut width: isize, next: (usize, Mode, &Doc), rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![next];
+    let mut rest = rest.iter().rev();
+    while width >= 0 {
+        let (indent, mode, doc) = match stack.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some(item) => *item,
+                None => return true
+            }
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(text) => match text.find('\n') {
+                Some(index) => return width >= text[..index].chars().count() as isize,
+                None => width -= text.chars().count() as isize
+            },
+            Doc::Line if mode == Mode::Flat => width -= 1,
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::Line | Doc::SoftLine => return true,
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(size, doc) => stack.push((indent + size, mode, doc)),
+            Doc::Group(doc) => stack.push((indent, mode, doc))
+        }
+    }
+    false
+}
+
+impl From<&str> for Doc {
+    fn from(text: &str) -> Self {
+        Doc::Text(text.to_string())
+    }
+}
+
+impl From<String> for Doc {
+    fn from(text: String) -> Self {
+        Doc::Text(text)
+    }
+}
+
+/// Create an empty document
+pub fn nil() -> Doc {
+    Doc::Nil
+}
+
+/// Create a document with the text printed as it is
+pub fn text<T: AsRef<str>>(text: T) -> Doc {
+    Doc::Text(text.as_ref().to_string())
+}
+
+/// Create a space that becomes a new line if the group is broken
+pub fn line() -> Doc {
+    Doc::Line
+}
+
+/// Create a new line that disappears if the group fits on a single line
+pub fn softline() -> Doc {
+    Doc::SoftLine
+}
+
+/// Create a new line that always breaks the group
+pub fn hardline() -> Doc {
+    Doc::HardLine
+}
+
+/// Print the document on a single line if it fits
+pub fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+/// Indent the lines of the document by given amount of spaces
+pub fn nest(indent: usize, doc: Doc) -> Doc {
+    Doc::Nest(indent, Box::new(doc))
+}
+
+/// Print the documents one after another
+pub fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+/// Print the documents with the separator between them
+pub fn join(docs: Vec<Doc>, separator: Doc) -> Doc {
+    let mut result = vec![];
+    for (index, doc) in docs.into_iter().enumerate() {
+        if index > 0 {
+            result.push(separator.clone());
+        }
+        result.push(doc);
+    }
+    Doc::Concat(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compiling::{Compiler, DefaultMetadata};
+    use crate::compiling_rules::{Region, Rules};
+    use crate::reg;
+
+    fn block(statements: Vec<&str>) -> Doc {
+        group(concat(vec![
+            text("{"),
+            nest(2, concat(vec![line(), join(statements.into_iter().map(text).collect(), concat(vec![text(";"), line()]))])),
+            line(),
+            text("}")
+        ]))
+    }
+
+    #[test]
+    fn group_layout() {
+        let doc = block(vec!["a = 1", "b = 2"]);
+        assert_eq!(doc.pretty(20), "{ a = 1; b = 2 }");
+        assert_eq!(doc.pretty(10), "{\n  a = 1;\n  b = 2\n}");
+        // Text after the group has to fit as well
+        let doc = concat(vec![block(vec!["a"]), text(" else block")]);
+        assert_eq!(doc.pretty(16), "{ a } else block");
+        assert_eq!(doc.pretty(15), "{\n  a\n} else block");
+    }
+
+    #[test]
+    fn nested_groups() {
+        let inner = group(concat(vec![text("f("), nest(2, concat(vec![softline(), text("argument")])), softline(), text(")")]));
+        let doc = group(concat(vec![text("let x ="), nest(2, concat(vec![line(), inner]))]));
+        assert_eq!(doc.pretty(40), "let x = f(argument)");
+        assert_eq!(doc.pretty(16), "let x =\n  f(argument)");
+        assert_eq!(doc.pretty(8), "let x =\n  f(\n    argument\n  )");
+    }
+
+    #[test]
+    fn hard_lines() {
+        let doc = group(concat(vec![text("a"), hardline(), hardline(), nest(4, concat(vec![text("b"), line(), text("c")]))]));
+        assert_eq!(doc.pretty(80), "a\n\nb\n    c");
+        assert_eq!(text("a").append(nil()).append("b"), concat(vec![text("a"), text("b")]));
+    }
+
+    #[test]
+    fn tokens_to_doc() {
+        let rules = Rules::new(vec!['='], vec![], reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            }),
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n"
+            })
+        ]);
+        let cc = Compiler::builder("Test", rules).source("a =  'x\n  y' # note\nb=c").build().unwrap();
+        let meta = DefaultMetadata::new(cc.tokenize().unwrap(), None, Some(cc.code.clone()));
+        let doc = nest(4, Doc::from_tokens(&meta, 0..10));
+        assert_eq!(doc.pretty(80), "a = 'x\n  y' # note\n    b=c");
+        let meta = DefaultMetadata::new(cc.tokenize().unwrap(), None, None);
+        assert_eq!(Doc::from_tokens(&meta, 0..3).pretty(80), "a = 'x\n  y'");
+    }
+}
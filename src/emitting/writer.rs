@@ -0,0 +1,369 @@
+use std::fmt::Write as FmtWrite;
+use crate::compiling::json::escape_json;
+use crate::compiling::{SourceMap, Span, Token};
+use crate::compiling::failing::position_info::{PositionInfo, Position};
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Mapping of the position in the generated code to the position in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    /// Row and column in the generated code
+    pub generated: (usize, usize),
+    /// Path of the source
+    pub source: String,
+    /// Row and column in the source
+    pub original: (usize, usize),
+    /// Name of the symbol in the source
+    pub name: Option<String>
+}
+
+/// Writer of the generated code
+///
+/// Writer indents the lines by the current indentation level and remembers
+/// which positions of the source the generated code came from. These mappings
+/// can be looked up or exported as a Source Map v3 so that the errors reported
+/// for the generated code can be mapped back to the original lines.
+/// Rows and columns start from 1 and columns are counted in characters.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let mut writer = CodeWriter::new().indent_with("  ");
+/// writer.write_mapped("if true; then", &PositionInfo::at_pos(Some("main.ab".to_string()), (1, 1), 2));
+/// writer.newline();
+/// writer.indented(|writer| {
+///     writer.map(&PositionInfo::at_pos(Some("main.ab".to_string()), (2, 5), 4));
+///     writer.writeln("echo 12");
+/// });
+/// writer.writeln("fi");
+/// assert_eq!(writer.code(), "if true; then\n  echo 12\nfi\n");
+/// assert_eq!(writer.lookup(2, 8).unwrap().original, (2, 5));
+/// assert!(writer.to_source_map(Some("main.sh")).contains(r#""mappings": "AAAA;EACI""#));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeWriter {
+    code: String,
+    indent: String,
+    level: usize,
+    row: usize,
+    col: usize,
+    mappings: Vec<Mapping>,
+    sources: Option<SourceMap>
+}
+
+impl Default for CodeWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CodeWriter {
+    /// Create an empty writer that indents with four spaces
+    pub fn new() -> Self {
+        CodeWriter {
+            code: String::new(),
+            indent: "    ".to_string(),
+            level: 0,
+            row: 1,
+            col: 1,
+            mappings: vec![],
+            sources: None
+        }
+    }
+
+    /// Set the text of a single indentation level
+    pub fn indent_with<T: AsRef<str>>(mut self, indent: T) -> Self {
+        self.indent = indent.as_ref().to_string();
+        self
+    }
+
+    /// Set the sources that the spans of the tokens point to
+    ///
+    /// Sources are needed to map the tokens and they are embedded in the exported source map.
+    pub fn sources(mut self, sources: SourceMap) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    /// Increase the indentation level
+    pub fn indent(&mut self) -> &mut Self {
+        self.level += 1;
+        self
+    }
+
+    /// Decrease the indentation level
+    pub fn dedent(&mut self) -> &mut Self {
+        self.level = self.level.saturating_sub(1);
+        self
+    }
+
+    /// Write the code of the closure one indentation level deeper
+    pub fn indented(&mut self, write: impl FnOnce(&mut Self)) -> &mut Self {
+        self.indent();
+        write(self);
+        self.dedent()
+    }
+
+    /// Write the code
+    ///
+    /// Lines are indented when their first character is written so that empty lines stay empty.
+    pub fn write<T: AsRef<str>>(&mut self, text: T) -> &mut Self {
+        for letter in text.as_ref().chars() {
+            if letter == '\n' {
+                self.code.push('\n');
+                self.row += 1;
+                self.col = 1;
+                continue
+            }
+            if self.col == 1 {
+                for _ in 0..self.level {
+                    self.code.push_str(&self.indent);
+                }
+                self.col += self.indentation_width();
+            }
+            self.code.push(letter);
+            self.col += 1;
+        }
+        self
+    }
+
+    /// Write the code and end the line
+    pub fn writeln<T: AsRef<str>>(&mut self, text: T) -> &mut Self {
+        self.write(text).newline()
+    }
+
+    /// End the line
+    pub fn newline(&mut self) -> &mut Self {
+        self.write("\n")
+    }
+
+    /// Map the code written next to the position in the source
+    pub fn map(&mut self, position: &PositionInfo) -> &mut Self {
+        self.map_named(position, None::<&str>)
+    }
+
+    /// Map the code written next to the position of the named symbol in the source
+    pub fn map_named<T: AsRef<str>>(&mut self, position: &PositionInfo, name: Option<T>) -> &mut Self {
+        let original = match (&position.position, position.span) {
+            (Position::Pos(row, col), _) => Some((*row, *col)),
+            (Position::EOF, Some(span)) => self.resolve(span).map(|(_, position)| position),
+            (Position::EOF, None) => None
+        };
+        let source = position.path.clone()
+            .or_else(|| position.span.and_then(|span| self.resolve(span)).and_then(|(path, _)| path))
+            .unwrap_or_else(|| position.get_path());
+        if let Some(original) = original {
+            self.push_mapping(source, original, name.map(|name| name.as_ref().to_string()));
+        }
+        self
+    }
+
+    /// Map the code written next to the token
+    ///
+    /// Token is mapped only if it points to one of the sources of the writer.
+    pub fn map_token(&mut self, token: &Token) -> &mut Self {
+        if let Some((path, original)) = self.resolve(token.span) {
+            let source = path.unwrap_or_else(|| "[unknown]".to_string());
+            self.push_mapping(source, original, None);
+        }
+        self
+    }
+
+    /// Write the code that comes from the position in the source
+    pub fn write_mapped<T: AsRef<str>>(&mut self, text: T, position: &PositionInfo) -> &mut Self {
+        self.map(position).write(text)
+    }
+
+    /// Find the mapping of the position in the generated code
+    ///
+    /// The closest mapping before the position in the same row is returned.
+    /// If there is no such mapping, the first mapping of the row is returned instead.
+    pub fn lookup(&self, row: usize, col: usize) -> Option<&Mapping> {
+        let mut mappings = self.mappings.iter().filter(|mapping| mapping.generated.0 == row);
+        let first = mappings.clone().next();
+        mappings.rfind(|mapping| mapping.generated.1 <= col).or(first)
+    }
+
+    /// Get all the recorded mappings
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// Get the generated code
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Take the generated code out of the writer
+    pub fn into_code(self) -> String {
+        self.code
+    }
+
+    /// Export the mappings as a Source Map v3 JSON
+    pub fn to_source_map(&self, file: Option<&str>) -> String {
+        let mut sources: Vec<&str> = vec![];
+        let mut names: Vec<&str> = vec![];
+        for mapping in self.mappings.iter() {
+            if !sources.contains(&mapping.source.as_str()) {
+                sources.push(&mapping.source);
+            }
+            if let Some(name) = &mapping.name {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        let quote = |text: &str| format!("\"{}\"", escape_json(text));
+        let list = |items: Vec<String>| format!("[{}]", items.join(", "));
+        let contents = sources.iter().map(|path| {
+            self.sources.as_ref()
+                .and_then(|sources| sources.find(path))
+                .and_then(|id| self.sources.as_ref()?.get(id))
+                .map_or("null".to_string(), |source| quote(source.code()))
+        }).collect();
+        let mut result = String::from("{\n  \"version\": 3,\n");
+        if let Some(file) = file {
+            let _ = writeln!(result, "  \"file\": {},", quote(file));
+        }
+        let _ = writeln!(result, "  \"sources\": {},", list(sources.iter().map(|path| quote(path)).collect()));
+        let _ = writeln!(result, "  \"sourcesContent\": {},", list(contents));
+        let _ = writeln!(result, "  \"names\": {},", list(names.iter().map(|name| quote(name)).collect()));
+        let _ = writeln!(result, "  \"mappings\": \"{}\"", self.encode_mappings(&sources, &names));
+        result.push('}');
+        result
+    }
+
+    // Encode the mappings with the base64 VLQ
+    fn encode_mappings(&self, sources: &[&str], names: &[&str]) -> String {
+        let index = |items: &[&str], item: &str| items.iter().position(|value| *value == item).unwrap_or_default() as isize;
+        let mut result = String::new();
+        let mut row = 1;
+        let mut previous = (0, 0, 0, 0, 0);
+        for mapping in self.mappings.iter() {
+            let (generated_row, generated_col) = mapping.generated;
+            if generated_row > row {
+                result.push_str(&";".repeat(generated_row - row));
+                row = generated_row;
+                previous.0 = 0;
+            } else if !result.is_empty() && !result.ends_with(';') {
+                result.push(',');
+            }
+            let current = (
+                generated_col as isize - 1,
+                index(sources, &mapping.source),
+                mapping.original.0 as isize - 1,
+                mapping.original.1 as isize - 1,
+                mapping.name.as_ref().map(|name| index(names, name))
+            );
+            encode_vlq(&mut result, current.0 - previous.0);
+            encode_vlq(&mut result, current.1 - previous.1);
+            encode_vlq(&mut result, current.2 - previous.2);
+            encode_vlq(&mut result, current.3 - previous.3);
+            if let Some(name) = current.4 {
+                encode_vlq(&mut result, name - previous.4);
+                previous.4 = name;
+            }
+            previous = (current.0, current.1, current.2, current.3, previous.4);
+        }
+        result
+    }
+
+    fn indentation_width(&self) -> usize {
+        self.indent.chars().count() * self.level
+    }
+
+    fn push_mapping(&mut self, source: String, original: (usize, usize), name: Option<String>) {
+        // Indentation is written together with the first character of the line
+        let col = match self.col {
+            1 => 1 + self.indentation_width(),
+            col => col
+        };
+        let mapping = Mapping { generated: (self.row, col), source, original, name };
+        // Later mapping of the same position replaces the previous one
+        match self.mappings.last_mut() {
+            Some(last) if last.generated == mapping.generated => *last = mapping,
+            _ => self.mappings.push(mapping)
+        }
+    }
+
+    // Find the path and position of the span in the sources
+    fn resolve(&self, span: Span) -> Option<(Option<String>, (usize, usize))> {
+        let source = self.sources.as_ref()?.get(span.file)?;
+        Some((source.path().cloned(), source.offset_position(span.start())))
+    }
+}
+
+fn encode_vlq(result: &mut String, value: isize) {
+    let mut value = match value < 0 {
+        true => ((-value as usize) << 1) | 1,
+        false => (value as usize) << 1
+    };
+    loop {
+        let mut digit = value & 0b11111;
+        value >>= 5;
+        if value > 0 {
+            digit |= 0b100000;
+        }
+        result.push(BASE64[digit] as char);
+        if value == 0 {
+            break
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(row: usize, col: usize) -> PositionInfo {
+        PositionInfo::at_pos(Some("main.ab".to_string()), (row, col), 1)
+    }
+
+    #[test]
+    fn indentation() {
+        let mut writer = CodeWriter::new();
+        writer.writeln("main() {");
+        writer.indented(|writer| {
+            writer.write("a=1\n\nb=2\n");
+            writer.indent().writeln("c=3").dedent();
+        });
+        writer.dedent().write("}");
+        assert_eq!(writer.code(), "main() {\n    a=1\n\n    b=2\n        c=3\n}");
+    }
+
+    #[test]
+    fn vlq() {
+        let mut result = String::new();
+        for value in [0, 1, -1, 15, 16, -17, 1000] {
+            encode_vlq(&mut result, value);
+            result.push(' ');
+        }
+        assert_eq!(result, "A C D e gB jB w+B ");
+    }
+
+    #[test]
+    fn source_map() {
+        let mut sources = SourceMap::new();
+        let file = sources.add(Some("lib.ab".to_string()), "let x = 1");
+        let token = Token::new("x", Span::new(file, 4, 5));
+        let mut writer = CodeWriter::new().sources(sources);
+        writer.write_mapped("echo", &at(3, 1)).write(" ");
+        writer.map_named(&at(3, 6), Some("value")).writeln("\"$x\"");
+        writer.indented(|writer| {
+            writer.map_token(&token).write("x=1");
+        });
+        assert_eq!(writer.lookup(1, 3).unwrap().original, (3, 1));
+        assert_eq!(writer.lookup(1, 9).unwrap().name.as_deref(), Some("value"));
+        assert_eq!(writer.lookup(2, 1).unwrap().generated, (2, 5));
+        assert_eq!(writer.lookup(3, 1), None);
+        assert_eq!(writer.to_source_map(None), [
+            "{",
+            "  \"version\": 3,",
+            "  \"sources\": [\"main.ab\", \"lib.ab\"],",
+            "  \"sourcesContent\": [null, \"let x = 1\"],",
+            "  \"names\": [\"value\"],",
+            "  \"mappings\": \"AAEA,KAAKA;ICFD\"",
+            "}"
+        ].join("\n"));
+    }
+}
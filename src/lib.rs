@@ -21,17 +21,20 @@
 //! ```
 //! use heraclitus_compiler::prelude::*;
 //! # let rules = Rules::new(vec![], vec![], reg![]);
-//! Compiler::new("HerbScript", rules);
+//! Compiler::builder("HerbScript", rules);
 //! ```
 //! It is recommended to use included prelude to import just the things we will actually need.
 //! 
-//! The `Compiler` requires lexer rules in order to exist.
+//! The `Compiler` requires lexer rules and the source code in order to exist.
 //! 
 //! ```
 //! # use heraclitus_compiler::prelude::*;
 //! # fn compiler() -> Result<(), LexerError> {
 //! # let rules = Rules::new(vec![], vec![], reg![]);
-//! let cc = Compiler::new("HerbScript", rules);
+//! let cc = Compiler::builder("HerbScript", rules)
+//!     .source("let a = 12")
+//!     .build()
+//!     .unwrap();
 //! let tokens = cc.tokenize()?;
 //! # Ok(())
 //! # }
@@ -39,6 +42,8 @@
 
 pub mod compiling_rules;
 pub mod compiling;
+pub mod emitting;
+pub mod testing;
 
 pub mod prelude {
     //! Use all the necessary modules
@@ -51,8 +56,12 @@ pub mod prelude {
     pub use crate::*;
     pub use crate::compiling_rules::*;
     pub use crate::compiling::*;
+    pub use crate::emitting::*;
     pub use crate::compiling::patterns::*;
     pub use crate::compiling::failing::position_info::{PositionInfo, Position};
     pub use crate::compiling::failing::message::{Message, MessageType};
     pub use crate::compiling::failing::failure::Failure;
+    pub use crate::compiling::failing::catalog::{Catalog, Catalogs, keys, translate, set_locale, get_locale, add_catalog};
+    #[cfg(feature = "derive")]
+    pub use heraclitus_derive::{Metadata, SyntaxModule};
 }
\ No newline at end of file
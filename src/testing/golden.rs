@@ -0,0 +1,280 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::compiling::{lexer_message, Compiler, DefaultMetadata, Metadata, SourceMap, SyntaxModule, Token};
+use crate::compiling::failing::failure::Failure;
+use crate::compiling::failing::position_info::Position;
+
+/// Environment variable that updates the golden files instead of comparing them
+pub const BLESS_ENV: &str = "HERACLITUS_BLESS";
+
+/// Golden file test runner
+///
+/// Runner renders every source file in the directory and compares the result with
+/// the file of the same name that ends with `.expected` (for instance `if.cb.expected`).
+/// Setting the `HERACLITUS_BLESS` environment variable writes the rendered output
+/// to the expected files instead, which is how the snapshots are created and updated.
+/// # Example
+/// ```no_run
+/// # use heraclitus_compiler::prelude::*;
+/// # use heraclitus_compiler::testing::*;
+/// # let rules = Rules::new(vec![], vec![], reg![]);
+/// Golden::new("tests/golden")
+///     .extension("herb")
+///     .run(|name, code| {
+///         let cc = Compiler::builder("HerbScript", rules.clone())
+///             .path(name)
+///             .source(code)
+///             .build()
+///             .unwrap();
+///         dump_tokens(&cc)
+///     });
+/// ```
+#[derive(Debug, Clone)]
+pub struct Golden {
+    dir: PathBuf,
+    extension: Option<String>,
+    bless: bool
+}
+
+impl Golden {
+    /// Create a runner for the source files in given directory
+    pub fn new<T: AsRef<Path>>(dir: T) -> Self {
+        Golden {
+            dir: dir.as_ref().to_path_buf(),
+            extension: None,
+            bless: Self::is_blessed_by_env()
+        }
+    }
+
+    /// Check if updating the golden files was requested by the environment variable
+    pub fn is_blessed_by_env() -> bool {
+        std::env::var(BLESS_ENV).is_ok_and(|value| !value.is_empty() && value != "0")
+    }
+
+    /// Test only the source files with given extension
+    pub fn extension<T: AsRef<str>>(mut self, extension: T) -> Self {
+        self.extension = Some(extension.as_ref().trim_start_matches('.').to_string());
+        self
+    }
+
+    /// Update the golden files instead of comparing them
+    pub fn bless(mut self, bless: bool) -> Self {
+        self.bless = bless;
+        self
+    }
+
+    /// Get the source files that are going to be tested in alphabetical order
+    pub fn sources(&self) -> Result<Vec<PathBuf>, String> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|error| format!("Could not read directory {}: {error}", self.dir.display()))?;
+        let mut sources = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter(|path| path.extension().is_some_and(|extension| extension != "expected"))
+            .filter(|path| match &self.extension {
+                Some(extension) => path.extension().is_some_and(|ext| ext == extension.as_str()),
+                None => true
+            })
+            .collect::<Vec<_>>();
+        sources.sort();
+        Ok(sources)
+    }
+
+    /// Render all the source files and compare them with the golden files
+    ///
+    /// The render function receives the name of the file and its code.
+    /// Returns the number of tested files or a report of all the mismatches.
+    pub fn check(&self, mut render: impl FnMut(&str, &str) -> String) -> Result<usize, String> {
+        let sources = self.sources()?;
+        if sources.is_empty() {
+            return Err(format!("No source files found in {}", self.dir.display()))
+        }
+        let mut failures = vec![];
+        for source in sources.iter() {
+            let name = source.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let code = fs::read_to_string(source)
+                .map_err(|error| format!("Could not read {}: {error}", source.display()))?;
+            let actual = render(&name, &code);
+            let path = PathBuf::from(format!("{}.expected", source.display()));
+            let expected = fs::read_to_string(&path).ok().map(|expected| expected.replace("\r\n", "\n"));
+            if expected.as_deref() == Some(actual.as_str()) {
+                continue
+            }
+            if self.bless {
+                fs::write(&path, &actual)
+                    .map_err(|error| format!("Could not write {}: {error}", path.display()))?;
+                continue
+            }
+            failures.push(match expected {
+                Some(expected) => format!("Golden file {} does not match:\n{}", path.display(), diff(&expected, &actual)),
+                None => format!("Golden file {} is missing", path.display())
+            });
+        }
+        match failures.is_empty() {
+            true => Ok(sources.len()),
+            false => Err(format!("{}\nRun with {BLESS_ENV}=1 to update the golden files", failures.join("\n\n")))
+        }
+    }
+
+    /// Render all the source files and panic if any of them does not match its golden file
+    pub fn run(&self, render: impl FnMut(&str, &str) -> String) {
+        if let Err(report) = self.check(render) {
+            panic!("{report}");
+        }
+    }
+}
+
+/// Dump the tokens of the code followed by the lexer errors
+///
+/// Every token is written in a separate line together with its row and column.
+pub fn dump_tokens(compiler: &Compiler) -> String {
+    let (tokens, errors) = compiler.tokenize_all();
+    let meta = DefaultMetadata::new(vec![], compiler.path.clone(), Some(compiler.code.clone()));
+    let mut result = token_lines(&compiler.code, &tokens);
+    for error in errors.iter() {
+        result.push('\n');
+        result.push_str(&lexer_message(&meta, error).render());
+    }
+    result
+}
+
+/// Dump the tokens of the code followed by the diagnostics of the compilation
+pub fn dump_compilation<M: Metadata>(compiler: &Compiler, module: &mut impl SyntaxModule<M>) -> String {
+    match compiler.compile(module) {
+        Ok(compilation) => {
+            // Metadata that does not expose its tokens is dumped with the tokens of the lexer
+            let tokens = match compilation.tokens() {
+                Some(tokens) => Cow::Borrowed(tokens),
+                None => Cow::Owned(compiler.tokenize_all().0)
+            };
+            let mut result = token_lines(&compiler.code, &tokens);
+            for message in compilation.diagnostics.iter() {
+                result.push('\n');
+                result.push_str(&message.render());
+            }
+            result
+        }
+        Err(failure) => {
+            let (tokens, _) = compiler.tokenize_all();
+            let failure = match failure {
+                Failure::Loud(message) => message.render(),
+                Failure::Quiet(info) => match info.position {
+                    Position::Pos(row, col) => format!("Quiet failure at {row}:{col}\n"),
+                    Position::EOF => "Quiet failure at the end of file\n".to_string()
+                }
+            };
+            format!("{}\n{failure}", token_lines(&compiler.code, &tokens))
+        }
+    }
+}
+
+// Write the tokens with their positions
+fn token_lines(code: &str, tokens: &[Token]) -> String {
+    let mut sources = SourceMap::new();
+    let file = sources.add(None, code);
+    let source = sources.get(file).unwrap();
+    tokens.iter()
+        .map(|token| {
+            let (row, col) = source.offset_position(token.span.start());
+            format!("{row}:{col} {:?}\n", token.word)
+        })
+        .collect()
+}
+
+// Show the lines that differ together with a few surrounding lines
+fn diff(expected: &str, actual: &str) -> String {
+    const CONTEXT: usize = 2;
+    let old = expected.lines().collect::<Vec<_>>();
+    let new = actual.lines().collect::<Vec<_>>();
+    // Length of the longest common subsequence of the remaining lines
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        }
+        // Removed lines are shown before the added ones
+        else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        }
+        else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+    let changes = lines.iter()
+        .enumerate()
+        .filter(|(_, (kind, _))| *kind != ' ')
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if changes.is_empty() {
+        return "  (files differ only in the trailing new line)".to_string()
+    }
+    let mut result = vec![];
+    let mut last = None;
+    for (index, (kind, line)) in lines.iter().enumerate() {
+        let is_near = changes.iter().any(|change| change.abs_diff(index) <= CONTEXT);
+        if !is_near {
+            continue
+        }
+        if last.is_some_and(|last| last + 1 < index) {
+            result.push("  ...".to_string());
+        }
+        result.push(format!("{kind} {line}"));
+        last = Some(index);
+    }
+    result.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn line_diff() {
+        let expected = "a\nb\nc\nd\ne\nf\ng\nh";
+        let actual = "a\nb\nc\nd\ne\nF\ng\nh\ni";
+        assert_eq!(diff(expected, actual), [
+            "  d",
+            "  e",
+            "- f",
+            "+ F",
+            "  g",
+            "  h",
+            "+ i"
+        ].join("\n"));
+        assert_eq!(diff("a\nb", "a\nb\n"), "  (files differ only in the trailing new line)");
+    }
+
+    #[test]
+    fn bless_and_check() {
+        let dir = std::env::temp_dir().join(format!("heraclitus-golden-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("first.txt"), "one").unwrap();
+        fs::write(dir.join("second.txt"), "two").unwrap();
+        fs::write(dir.join("ignored.md"), "three").unwrap();
+        let golden = Golden::new(&dir).extension("txt").bless(false);
+        let report = golden.check(|_, code| code.to_uppercase()).unwrap_err();
+        assert!(report.contains("first.txt.expected is missing"));
+        assert!(report.contains(BLESS_ENV));
+        assert_eq!(golden.clone().bless(true).check(|_, code| code.to_uppercase()), Ok(2));
+        assert_eq!(fs::read_to_string(dir.join("second.txt.expected")).unwrap(), "TWO");
+        assert_eq!(golden.check(|_, code| code.to_uppercase()), Ok(2));
+        let report = golden.check(|name, code| format!("{name}: {code}")).unwrap_err();
+        assert!(report.contains("- ONE\n+ first.txt: one"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
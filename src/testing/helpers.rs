@@ -0,0 +1,233 @@
+use crate::compiling::{lexer_message, syntax, Compiler, DefaultMetadata, Metadata, SourceMap, SyntaxModule, Token};
+use crate::compiling::failing::failure::Failure;
+use crate::compiling::failing::message::Message;
+use crate::compiling::failing::position_info::Position;
+use crate::compiling_rules::Rules;
+
+/// Macro for asserting that the syntax module parses and consumes exactly given amount of tokens
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # use heraclitus_compiler::testing::*;
+/// # struct Let;
+/// # impl SyntaxModule<DefaultMetadata> for Let {
+/// #     syntax_name!("Let");
+/// #     fn new() -> Self { Let }
+/// #     fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+/// #         token(meta, "let")?;
+/// #         variable(meta, vec![])?;
+/// #         Ok(())
+/// #     }
+/// # }
+/// let rules = Rules::new(vec![], vec![], reg![]);
+/// assert_parses!(&mut metadata(&rules, "let a"), Let, 2);
+/// ```
+#[macro_export]
+macro_rules! assert_parses {
+    ($meta:expr, $module:ty, $consumed:expr) => {
+        $crate::testing::assert_parses($meta, &mut <$module as $crate::compiling::SyntaxModule<_>>::new(), $consumed)
+    };
+}
+
+/// Macro for asserting that the syntax module fails loudly at given row and column
+///
+/// The message has to match the pattern in which `*` stands for any text.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # use heraclitus_compiler::testing::*;
+/// # struct Let;
+/// # impl SyntaxModule<DefaultMetadata> for Let {
+/// #     syntax_name!("Let");
+/// #     fn new() -> Self { Let }
+/// #     fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+/// #         token(meta, "let")?;
+/// #         let tok = meta.get_current_token();
+/// #         if variable(meta, vec![]).is_err() {
+/// #             return error!(meta, tok, "Expected a variable name")
+/// #         }
+/// #         Ok(())
+/// #     }
+/// # }
+/// let rules = Rules::new(vec!['='], vec![], reg![]);
+/// assert_loud_error!(&mut metadata(&rules, "let = 12"), Let, 1:5, "Expected *");
+/// ```
+#[macro_export]
+macro_rules! assert_loud_error {
+    ($meta:expr, $module:ty, $row:literal : $col:literal, $pattern:expr) => {
+        $crate::testing::assert_loud_error($meta, &mut <$module as $crate::compiling::SyntaxModule<_>>::new(), ($row, $col), $pattern)
+    };
+}
+
+/// Tokenize the code with given rules
+///
+/// Panics with the rendered lexer error if the code could not be tokenized.
+pub fn tokens(rules: &Rules, code: &str) -> Vec<Token> {
+    metadata_from::<DefaultMetadata>(&compiler(rules, code)).expr
+}
+
+/// Create metadata with the tokens of the code tokenized with given rules
+///
+/// Panics with the rendered lexer error if the code could not be tokenized.
+pub fn metadata<M: Metadata>(rules: &Rules, code: &str) -> M {
+    metadata_from(&compiler(rules, code))
+}
+
+/// Create metadata with the tokens of the code of the compiler
+///
+/// Use this one when the compiler has to be configured (for instance with the indentation).
+/// Panics with the rendered lexer error if the code could not be tokenized.
+pub fn metadata_from<M: Metadata>(compiler: &Compiler) -> M {
+    let mut sources = SourceMap::new();
+    let file = sources.add(compiler.path.clone(), compiler.code.clone());
+    let result = compiler.lexer.tokenize_source(sources.get(file).unwrap());
+    let tokens = result.as_ref().map_or(vec![], |tokens| tokens.clone());
+    let mut meta = M::new(tokens, compiler.path.clone(), Some(compiler.code.clone()));
+    meta.set_sources(sources, file);
+    if let Err(error) = result {
+        panic!("Could not tokenize the code:\n{}", lexer_message(&meta, &error).render());
+    }
+    meta
+}
+
+/// Parse the syntax module and return the number of consumed tokens
+pub fn parse<M: Metadata>(meta: &mut M, module: &mut impl SyntaxModule<M>) -> Result<usize, Failure> {
+    let index = meta.get_index();
+    syntax(meta, module)?;
+    Ok(meta.get_index() - index)
+}
+
+/// Assert that the syntax module parses and consumes exactly given amount of tokens
+#[track_caller]
+pub fn assert_parses<M: Metadata>(meta: &mut M, module: &mut impl SyntaxModule<M>, consumed: usize) {
+    match parse(meta, module) {
+        Ok(count) => assert_eq!(count, consumed, "Syntax module consumed {count} tokens instead of {consumed}"),
+        Err(Failure::Quiet(info)) => panic!("Syntax module failed quietly at {}", show_position(&info.position)),
+        Err(Failure::Loud(message)) => panic!("Syntax module failed loudly:\n{}", message.render())
+    }
+}
+
+/// Assert that the syntax module fails loudly at given row and column
+///
+/// The message has to match the pattern in which `*` stands for any text.
+/// Returns the message so that it can be checked further.
+#[track_caller]
+pub fn assert_loud_error<M: Metadata>(meta: &mut M, module: &mut impl SyntaxModule<M>, (row, col): (usize, usize), pattern: &str) -> Message {
+    let message = match parse(meta, module) {
+        Ok(count) => panic!("Syntax module parsed {count} tokens instead of failing loudly"),
+        Err(Failure::Quiet(info)) => panic!("Syntax module failed quietly at {} instead of failing loudly", show_position(&info.position)),
+        Err(Failure::Loud(message)) => message
+    };
+    let position = message.trace.first().map(|pos| pos.position.clone());
+    if position != Some(Position::Pos(row, col)) {
+        panic!("Expected the error at {row}:{col} but it was reported at {}:\n{}", position.as_ref().map_or("unknown location".to_string(), show_position), message.render());
+    }
+    let text = message.message.clone().unwrap_or_default();
+    if !matches_pattern(&text, pattern) {
+        panic!("Error message {text:?} does not match the pattern {pattern:?}");
+    }
+    message
+}
+
+// Create compiler with the default settings
+fn compiler(rules: &Rules, code: &str) -> Compiler {
+    Compiler::builder("Test", rules.clone())
+        .source(code)
+        .build()
+        .expect("Compiler with the source code cannot fail to build")
+}
+
+fn show_position(position: &Position) -> String {
+    match position {
+        Position::Pos(row, col) => format!("{row}:{col}"),
+        Position::EOF => "the end of file".to_string()
+    }
+}
+
+// Check if the text matches the pattern where '*' matches any text
+fn matches_pattern(text: &str, pattern: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty()
+    };
+    for part in middle.iter() {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    struct Let;
+
+    impl SyntaxModule<DefaultMetadata> for Let {
+        syntax_name!("Let");
+
+        fn new() -> Self {
+            Let
+        }
+
+        fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+            token(meta, "let")?;
+            let tok = meta.get_current_token();
+            if variable(meta, vec!['_']).is_err() {
+                return error!(meta, tok, "Expected a variable name", "Names consist of letters")
+            }
+            token(meta, "=")?;
+            Ok(())
+        }
+    }
+
+    fn rules() -> Rules {
+        Rules::new(vec!['='], vec![], reg![])
+    }
+
+    #[test]
+    fn pattern() {
+        assert!(matches_pattern("Expected a name", "Expected a name"));
+        assert!(matches_pattern("Expected a name", "Expected *"));
+        assert!(matches_pattern("Expected a name", "*a*name"));
+        assert!(matches_pattern("aa", "a*a"));
+        assert!(!matches_pattern("a", "a*a"));
+        assert!(!matches_pattern("Expected a name", "*value*"));
+        assert!(!matches_pattern("Expected a name", "Expected"));
+    }
+
+    #[test]
+    fn parses() {
+        let tokens = tokens(&rules(), "let a = 12");
+        assert_eq!(tokens.iter().map(|token| token.word.as_str()).collect::<Vec<_>>(), vec!["let", "a", "=", "12"]);
+        assert_parses!(&mut metadata(&rules(), "let a = 12"), Let, 3);
+        assert!(parse(&mut metadata::<DefaultMetadata>(&rules(), "var a"), &mut Let).unwrap_err().is_quiet());
+    }
+
+    #[test]
+    fn loud_error() {
+        let message = assert_loud_error!(&mut metadata(&rules(), "\nlet = 12"), Let, 2:5, "Expected * name");
+        assert_eq!(message.comment.as_deref(), Some("Names consist of letters"));
+    }
+
+    #[test]
+    #[should_panic(expected = "consumed 3 tokens instead of 4")]
+    fn wrong_consumption() {
+        assert_parses!(&mut metadata(&rules(), "let a = 12"), Let, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected the error at 1:1")]
+    fn wrong_position() {
+        assert_loud_error!(&mut metadata(&rules(), "let = 12"), Let, 1:1, "*");
+    }
+}
@@ -0,0 +1,11 @@
+//! Support for testing languages built with heraclitus
+//!
+//! This module contains a golden file test runner that compares the tokens
+//! and the rendered diagnostics with the snapshots stored next to the sources,
+//! as well as helpers that build the tokens from code and assert the results of parsing.
+
+mod golden;
+mod helpers;
+
+pub use golden::*;
+pub use helpers::*;
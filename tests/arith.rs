@@ -15,9 +15,52 @@ fn arith() {
         })
     ];
     let rules = Rules::new(symbols, vec![], region);
-    let mut compiler = Compiler::new("Arith", rules);
-    compiler.load("// test\n12.24 +.123 + 12 + 321");
+    let compiler = Compiler::builder("Arith", rules)
+        .source("// test\n12.24 +.123 + 12 + 321")
+        .debug()
+        .build()
+        .unwrap();
     let mut expr = arith_modules::Expr::new();
-    compiler.debug();
     assert!(compiler.compile(&mut expr).is_ok());
-}
\ No newline at end of file
+}
+
+#[test]
+fn arith_trace() {
+    let symbols = vec!['+', '/'];
+    let rules = Rules::new(symbols, vec![], reg![]);
+    // Unique path so that concurrent test runs do not overwrite each other's trace
+    let path = std::env::temp_dir().join(format!("heraclitus_arith_trace_{}.txt", std::process::id()));
+    let compiler = Compiler::builder("Arith", rules)
+        .source("12 + 24")
+        .trace(path.to_string_lossy(), TraceFormat::Tree)
+        .build()
+        .unwrap();
+    let mut expr = arith_modules::Expr::new();
+    let Compilation::<DefaultMetadata> { mut meta, .. } = compiler.compile(&mut expr).unwrap();
+    let events = meta.get_tracer().unwrap().events().to_vec();
+    assert_eq!(events[0].name, "Expr");
+    assert_eq!((events[0].start, events[0].end), (0, 3));
+    let tree = std::fs::read_to_string(&path).unwrap();
+    assert!(tree.starts_with("Expr [0..3] ok"));
+    assert!(tree.contains("\n  Add [0..3] ok"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn arith_profile() {
+    let symbols = vec!['+', '/'];
+    let rules = Rules::new(symbols, vec![], reg![]);
+    let compiler = Compiler::builder("Arith", rules)
+        .source("12 + 24 + 36")
+        .profile()
+        .build()
+        .unwrap();
+    let mut expr = arith_modules::Expr::new();
+    let compilation: Compilation<DefaultMetadata> = compiler.compile(&mut expr).unwrap();
+    assert_eq!(compilation.tokens().unwrap().len(), 5);
+    let profile = compilation.profile.unwrap();
+    let add = profile.entries().iter().find(|entry| entry.name == "Add").unwrap();
+    assert_eq!((add.calls, add.successes, add.quiet_failures), (3, 2, 1));
+    // The last addition consumes a number before it fails on missing `+`
+    assert_eq!(add.tokens_backtracked, 1);
+}
@@ -12,14 +12,16 @@ fn cobra() {
         })
     ];
     let rules = Rules::new(symbols, compounds, region);
-    let mut compiler = Compiler::new("Cobra", rules);
-    compiler.use_indents();
-    compiler.load(vec![
-        "if 'condition\\\\':",
-        "  'do + this'",
-        "  'do ++ that'"
-    ].join("\n"));
+    let compiler = Compiler::builder("Cobra", rules)
+        .indent()
+        .source([
+            "if 'condition\\\\':",
+            "  'do + this'",
+            "  'do ++ that'"
+        ].join("\n"))
+        .debug()
+        .build()
+        .unwrap();
     let mut ast = cobra_modules::IfStatement::new();
-    compiler.debug();
     assert!(compiler.compile(&mut ast).is_ok());
 }
@@ -0,0 +1,52 @@
+use heraclitus_compiler::prelude::*;
+
+#[test]
+fn definition_file() {
+    let definition = LanguageDefinition::from_file("tests/definitions/herb.toml").unwrap();
+    assert_eq!(definition.name, "HerbScript");
+    assert!(definition.is_keyword("print"));
+    let cc = definition.builder()
+        .source("let a = 'x {1 + 2}' # comment\nprint a")
+        .build()
+        .unwrap();
+    let words = cc.tokenize().unwrap().into_iter().map(|token| token.word.to_string()).collect::<Vec<_>>();
+    assert_eq!(words, vec!["let", "a", "=", "'x ", "{", "1", "+", "2", "}", "'", "# comment\n", "\n", "print", "a"]);
+}
+
+#[test]
+fn definition_unclosed_message() {
+    let definition = LanguageDefinition::from_file("tests/definitions/herb.toml").unwrap();
+    let cc = definition.builder().source("let a = 'x").build().unwrap();
+    let Err((LexerErrorType::Unclosed(region), _)) = cc.tokenize() else {
+        panic!("Expected the string to be unclosed")
+    };
+    assert_eq!(region.message.as_deref(), Some("String is never closed"));
+}
+
+#[test]
+fn definition_error() {
+    let error = LanguageDefinition::from_file("tests/definitions/broken.toml").unwrap_err();
+    assert_eq!(error.to_string(), "tests/definitions/broken.toml:9:7: Region 'interpolation' is not defined");
+    let Failure::Loud(message) = Failure::from(error) else {
+        panic!("Expected a loud failure")
+    };
+    assert_eq!(message.trace[0].position, Position::Pos(9, 7));
+    assert!(message.code.is_some());
+    // JSON documents do not keep the locations of the values
+    let error = LanguageDefinition::from_file("tests/definitions/broken.json").unwrap_err();
+    assert_eq!(error.to_string(), "tests/definitions/broken.json: Region 'interpolation' is not defined");
+}
+
+#[test]
+fn definition_highlighting() {
+    let definition = LanguageDefinition::from_file("tests/definitions/herb.toml").unwrap();
+    let grammar = definition.highlighting().file_types(vec!["herb"]);
+    let textmate = grammar.to_textmate();
+    assert!(textmate.contains(r#""scopeName": "source.herbscript""#));
+    assert!(textmate.contains(r#""fileTypes": [
+    "herb"
+  ]"#));
+    assert!(textmate.contains("print"));
+    let sublime = grammar.to_sublime();
+    assert!(sublime.contains("scope: 'source.herbscript'"));
+}
@@ -0,0 +1,122 @@
+use heraclitus_compiler::prelude::*;
+use heraclitus_derive::{Metadata, SyntaxModule};
+
+#[derive(Metadata)]
+struct LangMetadata {
+    index: usize,
+    expr: Vec<Token>,
+    path: Option<String>,
+    code: Option<String>,
+    indent: Option<usize>,
+    sources: SourceMap,
+    file: Option<FileId>,
+    #[metadata(scopes, state)]
+    variables: Scopes<usize>,
+    #[metadata(state)]
+    declared: usize,
+    semantic: SemanticTokens
+}
+
+// Assignment `name = value` that defines the variable
+#[derive(SyntaxModule)]
+#[syntax(metadata = LangMetadata)]
+struct Assignment {
+    name: String,
+    value: usize
+}
+
+impl Assignment {
+    fn parse_syntax(&mut self, meta: &mut LangMetadata) -> SyntaxResult {
+        let tok = meta.get_current_token();
+        let index = meta.get_index();
+        self.name = variable(meta, vec![])?;
+        meta.tag_token(index, "variable", &["declaration"]);
+        let position = PositionInfo::from_token(meta, tok);
+        meta.declared += 1;
+        token(meta, "=")?;
+        meta.tag_token(index + 2, "number", &[]);
+        self.value = integer(meta, vec![])?.parse().unwrap();
+        define(meta, &self.name, self.value, position)
+    }
+}
+
+#[derive(SyntaxModule)]
+#[syntax(name = "Program", metadata = LangMetadata, parse = parse_program)]
+struct Block(Vec<Assignment>);
+
+impl Block {
+    fn parse_program(&mut self, meta: &mut LangMetadata) -> SyntaxResult {
+        while meta.get_current_token().is_some() {
+            let mut assignment = Assignment::new();
+            // Failed assignment does not leave its declarations behind
+            match syntax(meta, &mut assignment) {
+                Ok(()) => self.0.push(assignment),
+                Err(Failure::Quiet(_)) => token(meta, ";").map(|_| ())?,
+                Err(failure) => return Err(failure)
+            }
+        }
+        Ok(())
+    }
+}
+
+fn compile(code: &str) -> Result<(Block, LangMetadata), Failure> {
+    let rules = Rules::new(vec!['=', ';'], vec![], reg![]);
+    let compiler = Compiler::builder("Derive", rules)
+        .source(code)
+        .build()
+        .unwrap();
+    let tokens = compiler.tokenize().unwrap();
+    let mut meta = LangMetadata::new(tokens, None, Some(code.to_string()));
+    let mut block = Block::new();
+    block.parse(&mut meta)?;
+    Ok((block, meta))
+}
+
+#[test]
+fn derived_names() {
+    assert_eq!(<Assignment as SyntaxModule<LangMetadata>>::name(), "Assignment");
+    assert_eq!(<Block as SyntaxModule<LangMetadata>>::name(), "Program");
+}
+
+#[test]
+fn derived_metadata() {
+    let (block, meta) = compile("a = 1 ; b = 2").unwrap();
+    let names = block.0.iter().map(|assignment| assignment.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["a", "b"]);
+    assert_eq!(meta.get_scopes().lookup("b").unwrap().data, 2);
+    assert_eq!(meta.get_code().unwrap(), "a = 1 ; b = 2");
+    assert!(meta.get_source().is_some());
+    // The failed attempt to parse `;` as an assignment was rolled back
+    assert_eq!(meta.declared, 2);
+    assert_eq!(meta.encode_semantic_tokens(), vec![
+        0, 0, 1, 8, 1,
+        0, 4, 1, 19, 0,
+        0, 4, 1, 8, 1,
+        0, 4, 1, 19, 0
+    ]);
+}
+
+#[test]
+fn derived_positions() {
+    let Err(Failure::Loud(message)) = compile("a = 1\na = 2") else {
+        panic!("Expected redefinition to fail loudly")
+    };
+    let positions = message.trace.iter().map(|pos| pos.position.clone()).collect::<Vec<_>>();
+    assert_eq!(positions, vec![Position::Pos(2, 1)]);
+    assert_eq!(message.notes[0].position.position, Position::Pos(1, 1));
+}
+
+#[derive(Metadata)]
+struct SourceOnlyMetadata {
+    index: usize,
+    expr: Vec<Token>,
+    indent: Option<usize>,
+    sources: SourceMap,
+    file: Option<FileId>
+}
+
+#[test]
+fn derived_code_from_sources() {
+    let meta = SourceOnlyMetadata::new(vec![], None, Some("a = 1".to_string()));
+    assert_eq!(meta.get_code().unwrap(), "a = 1");
+}
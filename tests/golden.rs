@@ -0,0 +1,25 @@
+use heraclitus_compiler::prelude::*;
+use heraclitus_compiler::testing::*;
+mod cobra_modules;
+
+fn compiler(name: &str, code: &str) -> Compiler {
+    let rules = Rules::new(vec!['+'], vec![('+', '+')], reg![
+        reg!(string as "string literal" => {
+            begin: "'",
+            end: "'"
+        })
+    ]);
+    Compiler::builder("Cobra", rules)
+        .indent()
+        .path(name)
+        .source(code)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn golden_compilation() {
+    Golden::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+        .extension("cb")
+        .run(|name, code| dump_compilation(&compiler(name, code), &mut cobra_modules::IfStatement::new()));
+}
@@ -0,0 +1,99 @@
+use heraclitus_compiler::prelude::*;
+use heraclitus_derive::SyntaxModule;
+
+#[derive(Debug, PartialEq, SyntaxModule)]
+#[syntax(name = "Expression")]
+enum Expr {
+    Number(#[grammar(integer)] String),
+    // Has to be tried before the call as `true` is a valid name
+    #[grammar("true")]
+    True,
+    #[grammar(name, "(", args, ")")]
+    Call {
+        #[grammar(variable, extend = "_")]
+        name: String,
+        #[grammar(separator = ",")]
+        args: Vec<Expr>
+    },
+    #[grammar("(", 0, ")")]
+    Group(Box<Expr>)
+}
+
+#[derive(Debug, PartialEq, SyntaxModule)]
+#[grammar(public, "let", name, "=", value)]
+struct Let {
+    #[grammar(token("pub"))]
+    public: bool,
+    #[grammar(variable, extend = "_")]
+    name: String,
+    value: Expr,
+    // Not a part of the grammar
+    uses: usize
+}
+
+#[derive(Debug, PartialEq, SyntaxModule)]
+enum Statement {
+    Let(Let),
+    #[grammar("print", 0)]
+    Print(Expr)
+}
+
+#[derive(Debug, SyntaxModule)]
+#[syntax(name = "Program")]
+struct Program {
+    #[grammar]
+    statements: Vec<Statement>
+}
+
+fn compile(code: &str) -> Result<Vec<Statement>, Failure> {
+    let rules = Rules::new(vec!['(', ')', ',', '='], vec![], reg![]);
+    let compiler = Compiler::builder("Grammar", rules)
+        .source(code)
+        .build()
+        .unwrap();
+    let mut program = Program::new();
+    compiler.compile(&mut program)?;
+    Ok(program.statements)
+}
+
+fn expected(code: &str) -> (String, Position) {
+    let Err(Failure::Loud(message)) = compile(code) else {
+        panic!("Expected '{code}' to fail loudly")
+    };
+    (message.message.unwrap(), message.trace[0].position.clone())
+}
+
+#[test]
+fn grammar_sequence() {
+    let statements = compile("pub let answer = sum(1, (2), true) print 3").unwrap();
+    assert_eq!(statements, vec![
+        Statement::Let(Let {
+            public: true,
+            name: "answer".to_string(),
+            value: Expr::Call {
+                name: "sum".to_string(),
+                args: vec![
+                    Expr::Number("1".to_string()),
+                    Expr::Group(Box::new(Expr::Number("2".to_string()))),
+                    Expr::True
+                ]
+            },
+            uses: 0
+        }),
+        Statement::Print(Expr::Number("3".to_string()))
+    ]);
+}
+
+#[test]
+fn grammar_new() {
+    assert_eq!(Expr::new(), Expr::Number(String::new()));
+    assert_eq!(<Statement as SyntaxModule<DefaultMetadata>>::name(), "Statement");
+}
+
+#[test]
+fn grammar_errors() {
+    assert_eq!(expected("let = 1"), ("Expected variable".to_string(), Position::Pos(1, 5)));
+    assert_eq!(expected("let a = "), ("Expected Expression".to_string(), Position::Pos(1, 9)));
+    assert_eq!(expected("print sum(1, 2"), ("Expected ')'".to_string(), Position::Pos(1, 15)));
+    assert_eq!(expected("print (1,"), ("Expected ')'".to_string(), Position::Pos(1, 9)));
+}
@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+use heraclitus_compiler::prelude::*;
+
+#[derive(Debug)]
+struct Program {
+    words: Vec<String>,
+    imports: Vec<Program>,
+    cached: usize
+}
+
+impl SyntaxModule<DefaultMetadata> for Program {
+    syntax_name!("Program");
+
+    fn new() -> Self {
+        Program {
+            words: vec![],
+            imports: vec![],
+            cached: 0
+        }
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        while meta.get_current_token().is_some() {
+            if token(meta, "import").is_ok() {
+                let path = variable(meta, vec!['.'])?;
+                let mut program = Program::new();
+                match import(meta, path, &mut program)? {
+                    Import::Parsed(_) => self.imports.push(program),
+                    Import::Cached(_) => self.cached += 1
+                }
+            } else {
+                self.words.push(variable(meta, vec![])?);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn setup(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("heraclitus_imports_{name}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, code) in files {
+        std::fs::write(dir.join(file), code).unwrap();
+    }
+    dir
+}
+
+fn compile(dir: &Path, file: &str) -> (Result<DefaultMetadata, Failure>, Program) {
+    let path = dir.join(file).to_string_lossy().to_string();
+    let compiler = Compiler::builder("Imports", Rules::new(vec![], vec![], reg![]))
+        .source_file(path)
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut program = Program::new();
+    let result = compiler.compile(&mut program).map(|compilation| compilation.meta);
+    (result, program)
+}
+
+fn read_code(dir: &Path, file: &str) -> String {
+    std::fs::read_to_string(dir.join(file)).unwrap()
+}
+
+#[test]
+fn import_chain() {
+    let dir = setup("chain", &[
+        ("main.ab", "import a.ab\nimport b.ab\nmain"),
+        ("a.ab", "import b.ab\napple"),
+        ("b.ab", "banana")
+    ]);
+    let (meta, program) = compile(&dir, "main.ab");
+    let meta = meta.unwrap();
+    assert_eq!(program.words, vec!["main"]);
+    assert_eq!(program.imports.len(), 1);
+    assert_eq!(program.imports[0].words, vec!["apple"]);
+    assert_eq!(program.imports[0].imports[0].words, vec!["banana"]);
+    // Module `b.ab` was already parsed while importing `a.ab`
+    assert_eq!(program.cached, 1);
+    assert_eq!(meta.get_sources().unwrap().files().len(), 3);
+}
+
+#[test]
+fn import_cycle() {
+    let dir = setup("cycle", &[
+        ("main.ab", "import a.ab\nmain"),
+        ("a.ab", "import main.ab\napple")
+    ]);
+    let (meta, _) = compile(&dir, "main.ab");
+    let Err(Failure::Loud(message)) = meta else {
+        panic!("Expected import cycle to fail loudly")
+    };
+    assert_eq!(message.message.unwrap(), "Import cycle detected while importing 'main.ab'");
+    assert!(message.comment.unwrap().ends_with("main.ab -> a.ab -> main.ab"));
+    // Trace points at the import in `main.ab` and the error at the import in `a.ab`
+    assert_eq!(message.trace.len(), 2);
+    assert_eq!(message.trace[0].get_pos_by_code(read_code(&dir, "main.ab")), (1, 8));
+}
+
+#[test]
+fn import_missing() {
+    let dir = setup("missing", &[("main.ab", "import nothing.ab")]);
+    let (meta, _) = compile(&dir, "main.ab");
+    let Err(Failure::Loud(message)) = meta else {
+        panic!("Expected missing import to fail loudly")
+    };
+    assert_eq!(message.message.unwrap(), "Could not import module 'nothing.ab'");
+}
+
+#[test]
+fn import_traced() {
+    let dir = setup("traced", &[
+        ("main.ab", "import a.ab\nmain"),
+        ("a.ab", "apple")
+    ]);
+    let path = dir.join("main.ab").to_string_lossy().to_string();
+    let compiler = Compiler::builder("Imports", Rules::new(vec![], vec![], reg![]))
+        .source_file(path)
+        .unwrap()
+        .profile()
+        .build()
+        .unwrap();
+    let compilation = compiler.compile(&mut Program::new()).unwrap();
+    let profile = compilation.profile.unwrap();
+    // Both the main and the imported module were recorded
+    assert_eq!(profile.entries().iter().find(|entry| entry.name == "Program").unwrap().calls, 2);
+}
+
+// Module that takes the loader away from the metadata
+struct Thief;
+
+impl SyntaxModule<DefaultMetadata> for Thief {
+    syntax_name!("Thief");
+
+    fn new() -> Self {
+        Thief
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        meta.take_loader();
+        Ok(())
+    }
+}
+
+struct Importer;
+
+impl SyntaxModule<DefaultMetadata> for Importer {
+    syntax_name!("Importer");
+
+    fn new() -> Self {
+        Importer
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        token(meta, "import")?;
+        let path = variable(meta, vec!['.'])?;
+        import(meta, path, &mut Thief).map(|_| ())
+    }
+}
+
+#[test]
+fn import_loader_lost() {
+    let dir = setup("lost", &[("main.ab", "import a.ab"), ("a.ab", "apple")]);
+    let path = dir.join("main.ab").to_string_lossy().to_string();
+    let compiler = Compiler::builder("Imports", Rules::new(vec![], vec![], reg![]))
+        .source_file(path)
+        .unwrap()
+        .build()
+        .unwrap();
+    let Err(Failure::Loud(message)) = compiler.compile(&mut Importer) else {
+        panic!("Expected lost loader to fail loudly")
+    };
+    assert_eq!(message.message.unwrap(), "Module 'a.ab' took the module loader and did not put it back");
+}
+
+// Module that reports every failed import and carries on
+struct Retry {
+    failures: Vec<String>
+}
+
+impl SyntaxModule<DefaultMetadata> for Retry {
+    syntax_name!("Retry");
+
+    fn new() -> Self {
+        Retry { failures: vec![] }
+    }
+
+    fn parse(&mut self, meta: &mut DefaultMetadata) -> SyntaxResult {
+        while meta.get_current_token().is_some() {
+            token(meta, "import")?;
+            let path = variable(meta, vec!['.'])?;
+            match import(meta, path, &mut Program::new()) {
+                Err(Failure::Loud(message)) => self.failures.push(message.message.unwrap()),
+                result => result.map(|_| ())?
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn import_broken_twice() {
+    let dir = setup("broken", &[
+        ("main.ab", "import broken.ab\nimport broken.ab"),
+        ("broken.ab", "apple 12")
+    ]);
+    let path = dir.join("main.ab").to_string_lossy().to_string();
+    let compiler = Compiler::builder("Imports", Rules::new(vec![], vec![], reg![]))
+        .source_file(path)
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut retry = Retry::new();
+    compiler.compile(&mut retry).unwrap();
+    // Failed module is not cached so the second import fails as well
+    assert_eq!(retry.failures, vec!["Could not parse module 'broken.ab'"; 2]);
+}
@@ -0,0 +1,123 @@
+use std::any::Any;
+use heraclitus_compiler::prelude::*;
+
+struct ScopedMeta {
+    index: usize,
+    expr: Vec<Token>,
+    path: Option<String>,
+    code: Option<String>,
+    scopes: Scopes<usize>
+}
+
+impl Metadata for ScopedMeta {
+    fn new(expr: Vec<Token>, path: Option<String>, code: Option<String>) -> Self {
+        ScopedMeta { index: 0, expr, path, code, scopes: Scopes::new() }
+    }
+    fn get_token_at(&self, index: usize) -> Option<Token> { self.expr.get(index).cloned() }
+    fn get_index(&self) -> usize { self.index }
+    fn set_index(&mut self, index: usize) { self.index = index }
+    fn get_debug(&mut self) -> Option<usize> { None }
+    fn set_debug(&mut self, _indent: usize) {}
+    fn get_path(&self) -> Option<String> { self.path.clone() }
+    fn get_code(&self) -> Option<&String> { self.code.as_ref() }
+    fn snapshot_state(&mut self) -> Option<Box<dyn Any>> { Some(Box::new(self.scopes.checkpoint())) }
+    fn restore_state(&mut self, state: Box<dyn Any>) {
+        if let Ok(checkpoint) = state.downcast::<ScopesCheckpoint>() {
+            self.scopes.rollback(*checkpoint);
+        }
+    }
+    fn commit_state(&mut self, state: Box<dyn Any>) {
+        if let Ok(checkpoint) = state.downcast::<ScopesCheckpoint>() {
+            self.scopes.commit(*checkpoint);
+        }
+    }
+}
+
+impl ScopedMetadata<usize> for ScopedMeta {
+    fn get_scopes(&self) -> &Scopes<usize> { &self.scopes }
+    fn get_scopes_mut(&mut self) -> &mut Scopes<usize> { &mut self.scopes }
+}
+
+// Block of statements: `let name = value`, `name` and `{ ... }`
+struct Block {
+    values: Vec<usize>
+}
+
+impl SyntaxModule<ScopedMeta> for Block {
+    syntax_name!("Block");
+
+    fn new() -> Self {
+        Block { values: vec![] }
+    }
+
+    fn parse(&mut self, meta: &mut ScopedMeta) -> SyntaxResult {
+        while let Some(tok) = meta.get_current_token() {
+            if token(meta, "}").is_ok() {
+                meta.offset_index(-1);
+                break;
+            }
+            if token(meta, "{").is_ok() {
+                meta.get_scopes_mut().push_scope();
+                let mut block = Block::new();
+                syntax(meta, &mut block)?;
+                token(meta, "}")?;
+                meta.get_scopes_mut().pop_scope();
+                self.values.extend(block.values);
+            }
+            else if token(meta, "let").is_ok() {
+                let tok = meta.get_current_token();
+                let name = variable(meta, vec![])?;
+                let position = PositionInfo::from_token(meta, tok);
+                token(meta, "=")?;
+                let value = integer(meta, vec![])?.parse().unwrap();
+                define(meta, name, value, position)?;
+            }
+            else {
+                let name = variable(meta, vec![])?;
+                let position = PositionInfo::from_token(meta, Some(tok));
+                let symbol = resolve(meta, name, position)?;
+                self.values.push(symbol.data);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn compile(code: &str) -> Result<Vec<usize>, Failure> {
+    let rules = Rules::new(vec!['{', '}', '='], vec![], reg![]);
+    let compiler = Compiler::builder("Scopes", rules)
+        .source(code)
+        .build()
+        .unwrap();
+    let mut block = Block::new();
+    compiler.compile(&mut block)?;
+    Ok(block.values)
+}
+
+#[test]
+fn scoped_symbols() {
+    let values = compile("let a = 1 a { let a = 2 a } a").unwrap();
+    assert_eq!(values, vec![1, 2, 1]);
+}
+
+#[test]
+fn redefined_symbol() {
+    let Err(Failure::Loud(message)) = compile("let a = 1\nlet a = 2") else {
+        panic!("Expected redefinition to fail loudly")
+    };
+    assert_eq!(message.message.unwrap(), "'a' is already defined in this scope");
+    // Error points at the new definition and the note at the previous one
+    let positions = message.trace.iter().map(|pos| pos.position.clone()).collect::<Vec<_>>();
+    assert_eq!(positions, vec![Position::Pos(2, 5)]);
+    assert_eq!(message.notes[0].label, "'a' was first defined here");
+    assert_eq!(message.notes[0].position.position, Position::Pos(1, 5));
+}
+
+#[test]
+fn undefined_symbol() {
+    let Err(Failure::Loud(message)) = compile("{ let a = 1 } a") else {
+        panic!("Expected undefined symbol to fail loudly")
+    };
+    assert_eq!(message.message.unwrap(), "'a' is not defined");
+    assert_eq!(message.trace[0].position, Position::Pos(1, 15));
+}